@@ -0,0 +1,106 @@
+//! Read-path benchmark over a synthetic 10k-prompt library
+//!
+//! Run with `cargo bench -p jfp --bench read_path`. Fails if the median
+//! `list_prompts` exceeds 250ms.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use tempfile::tempdir;
+
+// jfp only builds a binary, so the modules behind the read path are
+// compiled into the benchmark directly
+#[allow(dead_code, unused_imports)]
+#[path = "../src/config/mod.rs"]
+mod config;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/lock.rs"]
+mod lock;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/storage/mod.rs"]
+mod storage;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/types/mod.rs"]
+mod types;
+
+use storage::Database;
+use types::{Prompt, PromptVariable, VariableType};
+
+const PROMPTS: usize = 10_000;
+const LIST_TARGET: Duration = Duration::from_millis(250);
+
+fn synthetic_prompts(count: usize) -> Vec<Prompt> {
+    (0..count)
+        .map(|i| {
+            let mut prompt = Prompt::new(
+                format!("prompt-{:05}", i),
+                format!("Synthetic prompt {}", i),
+                format!("Review {{{{CODE}}}} for issue {} in {{{{LANGUAGE}}}}", i),
+            );
+            prompt.category = Some(format!("category-{}", i % 12));
+            prompt.tags = vec![
+                format!("tag-{}", i % 50),
+                format!("tag-{}", (i / 7) % 50),
+                "synthetic".to_string(),
+            ];
+            prompt.variables = ["CODE", "LANGUAGE"]
+                .into_iter()
+                .map(|name| PromptVariable {
+                    name: name.to_string(),
+                    var_type: VariableType::Multiline,
+                    required: true,
+                    description: None,
+                    default: None,
+                })
+                .collect();
+            prompt
+        })
+        .collect()
+}
+
+fn read_path(c: &mut Criterion) {
+    let dir = tempdir().expect("temp dir");
+    let mut db = Database::open_at(&dir.path().join("bench.db")).expect("open bench DB");
+    db.bulk_upsert_prompts(&synthetic_prompts(PROMPTS)).expect("seed bench DB");
+    assert_eq!(db.list_prompts().expect("list").len(), PROMPTS);
+
+    let mut group = c.benchmark_group("read_path_10k");
+    group.sample_size(10);
+
+    // Per-iteration times of each sample, to hold the median to the target
+    let mut list_samples = Vec::new();
+    group.bench_function("list_prompts", |b| {
+        b.iter_custom(|iters| {
+            let started = Instant::now();
+            for _ in 0..iters {
+                black_box(db.list_prompts().expect("list"));
+            }
+            let elapsed = started.elapsed();
+            list_samples.push(elapsed / iters as u32);
+            elapsed
+        })
+    });
+    group.bench_function("list_prompts_filtered_tag", |b| {
+        b.iter(|| black_box(db.list_prompts_filtered(None, Some("tag-7"), false).expect("filter")))
+    });
+    group.bench_function("search_limit_50", |b| {
+        b.iter(|| black_box(db.search("review", 50).expect("search")))
+    });
+    group.bench_function("get_prompt", |b| {
+        b.iter(|| black_box(db.get_prompt("prompt-05000").expect("get")))
+    });
+    group.finish();
+
+    list_samples.sort();
+    let median = list_samples[list_samples.len() / 2];
+    assert!(
+        median <= LIST_TARGET,
+        "list_prompts took {:?} (target {:?})",
+        median,
+        LIST_TARGET
+    );
+}
+
+criterion_group!(benches, read_path);
+criterion_main!(benches);
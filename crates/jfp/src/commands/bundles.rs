@@ -3,36 +3,37 @@
 //! From EXISTING_JFP_STRUCTURE.md sections for bundles/bundle:
 //! - bundles: List available bundles
 //! - bundle: Show bundle details
+//! - Bundles come from the DB (registry payload, else the bundled set),
+//!   listed and expanded in `position` order
+//! - bundle create/add/remove/delete: user-defined local bundles, which
+//!   registry syncs never overwrite; members must be known prompts
 
-use std::collections::HashMap;
 use std::process::ExitCode;
 
+use anyhow::Result;
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
+use super::fail;
+use crate::lock::LockGuard;
+use crate::registry::{open_library, Library, RegistryStatus};
+use crate::storage::Database;
+use crate::types::{Bundle, BundleSummary, Config};
 
 #[derive(Serialize)]
 struct BundlesOutput {
     bundles: Vec<BundleSummary>,
     count: usize,
-}
-
-#[derive(Serialize)]
-struct BundleSummary {
-    id: String,
-    title: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    description: Option<String>,
-    prompt_count: usize,
+    #[serde(flatten)]
+    registry: RegistryStatus,
 }
 
 #[derive(Serialize)]
 struct BundleOutput {
-    id: String,
-    title: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    description: Option<String>,
+    #[serde(flatten)]
+    bundle: BundleSummary,
     prompts: Vec<BundlePrompt>,
+    #[serde(flatten)]
+    registry: RegistryStatus,
 }
 
 #[derive(Serialize)]
@@ -41,94 +42,71 @@ struct BundlePrompt {
     title: String,
 }
 
-struct BundleDefinition {
-    id: &'static str,
-    title: &'static str,
-    description: &'static str,
-    prompt_ids: &'static [&'static str],
-}
-
-const BUNDLE_DEFINITIONS: &[BundleDefinition] = &[
-    BundleDefinition {
-        id: "getting-started",
-        title: "Getting Started",
-        description: "Essential prompts for new users",
-        prompt_ids: &["code-review", "debug", "explain-code"],
-    },
-    BundleDefinition {
-        id: "quality-essentials",
-        title: "Quality Essentials",
-        description: "Core prompts for code quality and refactoring",
-        prompt_ids: &["write-tests", "refactor", "optimize"],
-    },
-    BundleDefinition {
-        id: "docs-and-design",
-        title: "Docs & Design",
-        description: "Prompts for documentation and API design",
-        prompt_ids: &["documentation", "api-design", "explain-code"],
-    },
-];
-
-fn prompt_title_map() -> HashMap<String, String> {
-    bundled_prompts()
-        .into_iter()
-        .map(|prompt| (prompt.id, prompt.title))
-        .collect()
-}
-
-fn build_bundle_summary(
-    bundle: &BundleDefinition,
-    titles_by_id: &HashMap<String, String>,
-) -> BundleSummary {
-    let prompt_count = bundle
-        .prompt_ids
-        .iter()
-        .filter(|id| titles_by_id.contains_key(**id))
-        .count();
-
-    BundleSummary {
-        id: bundle.id.to_string(),
-        title: bundle.title.to_string(),
-        description: Some(bundle.description.to_string()),
-        prompt_count,
-    }
-}
-
-fn build_bundle_output(
-    bundle: &BundleDefinition,
-    titles_by_id: &HashMap<String, String>,
-) -> BundleOutput {
-    let prompts = bundle
-        .prompt_ids
-        .iter()
-        .filter_map(|prompt_id| {
-            titles_by_id.get(*prompt_id).map(|title| BundlePrompt {
-                id: (*prompt_id).to_string(),
-                title: title.clone(),
-            })
-        })
-        .collect();
-
-    BundleOutput {
-        id: bundle.id.to_string(),
-        title: bundle.title.to_string(),
-        description: Some(bundle.description.to_string()),
-        prompts,
+#[derive(Serialize)]
+struct BundleEditOutput {
+    /// created, updated or deleted
+    action: &'static str,
+    #[serde(flatten)]
+    bundle: BundleSummary,
+    prompts: Vec<String>,
+}
+
+/// Open the library, reporting failures the way every read command does
+fn open(config: &Config, use_json: bool) -> Option<Library> {
+    match open_library(config) {
+        Ok(library) => Some(library),
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            None
+        }
+    }
+}
+
+/// Resolve member titles, keeping the bundle's `position` order
+fn bundle_prompts(db: &Database, bundle: &Bundle) -> Result<Vec<BundlePrompt>> {
+    let mut prompts = Vec::with_capacity(bundle.prompts.len());
+    for id in &bundle.prompts {
+        if let Some(prompt) = db.get_prompt(id)? {
+            prompts.push(BundlePrompt {
+                id: prompt.id,
+                title: prompt.title,
+            });
+        }
     }
+    Ok(prompts)
 }
 
 /// List all available bundles
-pub fn list_bundles(use_json: bool) -> ExitCode {
-    let titles_by_id = prompt_title_map();
-    let bundles: Vec<BundleSummary> = BUNDLE_DEFINITIONS
-        .iter()
-        .map(|bundle| build_bundle_summary(bundle, &titles_by_id))
-        .collect();
+pub fn list_bundles(config: &Config, use_json: bool) -> ExitCode {
+    let Some(library) = open(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
+
+    let bundles: Vec<BundleSummary> = match library.db.list_bundles() {
+        Ok(bundles) => bundles.iter().map(BundleSummary::from).collect(),
+        Err(e) => {
+            if use_json {
+                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error listing bundles: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
 
     if use_json {
         let output = BundlesOutput {
             count: bundles.len(),
             bundles,
+            registry: library.registry,
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -144,7 +122,11 @@ pub fn list_bundles(use_json: bool) -> ExitCode {
             println!("No bundles available yet.");
         } else {
             for bundle in &bundles {
-                println!("  {} - {} ({} prompts)", bundle.id, bundle.title, bundle.prompt_count);
+                let featured = if bundle.featured { " *" } else { "" };
+                println!(
+                    "  {} - {} ({} prompts){}",
+                    bundle.id, bundle.title, bundle.prompt_count, featured
+                );
                 if let Some(desc) = &bundle.description {
                     println!("    {}", desc);
                 }
@@ -158,19 +140,42 @@ pub fn list_bundles(use_json: bool) -> ExitCode {
 }
 
 /// Show details for a specific bundle
-pub fn show_bundle(id: &str, use_json: bool) -> ExitCode {
-    let titles_by_id = prompt_title_map();
-    let Some(bundle) = BUNDLE_DEFINITIONS.iter().find(|bundle| bundle.id == id) else {
-        if use_json {
-            println!(r#"{{"error": "not_found", "id": "{}"}}"#, id);
-        } else {
-            eprintln!("Bundle '{}' not found.", id);
-            eprintln!("\nUse 'jfp bundles' to list available bundles");
-        }
+pub fn show_bundle(config: &Config, id: &str, use_json: bool) -> ExitCode {
+    let Some(library) = open(config, use_json) else {
         return ExitCode::FAILURE;
     };
+    let db = &library.db;
+
+    let found = db.get_bundle(id).and_then(|bundle| match bundle {
+        Some(bundle) => Ok(Some((bundle_prompts(db, &bundle)?, bundle))),
+        None => Ok(None),
+    });
+    let (prompts, bundle) = match found {
+        Ok(Some(found)) => found,
+        Ok(None) => {
+            if use_json {
+                println!(r#"{{"error": "not_found", "id": "{}"}}"#, id);
+            } else {
+                eprintln!("Bundle '{}' not found.", id);
+                eprintln!("\nUse 'jfp bundles' to list available bundles");
+            }
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            if use_json {
+                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error loading bundle: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
 
-    let output = build_bundle_output(bundle, &titles_by_id);
+    let output = BundleOutput {
+        bundle: BundleSummary::from(&bundle),
+        prompts,
+        registry: library.registry,
+    };
 
     if use_json {
         match serde_json::to_string_pretty(&output) {
@@ -181,10 +186,17 @@ pub fn show_bundle(id: &str, use_json: bool) -> ExitCode {
             }
         }
     } else {
-        println!("Bundle: {} - {}\n", output.id, output.title);
-        if let Some(desc) = &output.description {
+        let summary = &output.bundle;
+        println!("Bundle: {} - {}\n", summary.id, summary.title);
+        if let Some(desc) = &summary.description {
             println!("{}\n", desc);
         }
+        if let Some(version) = &summary.version {
+            println!("Version: {}", version);
+        }
+        if let Some(author) = &summary.author {
+            println!("Author: {}", author);
+        }
         println!("Prompts ({}):\n", output.prompts.len());
         for prompt in &output.prompts {
             println!("  - {} ({})", prompt.title, prompt.id);
@@ -196,46 +208,234 @@ pub fn show_bundle(id: &str, use_json: bool) -> ExitCode {
     ExitCode::SUCCESS
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::HashSet;
+/// Create a local bundle
+pub fn create(
+    config: &Config,
+    id: &str,
+    prompts: Vec<String>,
+    title: Option<String>,
+    description: Option<String>,
+    author: Option<String>,
+    use_json: bool,
+) -> ExitCode {
+    let Some((_lock, db)) = open_for_edit(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
 
-    #[test]
-    fn bundle_ids_are_unique() {
-        let mut seen = HashSet::new();
-        for bundle in BUNDLE_DEFINITIONS {
-            assert!(
-                seen.insert(bundle.id),
-                "duplicate bundle id found: {}",
-                bundle.id
+    match db.get_bundle(id) {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return fail(use_json, "already_exists", &format!("Bundle '{}' already exists", id));
+        }
+        Err(e) => return fail(use_json, "database_error", &e.to_string()),
+    }
+
+    let bundle = Bundle {
+        description,
+        author,
+        prompts: dedup(prompts),
+        is_local: true,
+        ..Bundle::new(id, title.unwrap_or_else(|| id.to_string()))
+    };
+    save(&db, bundle, "created", use_json)
+}
+
+/// Add prompts to a local bundle at `position` (default: the end)
+///
+/// Prompts already in the bundle are moved to the new position.
+pub fn add(
+    config: &Config,
+    id: &str,
+    prompts: Vec<String>,
+    position: Option<usize>,
+    use_json: bool,
+) -> ExitCode {
+    let Some((_lock, db)) = open_for_edit(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(mut bundle) = local_bundle(&db, id, use_json) else {
+        return ExitCode::FAILURE;
+    };
+
+    let added = dedup(prompts);
+    bundle.prompts.retain(|member| !added.contains(member));
+    let at = position.unwrap_or(bundle.prompts.len()).min(bundle.prompts.len());
+    bundle.prompts.splice(at..at, added);
+    save(&db, bundle, "updated", use_json)
+}
+
+/// Remove prompts from a local bundle
+pub fn remove(config: &Config, id: &str, prompts: &[String], use_json: bool) -> ExitCode {
+    let Some((_lock, db)) = open_for_edit(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(mut bundle) = local_bundle(&db, id, use_json) else {
+        return ExitCode::FAILURE;
+    };
+
+    if let Some(missing) = prompts.iter().find(|p| !bundle.prompts.contains(p)) {
+        return fail(
+            use_json,
+            "not_found",
+            &format!("Prompt '{}' is not in bundle '{}'", missing, id),
+        );
+    }
+    bundle.prompts.retain(|member| !prompts.contains(member));
+    save(&db, bundle, "updated", use_json)
+}
+
+/// Delete a local bundle
+pub fn delete(config: &Config, id: &str, use_json: bool) -> ExitCode {
+    let Some((_lock, db)) = open_for_edit(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(bundle) = local_bundle(&db, id, use_json) else {
+        return ExitCode::FAILURE;
+    };
+
+    if let Err(e) = db.delete_local_bundle(id) {
+        return fail(use_json, "database_error", &e.to_string());
+    }
+    print_edit("deleted", &bundle, use_json)
+}
+
+/// Bootstrap the library, then reopen it for writing under the sync lock
+fn open_for_edit(config: &Config, use_json: bool) -> Option<(LockGuard, Database)> {
+    if config.database.read_only {
+        fail(use_json, "read_only", "Bundles cannot be edited with --read-only");
+        return None;
+    }
+    // Seeds a fresh DB so members can be validated against the registry
+    drop(open(config, use_json)?);
+
+    let lock_path = crate::lock::sync_lock_path();
+    let lock = match crate::lock::acquire_at(&lock_path, config.sync.lock_timeout()) {
+        Ok(lock) => lock,
+        Err(e) => {
+            fail(use_json, e.code(), &e.to_string());
+            return None;
+        }
+    };
+    match Database::open() {
+        Ok(db) => Some((lock, db)),
+        Err(e) => {
+            fail(use_json, "database_error", &e.to_string());
+            None
+        }
+    }
+}
+
+/// Load bundle `id`, failing unless it is a local bundle
+fn local_bundle(db: &Database, id: &str, use_json: bool) -> Option<Bundle> {
+    match db.get_bundle(id) {
+        Ok(Some(bundle)) if bundle.is_local => Some(bundle),
+        Ok(Some(_)) => {
+            fail(
+                use_json,
+                "not_local",
+                &format!("Bundle '{}' comes from the registry; create a local bundle instead", id),
             );
+            None
+        }
+        Ok(None) => {
+            fail(use_json, "not_found", &format!("Bundle '{}' not found", id));
+            None
+        }
+        Err(e) => {
+            fail(use_json, "database_error", &e.to_string());
+            None
         }
     }
+}
 
-    #[test]
-    fn bundle_prompt_ids_exist_in_embedded_prompts() {
-        let titles_by_id = prompt_title_map();
-        for bundle in BUNDLE_DEFINITIONS {
-            for prompt_id in bundle.prompt_ids {
-                assert!(
-                    titles_by_id.contains_key(*prompt_id),
-                    "bundle '{}' references missing prompt id '{}'",
-                    bundle.id,
-                    prompt_id
-                );
+/// Validate members, write the bundle and report it
+fn save(db: &Database, bundle: Bundle, action: &'static str, use_json: bool) -> ExitCode {
+    let mut missing = Vec::new();
+    for id in &bundle.prompts {
+        match db.get_prompt(id) {
+            Ok(Some(_)) => {}
+            Ok(None) => missing.push(id.as_str()),
+            Err(e) => return fail(use_json, "database_error", &e.to_string()),
+        }
+    }
+    if !missing.is_empty() {
+        return fail(
+            use_json,
+            "prompt_not_found",
+            &format!("Unknown prompt(s): {}", missing.join(", ")),
+        );
+    }
+
+    if let Err(e) = db.save_local_bundle(&bundle) {
+        return fail(use_json, "database_error", &e.to_string());
+    }
+    let bundle = Bundle {
+        prompt_count: bundle.prompts.len(),
+        ..bundle
+    };
+    print_edit(action, &bundle, use_json)
+}
+
+fn print_edit(action: &'static str, bundle: &Bundle, use_json: bool) -> ExitCode {
+    if use_json {
+        let output = BundleEditOutput {
+            action,
+            bundle: BundleSummary::from(bundle),
+            prompts: bundle.prompts.clone(),
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
             }
         }
+    } else {
+        let verb = match action {
+            "created" => "Created",
+            "deleted" => "Deleted",
+            _ => "Updated",
+        };
+        println!("{} local bundle '{}' ({} prompts)", verb, bundle.id, bundle.prompts.len());
+        for (position, id) in bundle.prompts.iter().enumerate() {
+            println!("  {}. {}", position, id);
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Drop repeated ids, keeping the first occurrence
+fn dedup(ids: Vec<String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::with_capacity(ids.len());
+    for id in ids {
+        if !unique.contains(&id) {
+            unique.push(id);
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{bundled_bundles, bundled_prompts};
+
+    #[test]
+    fn bundle_prompts_follow_position_order() -> Result<()> {
+        let db = Database::in_memory()?;
+        db.sync_prompts(&bundled_prompts(), false)?;
+        db.sync_bundles(&bundled_bundles(), false)?;
+
+        let bundle = db.get_bundle("getting-started")?.expect("missing getting-started bundle");
+        let ids: Vec<String> = bundle_prompts(&db, &bundle)?.into_iter().map(|p| p.id).collect();
+        assert_eq!(ids, ["code-review", "debug", "explain-code"]);
+        assert_eq!(BundleSummary::from(&bundle).prompt_count, 3);
+        Ok(())
     }
 
     #[test]
-    fn bundle_output_prompt_count_matches_definition() {
-        let titles_by_id = prompt_title_map();
-        let bundle = BUNDLE_DEFINITIONS
-            .iter()
-            .find(|bundle| bundle.id == "getting-started")
-            .expect("missing getting-started bundle");
-        let output = build_bundle_output(bundle, &titles_by_id);
-        assert_eq!(output.prompts.len(), bundle.prompt_ids.len());
+    fn dedup_keeps_first_occurrence() {
+        let ids = ["b", "a", "b", "c", "a"].map(String::from).to_vec();
+        assert_eq!(dedup(ids), ["b", "a", "c"]);
     }
 }
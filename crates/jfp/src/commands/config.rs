@@ -2,7 +2,9 @@
 //!
 //! From EXISTING_JFP_STRUCTURE.md section 13 (config):
 //! - Actions: get, set, list, reset, path
-//! - Reads/writes config file at XDG config path
+//! - Reads/writes config file at `config::config_path()` (honors `JFP_HOME`)
+//! - Keys are dotted (`registry.cacheTtl`); `set` type-checks against `Config`
+//! - `list --show-origin` reports the layer/file behind each effective value
 
 use std::fs;
 use std::io::Write;
@@ -11,15 +13,10 @@ use std::process::ExitCode;
 
 use serde::Serialize;
 
-/// Get the config directory
-fn config_dir() -> Option<PathBuf> {
-    dirs::config_dir().map(|d| d.join("jfp"))
-}
-
-/// Get the config file path
-fn config_path() -> Option<PathBuf> {
-    config_dir().map(|d| d.join("config.toml"))
-}
+use crate::config::{
+    self, config_path, parse_value, set_dotted, validate_table, CliOverride, ConfigError,
+    EffectiveValue, LoadedConfig,
+};
 
 #[derive(Serialize)]
 struct ConfigOutput {
@@ -32,11 +29,33 @@ struct ConfigOutput {
     #[serde(skip_serializing_if = "Option::is_none")]
     path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    config: Option<toml::Value>,
+    origin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config: Option<toml::Table>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entries: Option<Vec<EffectiveValue>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
 }
 
+fn emit_config_error(err: &ConfigError, use_json: bool) -> ExitCode {
+    if use_json {
+        println!(
+            "{}",
+            serde_json::json!({"error": err.code(), "message": err.to_string()})
+        );
+    } else {
+        eprintln!("Error: {}", err);
+    }
+    ExitCode::FAILURE
+}
+
+fn load_config(cli: &[CliOverride], use_json: bool) -> Result<LoadedConfig, ExitCode> {
+    config::load(cli).map_err(|e| emit_config_error(&e, use_json))
+}
+
 fn emit_json(output: &ConfigOutput) -> ExitCode {
     match serde_json::to_string_pretty(output) {
         Ok(json) => {
@@ -57,7 +76,7 @@ fn parse_config_table(content: &str) -> Result<toml::map::Map<String, toml::Valu
 }
 
 fn write_config_atomically(path: &PathBuf, content: &str) -> Result<(), std::io::Error> {
-    let temp_path = path.with_extension("toml.tmp");
+    let temp_path = crate::lock::temp_path(path);
     let mut temp_file = fs::File::create(&temp_path)?;
     temp_file.write_all(content.as_bytes())?;
     temp_file.sync_all()?;
@@ -72,13 +91,20 @@ fn write_config_atomically(path: &PathBuf, content: &str) -> Result<(), std::io:
     Ok(())
 }
 
-pub fn run(action: &str, key: Option<String>, value: Option<String>, use_json: bool) -> ExitCode {
+pub fn run(
+    action: &str,
+    key: Option<String>,
+    value: Option<String>,
+    show_origin: bool,
+    cli: &[CliOverride],
+    use_json: bool,
+) -> ExitCode {
     match action {
         "path" => show_path(use_json),
-        "list" => list_config(use_json),
+        "list" => list_config(show_origin, cli, use_json),
         "get" => {
             if let Some(k) = key {
-                get_config(&k, use_json)
+                get_config(&k, cli, use_json)
             } else {
                 if use_json {
                     println!(r#"{{"error": "missing_key"}}"#);
@@ -121,7 +147,10 @@ fn show_path(use_json: bool) -> ExitCode {
             key: None,
             value: None,
             path: path.as_ref().map(|p| p.display().to_string()),
+            origin: None,
+            project: None,
             config: None,
+            entries: None,
             error: None,
         };
         return emit_json(&output);
@@ -145,66 +174,41 @@ fn show_path(use_json: bool) -> ExitCode {
     ExitCode::SUCCESS
 }
 
-fn list_config(use_json: bool) -> ExitCode {
-    let path = match config_path() {
-        Some(p) => p,
-        None => {
-            if use_json {
-                println!(r#"{{"error": "no_config_path"}}"#);
-            } else {
-                eprintln!("Could not determine config path");
-            }
-            return ExitCode::FAILURE;
-        }
-    };
-
-    let config: toml::Value = if path.exists() {
-        let content = match fs::read_to_string(&path) {
-            Ok(c) => c,
-            Err(e) => {
-                if use_json {
-                    println!(r#"{{"error": "read_error", "message": "{}"}}"#, e);
-                } else {
-                    eprintln!("Error reading config: {}", e);
-                }
-                return ExitCode::FAILURE;
-            }
-        };
-        match content.parse() {
-            Ok(v) => v,
-            Err(e) => {
-                if use_json {
-                    println!(r#"{{"error": "parse_error", "message": "{}"}}"#, e);
-                } else {
-                    eprintln!("Error parsing config: {}", e);
-                }
-                return ExitCode::FAILURE;
-            }
-        }
-    } else {
-        toml::Value::Table(toml::map::Map::new())
+fn list_config(show_origin: bool, cli: &[CliOverride], use_json: bool) -> ExitCode {
+    let loaded = match load_config(cli, use_json) {
+        Ok(loaded) => loaded,
+        Err(code) => return code,
     };
+    let path = config_path();
 
     if use_json {
         let output = ConfigOutput {
             action: Some("list".to_string()),
             key: None,
             value: None,
-            path: Some(path.display().to_string()),
-            config: Some(config),
+            path: path.map(|p| p.display().to_string()),
+            origin: None,
+            project: loaded.project_path().map(|p| p.display().to_string()),
+            config: Some(loaded.table().clone()),
+            entries: show_origin.then(|| loaded.entries()),
             error: None,
         };
         return emit_json(&output);
     } else {
-        println!("Config file: {}", path.display());
+        match &path {
+            Some(p) if p.exists() => println!("Config file: {}", p.display()),
+            Some(p) => println!("Config file: {} (not created yet)", p.display()),
+            None => println!("Config file: (unknown)"),
+        }
+        if let Some(project) = loaded.project_path() {
+            println!("Project file: {}", project.display());
+        }
         println!();
-        if let toml::Value::Table(t) = config {
-            if t.is_empty() {
-                println!("(no configuration set)");
+        for entry in loaded.entries() {
+            if show_origin {
+                println!("{}\t{} = {}", entry.origin, entry.key, entry.value);
             } else {
-                for (k, v) in t {
-                    println!("{} = {}", k, v);
-                }
+                println!("{} = {}", entry.key, entry.value);
             }
         }
     }
@@ -212,62 +216,31 @@ fn list_config(use_json: bool) -> ExitCode {
     ExitCode::SUCCESS
 }
 
-fn get_config(key: &str, use_json: bool) -> ExitCode {
-    let path = match config_path() {
-        Some(p) => p,
-        None => {
-            if use_json {
-                println!(r#"{{"error": "no_config_path"}}"#);
-            } else {
-                eprintln!("Could not determine config path");
-            }
-            return ExitCode::FAILURE;
-        }
-    };
-
-    if !path.exists() {
-        if use_json {
-            println!(r#"{{"error": "not_found", "key": "{}"}}"#, key);
-        } else {
-            eprintln!("Key '{}' not found (config file doesn't exist)", key);
-        }
-        return ExitCode::FAILURE;
+fn get_config(key: &str, cli: &[CliOverride], use_json: bool) -> ExitCode {
+    if config::key_template(key).is_none() {
+        let err = ConfigError::InvalidKey {
+            key: key.to_string(),
+        };
+        return emit_config_error(&err, use_json);
     }
 
-    let content = match fs::read_to_string(&path) {
-        Ok(c) => c,
-        Err(e) => {
-            if use_json {
-                println!(r#"{{"error": "read_error", "message": "{}"}}"#, e);
-            } else {
-                eprintln!("Error reading config: {}", e);
-            }
-            return ExitCode::FAILURE;
-        }
-    };
-
-    let config: toml::Value = match content.parse() {
-        Ok(v) => v,
-        Err(e) => {
-            if use_json {
-                println!(r#"{{"error": "parse_error", "message": "{}"}}"#, e);
-            } else {
-                eprintln!("Error parsing config: {}", e);
-            }
-            return ExitCode::FAILURE;
-        }
+    let loaded = match load_config(cli, use_json) {
+        Ok(loaded) => loaded,
+        Err(code) => return code,
     };
-
-    let value = config.get(key);
+    let value = loaded.get(key);
 
     if use_json {
         let output = ConfigOutput {
             action: Some("get".to_string()),
             key: Some(key.to_string()),
-            value: value.map(|v| v.to_string()),
+            value: value.map(display_value),
             path: None,
+            origin: value.map(|_| loaded.origin(key).to_string()),
+            project: None,
             config: None,
-            error: if value.is_none() { Some("not_found".to_string()) } else { None },
+            entries: None,
+            error: if value.is_none() { Some("not_set".to_string()) } else { None },
         };
         let status = emit_json(&output);
         if status != ExitCode::SUCCESS {
@@ -278,9 +251,9 @@ fn get_config(key: &str, use_json: bool) -> ExitCode {
         }
     } else {
         match value {
-            Some(v) => println!("{}", v),
+            Some(v) => println!("{}", display_value(v)),
             None => {
-                eprintln!("Key '{}' not found", key);
+                eprintln!("Key '{}' is not set", key);
                 return ExitCode::FAILURE;
             }
         }
@@ -289,7 +262,21 @@ fn get_config(key: &str, use_json: bool) -> ExitCode {
     ExitCode::SUCCESS
 }
 
+/// Strings print bare; everything else in TOML syntax
+fn display_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
 fn set_config(key: &str, value: &str, use_json: bool) -> ExitCode {
+    // Reject unknown keys and mistyped values before touching the file
+    let parsed_value = match parse_value(key, value) {
+        Ok(v) => v,
+        Err(e) => return emit_config_error(&e, use_json),
+    };
+
     let path = match config_path() {
         Some(p) => p,
         None => {
@@ -343,20 +330,12 @@ fn set_config(key: &str, value: &str, use_json: bool) -> ExitCode {
         toml::map::Map::new()
     };
 
-    // Try to parse value as appropriate type
-    let parsed_value = if value == "true" {
-        toml::Value::Boolean(true)
-    } else if value == "false" {
-        toml::Value::Boolean(false)
-    } else if let Ok(n) = value.parse::<i64>() {
-        toml::Value::Integer(n)
-    } else if let Ok(f) = value.parse::<f64>() {
-        toml::Value::Float(f)
-    } else {
-        toml::Value::String(value.to_string())
-    };
+    set_dotted(&mut config, key, parsed_value.clone());
 
-    config.insert(key.to_string(), parsed_value.clone());
+    // Never write a file that the loader would then refuse
+    if let Err(e) = validate_table(&config) {
+        return emit_config_error(&e, use_json);
+    }
 
     // Write config
     let content = match toml::to_string_pretty(&toml::Value::Table(config)) {
@@ -383,14 +362,22 @@ fn set_config(key: &str, value: &str, use_json: bool) -> ExitCode {
         let output = ConfigOutput {
             action: Some("set".to_string()),
             key: Some(key.to_string()),
-            value: Some(parsed_value.to_string()),
+            value: Some(display_value(&parsed_value)),
             path: Some(path.display().to_string()),
+            origin: None,
+            project: None,
             config: None,
+            entries: None,
             error: None,
         };
         return emit_json(&output);
     } else {
         println!("Set {} = {}", key, parsed_value);
+        if let Ok(loaded) = config::load(&[])
+            && !matches!(loaded.origin(key), config::ConfigOrigin::File(_))
+        {
+            println!("Note: {} is overridden by {}", key, loaded.origin(key));
+        }
     }
 
     ExitCode::SUCCESS
@@ -400,7 +387,7 @@ fn set_config(key: &str, value: &str, use_json: bool) -> ExitCode {
 mod tests {
     use std::fs;
 
-    use super::{parse_config_table, write_config_atomically};
+    use super::{display_value, parse_config_table, write_config_atomically};
     use tempfile::tempdir;
 
     #[test]
@@ -428,6 +415,12 @@ mod tests {
         assert_eq!(content, "foo = 2\nbar = true\n");
     }
 
+    #[test]
+    fn display_value_prints_strings_bare() {
+        assert_eq!(display_value(&toml::Value::String("a b".to_string())), "a b");
+        assert_eq!(display_value(&toml::Value::Integer(60)), "60");
+        assert_eq!(display_value(&toml::Value::Boolean(false)), "false");
+    }
 }
 
 fn reset_config(use_json: bool) -> ExitCode {
@@ -460,7 +453,10 @@ fn reset_config(use_json: bool) -> ExitCode {
             key: None,
             value: None,
             path: Some(path.display().to_string()),
+            origin: None,
+            project: None,
             config: None,
+            entries: None,
             error: None,
         };
         return emit_json(&output);
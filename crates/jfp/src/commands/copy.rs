@@ -2,57 +2,76 @@
 //!
 //! From EXISTING_JFP_STRUCTURE.md section 8 (copy):
 //! - Copies prompt content to clipboard
-//! - Optional --fill for interactive variable substitution
+//! - Variables are substituted from the same sources as render; --fill
+//!   asks for the rest interactively
 //! - Uses platform clipboard tools
 
-use std::io::{self, Write};
+use std::collections::BTreeMap;
+use std::io::Write;
 use std::process::{Command, ExitCode, Stdio};
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
-use crate::types::Prompt;
+use crate::registry::{open_library, RegistryStatus};
+use crate::template::{ask_stdin, cli_values, DynamicDefaults, ResolvedValue, Resolver};
+use crate::types::Config;
 
 #[derive(Serialize)]
 struct CopyOutput {
     id: String,
     title: String,
     copied: bool,
+    /// Values given with --var NAME=VALUE or --NAME=VALUE
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    variables: BTreeMap<String, String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    filled_variables: Option<Vec<FilledVariable>>,
+    filled_variables: Option<Vec<ResolvedValue>>,
+    /// Placeholders left in the copied text for lack of a value
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unresolved: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     content_length: Option<usize>,
+    #[serde(flatten)]
+    registry: RegistryStatus,
 }
 
-#[derive(Serialize)]
-struct FilledVariable {
-    name: String,
-    value: String,
-}
-
-pub fn run(id: &str, fill: bool, use_json: bool) -> ExitCode {
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+pub fn run(
+    config: &Config,
+    id: &str,
+    fill: bool,
+    vars: &[String],
+    no_dynamic: bool,
+    use_json: bool,
+) -> ExitCode {
+    let cli_values = match cli_values(vars) {
+        Ok(values) => values,
         Err(e) => {
             if use_json {
-                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                eprintln!("{}", e.to_json());
             } else {
-                eprintln!("Error opening database: {}", e);
+                eprintln!("Error: {}", e);
             }
             return ExitCode::FAILURE;
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
+    // Open the library (DB bootstrapped from the registry loader)
+    let library = match open_library(config) {
+        Ok(library) => library,
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
         }
-    }
+    };
+    let db = &library.db;
 
     // Get prompt
     let prompt = match db.get_prompt(id) {
@@ -75,12 +94,30 @@ pub fn run(id: &str, fill: bool, use_json: bool) -> ExitCode {
         }
     };
 
-    // Process content (with variable filling if requested)
-    let (content, filled_variables) = if fill && !prompt.variables.is_empty() {
-        fill_variables(&prompt, use_json)
-    } else {
-        (prompt.content.clone(), None)
+    let mut resolver = Resolver::new(&prompt)
+        .with_cli(cli_values.clone())
+        .with_env(std::env::vars())
+        .with_config(&config.variables);
+    if !no_dynamic && let Some(dynamic) = DynamicDefaults::current() {
+        resolver = resolver.with_dynamic(dynamic);
+    }
+    // Only ask when someone is there to answer
+    if fill && !use_json && atty::is(atty::Stream::Stdin) {
+        resolver = resolver.with_ask(Box::new(ask_stdin));
+    }
+    let rendered = match resolver.render() {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            if use_json {
+                eprintln!("{}", e.to_json());
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            return e.exit_code();
+        }
     };
+    let content = rendered.content;
+    let filled_variables = Some(rendered.values).filter(|values| !values.is_empty());
 
     // Copy to clipboard
     let copied = match copy_to_clipboard(&content) {
@@ -98,8 +135,11 @@ pub fn run(id: &str, fill: bool, use_json: bool) -> ExitCode {
             id: prompt.id.clone(),
             title: prompt.title.clone(),
             copied,
+            variables: cli_values,
             filled_variables,
+            unresolved: rendered.unresolved,
             content_length: Some(content.len()),
+            registry: library.registry.clone(),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -121,54 +161,12 @@ pub fn run(id: &str, fill: bool, use_json: bool) -> ExitCode {
             println!("Content ({} characters):", content.len());
             println!("{}", content);
         }
-    }
-
-    ExitCode::SUCCESS
-}
-
-/// Fill variables interactively by prompting the user
-fn fill_variables(prompt: &Prompt, use_json: bool) -> (String, Option<Vec<FilledVariable>>) {
-    let mut content = prompt.content.clone();
-    let mut filled = Vec::new();
-
-    // In JSON mode or non-TTY, don't prompt - just return original
-    if use_json || !atty::is(atty::Stream::Stdin) {
-        return (content, None);
-    }
-
-    for var in &prompt.variables {
-        let default_hint = var.default.as_ref()
-            .map(|d| format!(" [{}]", d))
-            .unwrap_or_default();
-
-        let description = var.description.as_ref()
-            .map(|d| format!(" ({})", d))
-            .unwrap_or_default();
-
-        print!("{}{}{}: ", var.name, description, default_hint);
-        io::stdout().flush().ok();
-
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_ok() {
-            let value = input.trim();
-            let value = if value.is_empty() {
-                var.default.clone().unwrap_or_default()
-            } else {
-                value.to_string()
-            };
-
-            // Replace the variable placeholder in content
-            let placeholder = format!("{{{{{}}}}}", var.name);
-            content = content.replace(&placeholder, &value);
-
-            filled.push(FilledVariable {
-                name: var.name.clone(),
-                value,
-            });
+        if !rendered.unresolved.is_empty() {
+            eprintln!("Warning: no value for {}", rendered.unresolved.join(", "));
         }
     }
 
-    (content, Some(filled))
+    ExitCode::SUCCESS
 }
 
 /// Copy text to clipboard using platform tools
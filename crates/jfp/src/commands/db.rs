@@ -0,0 +1,726 @@
+//! Database maintenance commands
+//!
+//! Rust-only (SQLite store), see RECOVERY_RUNBOOK.md:
+//! - `jfp db migrate` applies pending schema migrations (backing up first)
+//! - `jfp db migrate --status` lists applied and pending migrations
+//! - `jfp db reindex` rebuilds `prompts_fts` from `prompts`
+//! - `jfp db export-jsonl` / `import-jsonl` write and restore the JSONL backup
+//! - `jfp db check` runs `PRAGMA integrity_check` plus the FTS consistency check
+//! - `jfp db vacuum` / `checkpoint` compact the file and truncate the WAL
+//! - `jfp db recover` rebuilds whichever of SQLite/JSONL is out of date
+//! - Writers hold the cross-process sync lock (`sync.lockTimeoutMs`) and fail
+//!   under `--read-only`; `check` and `migrate --status` only read
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use super::{check_read_only, fail};
+use crate::lock::{sync_lock_path, LockGuard};
+use crate::storage::{
+    is_corruption, Database, FtsConsistency, ImportMode, MigrationReport, MigrationStatus, RecoveryAction,
+    SCHEMA_VERSION,
+};
+use crate::types::Config;
+
+#[derive(Serialize)]
+struct MigrateStatusOutput {
+    path: String,
+    exists: bool,
+    current_version: i32,
+    target_version: i32,
+    pending: usize,
+    migrations: Vec<MigrationStatus>,
+}
+
+#[derive(Serialize)]
+struct MigrateOutput {
+    path: String,
+    #[serde(flatten)]
+    report: MigrationReport,
+}
+
+#[derive(Serialize)]
+struct ReindexOutput {
+    indexed: usize,
+    consistent: bool,
+    before: FtsConsistency,
+}
+
+#[derive(Serialize)]
+struct ExportOutput {
+    path: String,
+    exported: usize,
+    sha256: String,
+}
+
+#[derive(Serialize)]
+struct ImportOutput {
+    path: String,
+    mode: ImportMode,
+    imported: usize,
+    prompt_count: usize,
+    /// Content hash, recorded in the sync markers after a full replace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CheckOutput {
+    path: String,
+    ok: bool,
+    integrity_ok: bool,
+    schema_version: i32,
+    target_version: i32,
+    /// Only checked on an intact DB with the current schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fts: Option<FtsConsistency>,
+}
+
+#[derive(Serialize)]
+struct VacuumOutput {
+    path: String,
+    size_before: u64,
+    size_after: u64,
+}
+
+#[derive(Serialize)]
+struct CheckpointOutput {
+    path: String,
+    checkpointed: bool,
+}
+
+pub fn migrate(config: &Config, status: bool, use_json: bool) -> ExitCode {
+    if !status && let Some(code) = check_read_only(config, use_json, "The database") {
+        return code;
+    }
+    let path = crate::storage::db_path();
+    let _lock = if status {
+        None
+    } else {
+        match take_lock(config, use_json) {
+            Some(guard) => Some(guard),
+            None => return ExitCode::FAILURE,
+        }
+    };
+
+    // `--status` only inspects: never create, lock or write the DB
+    if status && !path.exists() {
+        return no_database(path.display().to_string(), use_json);
+    }
+    let opened = if status {
+        Database::open_read_only_at(&path)
+    } else {
+        Database::open_unmigrated()
+    };
+    let db = match opened {
+        Ok(db) => db,
+        Err(e) => return report_error(use_json, "database_error", "opening database", e),
+    };
+
+    if status {
+        return migrate_status(&db, path.display().to_string(), use_json);
+    }
+
+    let report = match db.migrate() {
+        Ok(report) => report,
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    "{}",
+                    serde_json::json!({"error": "migration_error", "message": format!("{:#}", e)})
+                );
+            } else {
+                eprintln!("Error migrating database: {:#}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if use_json {
+        let output = MigrateOutput {
+            path: path.display().to_string(),
+            report,
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => return fail(true, "serialization_error", &e.to_string()),
+        }
+    } else if report.applied.is_empty() {
+        println!("Database is up to date (schema version {})", report.to_version);
+    } else {
+        println!(
+            "Migrated database from version {} to {}",
+            report.from_version, report.to_version
+        );
+        if let Some(backup) = &report.backup_path {
+            println!("Backup: {}", backup.display());
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// `migrate --status` without a DB file: nothing applied, nothing to migrate
+fn no_database(path: String, use_json: bool) -> ExitCode {
+    if use_json {
+        let output = serde_json::json!({
+            "path": path,
+            "exists": false,
+            "target_version": SCHEMA_VERSION,
+        });
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => return fail(true, "serialization_error", &e.to_string()),
+        }
+    } else {
+        println!("Database: {}", path);
+        println!(
+            "No database yet; it is created at schema version {} on first use.",
+            SCHEMA_VERSION
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+fn migrate_status(db: &Database, path: String, use_json: bool) -> ExitCode {
+    let (current_version, migrations) = match db.schema_version().and_then(|v| {
+        db.migration_status().map(|m| (v, m))
+    }) {
+        Ok(result) => result,
+        Err(e) => return report_error(use_json, "database_error", "reading migration status", e),
+    };
+    let pending = migrations.iter().filter(|m| !m.applied).count();
+
+    if use_json {
+        let output = MigrateStatusOutput {
+            path,
+            exists: true,
+            current_version,
+            target_version: SCHEMA_VERSION,
+            pending,
+            migrations,
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => return fail(true, "serialization_error", &e.to_string()),
+        }
+    } else {
+        println!("Database: {}", path);
+        println!("Schema version: {} (latest {})\n", current_version, SCHEMA_VERSION);
+        for m in &migrations {
+            let state = if m.applied { "applied" } else { "pending" };
+            let applied_at = m
+                .applied_at
+                .as_ref()
+                .map(|at| format!(" ({})", at))
+                .unwrap_or_default();
+            println!("  {:>3}  {:<7}  {}{}", m.version, state, m.description, applied_at);
+        }
+        if pending > 0 {
+            println!("\n{} pending migration(s). Run 'jfp db migrate' to apply.", pending);
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+pub fn reindex(config: &Config, use_json: bool) -> ExitCode {
+    if let Some(code) = check_read_only(config, use_json, "The database") {
+        return code;
+    }
+    let Some(_lock) = take_lock(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let db = match Database::open() {
+        Ok(db) => db,
+        Err(e) => return report_error(use_json, "database_error", "opening database", e),
+    };
+
+    let result = db.fts_consistency().and_then(|before| {
+        let indexed = db.reindex_fts()?;
+        let after = db.fts_consistency()?;
+        Ok((before, indexed, after.is_consistent()))
+    });
+    let (before, indexed, consistent) = match result {
+        Ok(result) => result,
+        Err(e) => return report_error(use_json, "database_error", "rebuilding search index", e),
+    };
+
+    if use_json {
+        let output = ReindexOutput {
+            indexed,
+            consistent,
+            before,
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => return fail(true, "serialization_error", &e.to_string()),
+        }
+    } else {
+        if before.is_consistent() {
+            println!("Search index was consistent");
+        } else {
+            println!(
+                "Search index had {} missing, {} orphaned and {} stale entries",
+                before.missing.len(),
+                before.orphaned.len(),
+                before.stale.len()
+            );
+        }
+        println!("Reindexed {} prompts", indexed);
+    }
+
+    if consistent {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+pub fn export_jsonl(config: &Config, path: Option<String>, use_json: bool) -> ExitCode {
+    let Some(_lock) = take_lock(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(path) = jsonl_path(path, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(db) = open_db(use_json) else {
+        return ExitCode::FAILURE;
+    };
+
+    let result = crate::storage::export_jsonl(&db, &path)
+        .and_then(|count| Ok((count, crate::storage::mark_synced(&db, &path)?)));
+    let (exported, state) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    "{}",
+                    serde_json::json!({"error": "export_error", "message": format!("{:#}", e)})
+                );
+            } else {
+                eprintln!("Error exporting JSONL: {:#}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let output = ExportOutput {
+        path: path.display().to_string(),
+        exported,
+        sha256: state.sqlite_sha256,
+    };
+    if use_json {
+        return print_json(&output);
+    }
+    println!("Exported {} prompts to {}", output.exported, output.path);
+    ExitCode::SUCCESS
+}
+
+pub fn import_jsonl(
+    config: &Config,
+    path: Option<String>,
+    mode: &str,
+    use_json: bool,
+) -> ExitCode {
+    if let Some(code) = check_read_only(config, use_json, "The database") {
+        return code;
+    }
+    let mode: ImportMode = match mode.parse() {
+        Ok(mode) => mode,
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    "{}",
+                    serde_json::json!({"error": "invalid_mode", "message": e.to_string()})
+                );
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+    let Some(path) = jsonl_path(path, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    // Reject a file from a newer jfp before the DB is opened and migrated
+    let contents = match crate::storage::load_jsonl(&path) {
+        Ok(contents) => contents,
+        Err(e) => return import_failed(use_json, e),
+    };
+    let Some(_lock) = take_lock(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(mut db) = open_db(use_json) else {
+        return ExitCode::FAILURE;
+    };
+
+    // A merge leaves prompts the file does not have, so only a replace
+    // brings the stores in sync
+    let result = crate::storage::import_jsonl_contents(&mut db, contents, mode).and_then(|imported| {
+        let sha256 = match mode {
+            ImportMode::Replace => Some(crate::storage::mark_synced(&db, &path)?.sqlite_sha256),
+            ImportMode::Merge => None,
+        };
+        Ok((imported, db.prompt_count()?, sha256))
+    });
+    let (imported, prompt_count, sha256) = match result {
+        Ok(result) => result,
+        Err(e) => return import_failed(use_json, e),
+    };
+
+    let output = ImportOutput {
+        path: path.display().to_string(),
+        mode,
+        imported,
+        prompt_count,
+        sha256,
+    };
+    if use_json {
+        return print_json(&output);
+    }
+    let verb = match mode {
+        ImportMode::Merge => "Merged",
+        ImportMode::Replace => "Replaced database with",
+    };
+    println!("{} {} prompts from {}", verb, output.imported, output.path);
+    println!("Database now has {} prompts", output.prompt_count);
+    ExitCode::SUCCESS
+}
+
+pub fn check(use_json: bool) -> ExitCode {
+    // Inspect only: a missing DB is not created and nothing is migrated
+    let path = crate::storage::db_path();
+    if !path.exists() {
+        return no_database(path.display().to_string(), use_json);
+    }
+    let output = match check_at(&path) {
+        Ok(output) => output,
+        Err(e) => return report_error(use_json, "database_error", "checking database", e),
+    };
+
+    if use_json {
+        if print_json(&output) == ExitCode::FAILURE {
+            return ExitCode::FAILURE;
+        }
+    } else {
+        println!("Database: {}", output.path);
+        println!(
+            "  Integrity: {}",
+            if output.integrity_ok { "ok" } else { "FAILED" }
+        );
+        println!(
+            "  Schema version: {} (latest {})",
+            output.schema_version, output.target_version
+        );
+        match &output.fts {
+            Some(fts) if fts.is_consistent() => {
+                println!("  Search index: ok ({} rows)", fts.fts_rows);
+            }
+            Some(fts) => println!(
+                "  Search index: {} missing, {} orphaned, {} stale",
+                fts.missing.len(),
+                fts.orphaned.len(),
+                fts.stale.len()
+            ),
+            None => println!("  Search index: not checked"),
+        }
+
+        if !output.integrity_ok {
+            println!("\nThe database is corrupt. Restore it with 'jfp db import-jsonl --mode replace' (see RECOVERY_RUNBOOK.md)");
+        } else if output.schema_version != output.target_version {
+            println!("\nRun 'jfp db migrate' to update the schema");
+        } else if !output.fts.as_ref().is_some_and(FtsConsistency::is_consistent) {
+            println!("\nRun 'jfp db reindex' to rebuild the search index");
+        }
+    }
+
+    if output.ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+pub fn vacuum(config: &Config, use_json: bool) -> ExitCode {
+    if let Some(code) = check_read_only(config, use_json, "The database") {
+        return code;
+    }
+    let Some(_lock) = take_lock(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(db) = open_db(use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let path = crate::storage::db_path();
+    let file_size = || std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+
+    let size_before = file_size();
+    if let Err(e) = db.checkpoint().and_then(|_| db.vacuum()) {
+        return report_error(use_json, "database_error", "vacuuming database", e);
+    }
+
+    let output = VacuumOutput {
+        path: path.display().to_string(),
+        size_before,
+        size_after: file_size(),
+    };
+    if use_json {
+        return print_json(&output);
+    }
+    println!(
+        "Vacuumed {} ({} -> {} bytes)",
+        output.path, output.size_before, output.size_after
+    );
+    ExitCode::SUCCESS
+}
+
+pub fn checkpoint(config: &Config, use_json: bool) -> ExitCode {
+    if let Some(code) = check_read_only(config, use_json, "The database") {
+        return code;
+    }
+    let Some(_lock) = take_lock(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(db) = open_db(use_json) else {
+        return ExitCode::FAILURE;
+    };
+
+    if let Err(e) = db.checkpoint() {
+        return report_error(use_json, "database_error", "checkpointing database", e);
+    }
+
+    let output = CheckpointOutput {
+        path: crate::storage::db_path().display().to_string(),
+        checkpointed: true,
+    };
+    if use_json {
+        return print_json(&output);
+    }
+    println!("Checkpointed WAL for {}", output.path);
+    ExitCode::SUCCESS
+}
+
+pub fn recover(config: &Config, path: Option<String>, use_json: bool) -> ExitCode {
+    if let Some(code) = check_read_only(config, use_json, "The database") {
+        return code;
+    }
+    let Some(jsonl_path) = jsonl_path(path, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(_lock) = take_lock(config, use_json) else {
+        return ExitCode::FAILURE;
+    };
+    let db_path = crate::storage::db_path();
+
+    let report = match crate::storage::recover(&db_path, &jsonl_path) {
+        Ok(report) => report,
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    "{}",
+                    serde_json::json!({"error": "recovery_error", "message": format!("{:#}", e)})
+                );
+            } else {
+                eprintln!("Error recovering database: {:#}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if use_json {
+        if print_json(&report) == ExitCode::FAILURE {
+            return ExitCode::FAILURE;
+        }
+    } else {
+        let action = match report.action {
+            RecoveryAction::InSync => "Nothing to do",
+            RecoveryAction::RebuiltSqlite => "Rebuilt SQLite from JSONL",
+            RecoveryAction::ReexportedJsonl => "Re-exported JSONL from SQLite",
+        };
+        println!("{} ({})", action, report.reason);
+        if let Some(backup) = &report.corrupt_backup {
+            println!("Corrupt database moved to {}", backup.display());
+        }
+        println!(
+            "  SQLite: {} prompts, sha256 {}",
+            report.state.sqlite_count, report.state.sqlite_sha256
+        );
+        println!(
+            "  JSONL:  {} prompts, sha256 {}",
+            report.state.jsonl_count, report.state.jsonl_sha256
+        );
+        if report.verified {
+            println!("Verified: counts and hashes match");
+        } else {
+            println!("Verification FAILED: stores still differ");
+        }
+    }
+
+    if report.verified {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Report a failed JSONL import
+fn import_failed(use_json: bool, e: anyhow::Error) -> ExitCode {
+    if use_json {
+        fail(true, "import_error", &format!("{:#}", e))
+    } else {
+        eprintln!("Error importing JSONL: {:#}", e);
+        ExitCode::FAILURE
+    }
+}
+
+/// Check the DB at `path` without writing to it
+///
+/// Corruption is a failed check rather than an error, so it gets reported;
+/// the FTS index is only compared once integrity and schema are fine.
+fn check_at(path: &Path) -> anyhow::Result<CheckOutput> {
+    let db = Database::open_read_only_at(path)?;
+    let integrity_ok = match db.integrity_check() {
+        Ok(ok) => ok,
+        Err(e) if is_corruption(&e) => false,
+        Err(e) => return Err(e),
+    };
+    let schema_version = if integrity_ok { db.schema_version()? } else { 0 };
+    let fts = if integrity_ok && schema_version == SCHEMA_VERSION {
+        Some(db.fts_consistency()?)
+    } else {
+        None
+    };
+
+    Ok(CheckOutput {
+        path: path.display().to_string(),
+        ok: fts.as_ref().is_some_and(FtsConsistency::is_consistent),
+        integrity_ok,
+        schema_version,
+        target_version: SCHEMA_VERSION,
+        fts,
+    })
+}
+
+/// Take the cross-process sync lock for a DB/JSONL write
+fn take_lock(config: &Config, use_json: bool) -> Option<LockGuard> {
+    match crate::lock::acquire_at(&sync_lock_path(), config.sync.lock_timeout()) {
+        Ok(guard) => Some(guard),
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    "{}",
+                    serde_json::json!({"error": e.code(), "message": e.to_string()})
+                );
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            None
+        }
+    }
+}
+
+fn open_db(use_json: bool) -> Option<Database> {
+    match Database::open() {
+        Ok(db) => Some(db),
+        Err(e) => {
+            report_error(use_json, "database_error", "opening database", e);
+            None
+        }
+    }
+}
+
+fn jsonl_path(path: Option<String>, use_json: bool) -> Option<PathBuf> {
+    let path = path.map(PathBuf::from).or_else(crate::storage::default_jsonl_path);
+    if path.is_none() {
+        if use_json {
+            fail(true, "no_home", "Could not determine home directory");
+        } else {
+            eprintln!("Error: could not determine home directory; pass a JSONL path");
+        }
+    }
+    path
+}
+
+/// Report `err` as `{"error": code, "message"}` JSON, or as
+/// `Error <context>: <err>` text
+fn report_error(use_json: bool, code: &str, context: &str, err: impl fmt::Display) -> ExitCode {
+    if use_json {
+        fail(true, code, &err.to_string())
+    } else {
+        eprintln!("Error {}: {}", context, err);
+        ExitCode::FAILURE
+    }
+}
+
+fn print_json<T: Serialize>(output: &T) -> ExitCode {
+    match serde_json::to_string_pretty(output) {
+        Ok(json) => {
+            println!("{}", json);
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(true, "serialization_error", &e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MIGRATIONS;
+    use anyhow::Result;
+    use rusqlite::Connection;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_check_reports_current_db_as_ok() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("jfp.db");
+        Database::open_at(&path)?;
+
+        let output = check_at(&path)?;
+        assert!(output.ok);
+        assert_eq!(output.schema_version, SCHEMA_VERSION);
+        assert!(output.fts.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_reports_corrupt_file_instead_of_failing() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("jfp.db");
+        fs::write(&path, b"this is not a sqlite database, just garbage bytes")?;
+
+        let output = check_at(&path)?;
+        assert!(!output.ok);
+        assert!(!output.integrity_ok);
+        assert!(output.fts.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_reports_old_schema_without_migrating() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("jfp.db");
+        {
+            let conn = Connection::open(&path)?;
+            conn.execute_batch(MIGRATIONS[0].sql)?;
+            conn.execute(
+                "INSERT INTO registry_meta (key, value) VALUES ('schema_version', '1')",
+                [],
+            )?;
+        }
+
+        let output = check_at(&path)?;
+        assert!(!output.ok);
+        assert!(output.integrity_ok);
+        assert_eq!(output.schema_version, 1);
+        assert!(output.fts.is_none());
+        // Checking must leave the file at v1
+        assert_eq!(Database::open_read_only_at(&path)?.schema_version()?, 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,368 @@
+//! Diff command implementation
+//!
+//! - diff <id> [from] [to]: unified diff between two recorded revisions
+//!   (default: the previous revision against the latest)
+//! - diff --upstream <id>: three-way view of a fork: base (upstream when
+//!   forked) against the upstream now, and base against the local copy
+//! - Prompts are compared as a metadata header plus content, so title, tag
+//!   or version changes show up alongside content edits
+
+use std::ops::Range;
+use std::process::ExitCode;
+
+use serde::Serialize;
+use similar::{DiffTag, TextDiff};
+
+use super::fail;
+use super::history::load_history;
+use crate::registry::{
+    load_forks, load_local_prompts, local_prompt_dirs, open_library, ForkState, ForkStatus,
+    RegistryStatus,
+};
+use crate::storage::PromptRevision;
+use crate::types::{Config, Prompt};
+
+#[derive(Serialize)]
+struct DiffOutput<'a> {
+    id: &'a str,
+    from: &'a PromptRevision,
+    to: &'a PromptRevision,
+    identical: bool,
+    /// Prompt fields that differ between the two revisions
+    changed: Vec<&'static str>,
+    /// Unified diff of the rendered prompts
+    diff: String,
+    #[serde(flatten)]
+    registry: &'a RegistryStatus,
+}
+
+#[derive(Serialize)]
+struct UpstreamDiffOutput<'a> {
+    #[serde(flatten)]
+    fork: &'a ForkStatus,
+    /// Fields changed from base to the upstream now
+    upstream_changed: Vec<&'static str>,
+    upstream_diff: String,
+    /// Fields changed from base to the local copy
+    local_changed: Vec<&'static str>,
+    local_diff: String,
+    /// Upstream and local edits touch the same base lines
+    conflicts: bool,
+    #[serde(flatten)]
+    registry: &'a RegistryStatus,
+}
+
+/// Render a prompt as the text that gets diffed
+pub(crate) fn prompt_document(prompt: &Prompt) -> String {
+    let mut doc = format!("title: {}\n", prompt.title);
+    let optional = [
+        ("description", &prompt.description),
+        ("category", &prompt.category),
+        ("version", &prompt.version),
+        ("author", &prompt.author),
+    ];
+    for (name, value) in optional {
+        if let Some(value) = value {
+            doc.push_str(&format!("{}: {}\n", name, value));
+        }
+    }
+    if !prompt.tags.is_empty() {
+        doc.push_str(&format!("tags: {}\n", prompt.tags.join(", ")));
+    }
+    if prompt.featured {
+        doc.push_str("featured: true\n");
+    }
+    for var in &prompt.variables {
+        doc.push_str(&format!(
+            "variable: {} ({:?}{})\n",
+            var.name,
+            var.var_type,
+            if var.required { ", required" } else { "" }
+        ));
+    }
+    doc.push_str("---\n");
+    doc.push_str(&prompt.content);
+    if !prompt.content.ends_with('\n') {
+        doc.push('\n');
+    }
+    doc
+}
+
+/// Names of the prompt fields that differ
+pub(crate) fn changed_fields(a: &Prompt, b: &Prompt) -> Vec<&'static str> {
+    let checks = [
+        ("title", a.title != b.title),
+        ("content", a.content != b.content),
+        ("description", a.description != b.description),
+        ("category", a.category != b.category),
+        ("tags", a.tags != b.tags),
+        ("variables", a.variables != b.variables),
+        ("featured", a.featured != b.featured),
+        ("version", a.version != b.version),
+        ("author", a.author != b.author),
+    ];
+    checks
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Unified diff between two prompts, labelled with `old_label`/`new_label`
+pub(crate) fn unified_diff(old: &Prompt, new: &Prompt, old_label: &str, new_label: &str) -> String {
+    let old_doc = prompt_document(old);
+    let new_doc = prompt_document(new);
+    TextDiff::from_lines(&old_doc, &new_doc)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_label, new_label)
+        .to_string()
+}
+
+/// Base line ranges replaced on the way from `base` to `new`
+fn touched_lines(base: &str, new: &str) -> Vec<Range<usize>> {
+    TextDiff::from_lines(base, new)
+        .ops()
+        .iter()
+        .filter(|op| op.tag() != DiffTag::Equal)
+        .map(|op| op.old_range())
+        .collect()
+}
+
+/// Whether both sides edited the same (or adjacent) base lines differently
+pub(crate) fn edits_conflict(base: &Prompt, upstream: &Prompt, local: &Prompt) -> bool {
+    let base = prompt_document(base);
+    let upstream = prompt_document(upstream);
+    let local = prompt_document(local);
+    if upstream == local {
+        return false;
+    }
+    let theirs = touched_lines(&base, &upstream);
+    let ours = touched_lines(&base, &local);
+    // Insertions have empty ranges, so compare inclusively
+    theirs
+        .iter()
+        .any(|a| ours.iter().any(|b| a.start <= b.end && b.start <= a.end))
+}
+
+pub fn run(
+    config: &Config,
+    id: &str,
+    from: Option<usize>,
+    to: Option<usize>,
+    use_json: bool,
+) -> ExitCode {
+    let library = match open_library(config) {
+        Ok(library) => library,
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let revisions = match load_history(&library.db, id) {
+        Ok(Some(revisions)) => revisions,
+        Ok(None) => {
+            if use_json {
+                println!(r#"{{"error": "not_found"}}"#);
+            } else {
+                eprintln!("Prompt '{}' has no history.", id);
+            }
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            if use_json {
+                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error loading history: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let latest = revisions.len();
+    let to = to.unwrap_or(latest);
+    let from = from.unwrap_or(to.saturating_sub(1).max(1));
+    let pick = |rev: usize| rev.checked_sub(1).and_then(|index| revisions.get(index));
+    let (Some(old), Some(new)) = (pick(from), pick(to)) else {
+        let message = format!(
+            "Revision must be between 1 and {} (see 'jfp history {}')",
+            latest, id
+        );
+        return fail(use_json, "invalid_revision", &message);
+    };
+
+    let diff = unified_diff(
+        &old.prompt,
+        &new.prompt,
+        &format!("{}@{}", id, old.rev),
+        &format!("{}@{}", id, new.rev),
+    );
+    let changed = changed_fields(&old.prompt, &new.prompt);
+
+    if use_json {
+        let output = DiffOutput {
+            id,
+            from: old,
+            to: new,
+            identical: old.content_hash == new.content_hash,
+            changed,
+            diff,
+            registry: &library.registry,
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else if diff.is_empty() {
+        println!("Revisions {} and {} of {} are identical.", old.rev, new.rev, id);
+    } else {
+        print!("{}", diff);
+    }
+
+    ExitCode::SUCCESS
+}
+
+pub fn upstream(config: &Config, id: &str, use_json: bool) -> ExitCode {
+    let library = match open_library(config) {
+        Ok(library) => library,
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let dirs = local_prompt_dirs(&config.local_prompts);
+    let record = load_forks(&dirs).into_iter().find(|record| record.id == id);
+    let local = load_local_prompts(&dirs)
+        .prompts
+        .into_iter()
+        .find(|prompt| prompt.id == id);
+    let (Some(record), Some(local)) = (record, local) else {
+        let message = format!("'{}' is not a fork (see 'jfp fork {}')", id, id);
+        return fail(use_json, "not_a_fork", &message);
+    };
+
+    let fork = ForkStatus::check(&record, &library.upstream);
+    // A removed upstream diffs as unchanged
+    let upstream = library
+        .upstream
+        .iter()
+        .find(|p| p.id == record.upstream_id)
+        .unwrap_or(&record.base);
+    let base = &record.base;
+
+    let output = UpstreamDiffOutput {
+        fork: &fork,
+        upstream_changed: changed_fields(base, upstream),
+        upstream_diff: unified_diff(
+            base,
+            upstream,
+            &format!("{}@base", record.upstream_id),
+            &format!("{}@upstream", record.upstream_id),
+        ),
+        local_changed: changed_fields(base, &local),
+        local_diff: unified_diff(
+            base,
+            &local,
+            &format!("{}@base", record.upstream_id),
+            &format!("{}@local", id),
+        ),
+        conflicts: edits_conflict(base, upstream, &local),
+        registry: &library.registry,
+    };
+
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        let state = match fork.state {
+            ForkState::Current => "upstream unchanged",
+            ForkState::Changed => "upstream changed",
+            ForkState::Removed => "upstream removed from the registry",
+        };
+        println!(
+            "Fork {} of {}{}: {}\n",
+            id,
+            record.upstream_id,
+            record.upstream_version.as_deref().map(|v| format!("@{}", v)).unwrap_or_default(),
+            state
+        );
+        for (title, diff) in [
+            ("Upstream changes (base -> upstream)", &output.upstream_diff),
+            ("Local changes (base -> local)", &output.local_diff),
+        ] {
+            println!("== {} ==", title);
+            if diff.is_empty() {
+                println!("(none)\n");
+            } else {
+                println!("{}", diff);
+            }
+        }
+        if output.conflicts {
+            println!("Upstream and local changes overlap; merge them by hand with 'jfp edit {}'.", id);
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_reports_metadata_and_content_changes() {
+        let old = Prompt::new("p", "Title", "line one\nline two\n");
+        let mut new = old.clone();
+        new.content = "line one\nline 2\n".to_string();
+        new.version = Some("1.1.0".to_string());
+
+        assert_eq!(changed_fields(&old, &new), ["content", "version"]);
+        let diff = unified_diff(&old, &new, "p@1", "p@2");
+        assert!(diff.starts_with("--- p@1\n+++ p@2\n"));
+        assert!(diff.contains("-line two\n+line 2\n"));
+        assert!(diff.contains("+version: 1.1.0\n"));
+        assert!(unified_diff(&old, &old, "a", "b").is_empty());
+    }
+
+    #[test]
+    fn conflicts_need_overlapping_edits() {
+        let base = Prompt::new("p", "Title", "one\ntwo\nthree\nfour\nfive\nsix\n");
+        let edit = |from: &str, to: &str| {
+            let mut prompt = base.clone();
+            prompt.content = base.content.replace(from, to);
+            prompt
+        };
+
+        let upstream = edit("one", "ONE");
+        assert!(!edits_conflict(&base, &upstream, &edit("six", "SIX")));
+        assert!(edits_conflict(&base, &upstream, &edit("one", "1")));
+        // Both sides making the same change is not a conflict
+        assert!(!edits_conflict(&base, &upstream, &upstream));
+    }
+}
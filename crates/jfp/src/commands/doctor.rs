@@ -3,17 +3,20 @@
 //! From EXISTING_JFP_STRUCTURE.md section 17 (doctor):
 //! - Runs environment diagnostics
 //! - Checks database, config, network connectivity, etc.
+//! - Reports effective registry settings with their origins
 
 use std::process::ExitCode;
 
 use serde::Serialize;
 
+use crate::config::{EffectiveValue, LoadedConfig, REGISTRY_KEYS};
 use crate::storage::Database;
 
 #[derive(Serialize)]
 struct DoctorOutput {
     checks: Vec<Check>,
     all_passed: bool,
+    registry: Vec<EffectiveValue>,
 }
 
 #[derive(Serialize)]
@@ -41,7 +44,8 @@ impl std::fmt::Display for CheckStatus {
     }
 }
 
-pub fn run(use_json: bool) -> ExitCode {
+pub fn run(loaded: &LoadedConfig, use_json: bool) -> ExitCode {
+    let registry = loaded.effective(REGISTRY_KEYS);
     let mut checks = Vec::new();
 
     // Check 1: Database
@@ -59,10 +63,20 @@ pub fn run(use_json: bool) -> ExitCode {
     // Check 5: Browser opener
     checks.push(check_browser_opener());
 
+    // Check 6: Registry config
+    checks.push(check_registry_config(loaded));
+
+    // Check 7: Search index consistency
+    checks.push(check_search_index());
+
     let all_passed = checks.iter().all(|c| c.status == CheckStatus::Pass);
 
     if use_json {
-        let output = DoctorOutput { checks, all_passed };
+        let output = DoctorOutput {
+            checks,
+            all_passed,
+            registry,
+        };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
             Err(e) => {
@@ -82,6 +96,11 @@ pub fn run(use_json: bool) -> ExitCode {
             println!("[{}] {}: {}", symbol, check.name, check.message);
         }
 
+        println!("\nRegistry settings:");
+        for entry in &registry {
+            println!("  {}: {} ({})", entry.key, entry.value, entry.origin);
+        }
+
         println!();
         if all_passed {
             println!("All checks passed!");
@@ -115,6 +134,65 @@ fn check_database() -> Check {
     }
 }
 
+fn check_search_index() -> Check {
+    let result = Database::open().and_then(|db| db.fts_consistency());
+    match result {
+        Ok(check) if check.is_consistent() => Check {
+            name: "Search Index".to_string(),
+            status: CheckStatus::Pass,
+            message: format!("{} prompts indexed", check.fts_rows),
+        },
+        Ok(check) => Check {
+            name: "Search Index".to_string(),
+            status: CheckStatus::Warn,
+            message: format!(
+                "{} missing, {} orphaned, {} stale entries (run 'jfp db reindex')",
+                check.missing.len(),
+                check.orphaned.len(),
+                check.stale.len()
+            ),
+        },
+        Err(e) => Check {
+            name: "Search Index".to_string(),
+            status: CheckStatus::Fail,
+            message: format!("Failed to check: {}", e),
+        },
+    }
+}
+
+fn check_registry_config(loaded: &LoadedConfig) -> Check {
+    let registry = &loaded.config.registry;
+    let origin = loaded.origin("registry.url");
+
+    if !registry.url.starts_with("http://") && !registry.url.starts_with("https://") {
+        return Check {
+            name: "Registry Config".to_string(),
+            status: CheckStatus::Fail,
+            message: format!("registry.url is not an HTTP(S) URL: {} ({})", registry.url, origin),
+        };
+    }
+
+    if registry.timeout_ms == 0 {
+        return Check {
+            name: "Registry Config".to_string(),
+            status: CheckStatus::Warn,
+            message: format!(
+                "registry.timeoutMs is 0 ({}); remote refresh will always time out",
+                loaded.origin("registry.timeoutMs")
+            ),
+        };
+    }
+
+    Check {
+        name: "Registry Config".to_string(),
+        status: CheckStatus::Pass,
+        message: format!(
+            "{} ({}), ttl {}s, timeout {}ms, autoRefresh {}",
+            registry.url, origin, registry.cache_ttl, registry.timeout_ms, registry.auto_refresh
+        ),
+    }
+}
+
 fn check_bundled_prompts() -> Check {
     let prompts = crate::registry::bundled_prompts();
     if prompts.is_empty() {
@@ -0,0 +1,353 @@
+//! New/edit command implementation
+//!
+//! - new [id] [--from <id>]: scaffold `<id>.md` in `localPrompts.dir`
+//!   (optionally copied from an existing prompt) and open it in the editor
+//! - edit <id>: open the file defining a local prompt in the editor
+//! - The editor is `$VISUAL`, then `$EDITOR`, then `vi`
+//! - On save the file is validated; errors are appended as `# jfp error:`
+//!   lines and the editor reopens. Valid files are written back and
+//!   re-indexed into SQLite and FTS through the library bootstrap
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+use serde::Serialize;
+use tempfile::TempDir;
+use thiserror::Error;
+
+use super::export::build_safe_export_filename;
+use super::{check_writable, fail};
+use crate::lock;
+use crate::registry::{
+    load_local_prompts, local_prompt_dirs, open_library, read_prompt_file, to_markdown,
+    RegistryStatus,
+};
+use crate::types::{Config, Prompt};
+
+/// Prefix of the validation error lines added for the next editor session
+const ERROR_MARKER: &str = "# jfp error: ";
+
+/// Id used by `jfp new` without an id or --from
+const DEFAULT_ID: &str = "new-prompt";
+
+#[derive(Serialize)]
+struct EditOutput {
+    /// `created`, `updated` or `unchanged`
+    action: &'static str,
+    ids: Vec<String>,
+    file: String,
+    #[serde(flatten)]
+    registry: RegistryStatus,
+}
+
+#[derive(Debug, Error)]
+enum EditError {
+    #[error("{0}")]
+    Editor(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl EditError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Editor(_) => "editor_error",
+            Self::Invalid(_) => "invalid_prompt",
+            Self::Io(_) => "write_error",
+        }
+    }
+}
+
+pub fn new_prompt(config: &Config, id: Option<String>, from: Option<String>, use_json: bool) -> ExitCode {
+    if let Some(code) = check_writable(config, use_json) {
+        return code;
+    }
+    let dir = &config.local_prompts.dir;
+
+    let mut prompt = match &from {
+        Some(from) => {
+            let library = match open_library(config) {
+                Ok(library) => library,
+                Err(e) => {
+                    return fail(
+                        use_json,
+                        crate::lock::error_code(&e, "database_error"),
+                        &e.to_string(),
+                    );
+                }
+            };
+            match library.db.get_prompt(from) {
+                Ok(Some(prompt)) => prompt,
+                Ok(None) => {
+                    return fail(use_json, "not_found", &format!("Prompt '{}' not found", from));
+                }
+                Err(e) => return fail(use_json, "database_error", &e.to_string()),
+            }
+        }
+        None => Prompt::new(
+            DEFAULT_ID,
+            "Untitled prompt",
+            "Write the prompt here. Use {{NAME}} for values filled in at render time.",
+        ),
+    };
+    prompt.id = match id.or(from) {
+        Some(id) => id,
+        None => unused_id(dir),
+    };
+    prompt.is_local = true;
+    prompt.saved_at = None;
+
+    let target = match build_safe_export_filename(&prompt.id, "md") {
+        Ok(name) => dir.join(name),
+        Err(e) => return fail(use_json, "invalid_prompt_id", &e),
+    };
+    if target.exists() {
+        return fail(
+            use_json,
+            "already_exists",
+            &format!("{} exists; use 'jfp edit {}'", target.display(), prompt.id),
+        );
+    }
+
+    run_editor(config, &target, &to_markdown(&prompt), "created", use_json)
+}
+
+pub fn edit(config: &Config, id: &str, use_json: bool) -> ExitCode {
+    if let Some(code) = check_writable(config, use_json) {
+        return code;
+    }
+
+    let local = load_local_prompts(&local_prompt_dirs(&config.local_prompts));
+    let Some(target) = local.files.get(id) else {
+        let is_registry = open_library(config)
+            .and_then(|library| library.db.get_prompt(id))
+            .is_ok_and(|prompt| prompt.is_some());
+        return if is_registry {
+            fail(
+                use_json,
+                "not_local",
+                &format!("'{}' is not a local prompt; use 'jfp new --from {}'", id, id),
+            )
+        } else {
+            fail(use_json, "not_found", &format!("Local prompt '{}' not found", id))
+        };
+    };
+
+    match fs::read_to_string(target) {
+        Ok(original) => run_editor(config, target, &original, "updated", use_json),
+        Err(e) => fail(use_json, "read_error", &format!("{}: {}", target.display(), e)),
+    }
+}
+
+/// Edit `initial` in a scratch copy of `target`, then save and re-index
+fn run_editor(config: &Config, target: &Path, initial: &str, action: &'static str, use_json: bool) -> ExitCode {
+    // Removed when `_scratch_dir` drops
+    let (_scratch_dir, scratch) = match scratch_path(target) {
+        Ok(scratch) => scratch,
+        Err(e) => return fail(use_json, "write_error", &e.to_string()),
+    };
+    let edited = fs::write(&scratch, initial)
+        .map_err(EditError::from)
+        .and_then(|()| edit_until_valid(&scratch, &editor_command()))
+        .and_then(|prompts| Ok((fs::read_to_string(&scratch)?, prompts)));
+    let (text, prompts) = match edited {
+        Ok(edited) => edited,
+        Err(e) => return fail(use_json, e.code(), &e.to_string()),
+    };
+
+    let action = if action == "updated" && text == initial {
+        "unchanged"
+    } else {
+        if let Err(e) = save(target, &text) {
+            return fail(use_json, "write_error", &format!("{}: {}", target.display(), e));
+        }
+        action
+    };
+    // The bootstrap sees a changed dir and rewrites the local rows
+    let library = match open_library(config) {
+        Ok(library) => library,
+        Err(e) => {
+            return fail(use_json, crate::lock::error_code(&e, "database_error"), &e.to_string());
+        }
+    };
+
+    let output = EditOutput {
+        action,
+        ids: prompts.into_iter().map(|p| p.id).collect(),
+        file: target.display().to_string(),
+        registry: library.registry,
+    };
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else if output.action == "unchanged" {
+        println!("No changes to {}", output.file);
+    } else {
+        println!("Saved {} ({})", output.file, output.ids.join(", "));
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Open `path` in `editor` until it holds valid prompts
+///
+/// Leaving the file unchanged after an error gives up with that error, so a
+/// non-interactive editor cannot loop forever.
+fn edit_until_valid(path: &Path, editor: &str) -> Result<Vec<Prompt>, EditError> {
+    let mut last_error = None;
+    loop {
+        let before = fs::read_to_string(path)?;
+        launch_editor(editor, path)?;
+        let after = fs::read_to_string(path)?;
+        if let Some(error) = last_error.take()
+            && after == before
+        {
+            return Err(EditError::Invalid(error));
+        }
+
+        let text = strip_errors(&after);
+        fs::write(path, &text)?;
+        match read_prompt_file(path) {
+            Ok(prompts) => return Ok(prompts),
+            Err(error) => {
+                fs::write(path, append_errors(&text, &error))?;
+                last_error = Some(error);
+            }
+        }
+    }
+}
+
+/// `text` with `error` appended as a block of marker lines
+fn append_errors(text: &str, error: &str) -> String {
+    let mut out = format!("{}\n", text.trim_end());
+    for line in error.lines() {
+        out.push_str(ERROR_MARKER);
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Drop the trailing block of error lines added by the previous round;
+/// marker-like lines elsewhere in the file are the user's
+fn strip_errors(text: &str) -> String {
+    let mut lines: Vec<&str> = text.lines().collect();
+    while lines.last().is_some_and(|line| line.starts_with(ERROR_MARKER) || line.trim().is_empty()) {
+        lines.pop();
+    }
+    let mut kept = lines.join("\n");
+    kept.push('\n');
+    kept
+}
+
+/// `$VISUAL`, then `$EDITOR`, then `vi`
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Run `editor` (a program plus optional arguments) on `path`
+fn launch_editor(editor: &str, path: &Path) -> Result<(), EditError> {
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| EditError::Editor(format!("failed to start '{}': {}", editor, e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(EditError::Editor(format!("'{}' exited with {}", editor, status)))
+    }
+}
+
+/// Scratch file with the same name as `target` in a private temp dir, so a
+/// frontmatter file without `id` still defaults to the right one
+fn scratch_path(target: &Path) -> io::Result<(TempDir, PathBuf)> {
+    let dir = tempfile::Builder::new().prefix("jfp-edit-").tempdir()?;
+    let path = dir.path().join(target.file_name().unwrap_or_default());
+    Ok((dir, path))
+}
+
+/// Atomically replace `target` with `text`
+fn save(target: &Path, text: &str) -> io::Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = lock::temp_path(target);
+    fs::write(&temp, text)?;
+    fs::rename(&temp, target).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+/// First of `new-prompt`, `new-prompt-2`, ... without a file in `dir`
+fn unused_id(dir: &Path) -> String {
+    (1..)
+        .map(|n| match n {
+            1 => DEFAULT_ID.to_string(),
+            n => format!("{}-{}", DEFAULT_ID, n),
+        })
+        .find(|id| !dir.join(format!("{}.md", id)).exists())
+        .unwrap_or_else(|| DEFAULT_ID.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_invalid_file_gets_error_lines_until_fixed() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("broken.md");
+        fs::write(&path, "---\ntitle: [oops\n---\nbody\n")?;
+
+        // `true` leaves the file as is, so the second round gives up
+        let err = edit_until_valid(&path, "true").unwrap_err();
+        assert_eq!(err.code(), "invalid_prompt");
+        assert!(err.to_string().starts_with("line 2: invalid frontmatter"));
+        let text = fs::read_to_string(&path)?;
+        assert!(text.ends_with(&format!("{}{}\n", ERROR_MARKER, err)));
+
+        // A fixed file is accepted and the error lines are dropped
+        let fixed = text.replace("[oops", "Fixed");
+        fs::write(&path, &fixed)?;
+        let prompts = edit_until_valid(&path, "true")?;
+        assert_eq!(prompts[0].id, "broken");
+        assert_eq!(fs::read_to_string(&path)?, "---\ntitle: Fixed\n---\nbody\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_only_the_trailing_error_block_is_stripped() {
+        let body = format!("---\ntitle: T\n---\n{}kept on purpose\nbody\n", ERROR_MARKER);
+        let marked = append_errors(&body, "line 2: first\nsecond line");
+        assert!(marked.ends_with(&format!("{}second line\n", ERROR_MARKER)));
+        assert_eq!(strip_errors(&marked), body);
+        assert_eq!(strip_errors(&body), body);
+    }
+
+    #[test]
+    fn test_failing_editor_aborts() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("p.md");
+        fs::write(&path, "---\ntitle: T\n---\nbody\n")?;
+        let err = edit_until_valid(&path, "false").unwrap_err();
+        assert_eq!(err.code(), "editor_error");
+        Ok(())
+    }
+}
@@ -11,9 +11,8 @@ use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
-use crate::types::Prompt;
+use crate::registry::{open_library, RegistryStatus};
+use crate::types::{Config, Prompt};
 
 #[derive(Serialize)]
 struct ExportOutput {
@@ -22,6 +21,8 @@ struct ExportOutput {
     format: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     output_dir: Option<String>,
+    #[serde(flatten)]
+    registry: RegistryStatus,
 }
 
 #[derive(Serialize)]
@@ -31,7 +32,7 @@ struct ExportedPrompt {
     file: Option<String>,
 }
 
-fn build_safe_export_filename(prompt_id: &str, ext: &str) -> Result<String, String> {
+pub(crate) fn build_safe_export_filename(prompt_id: &str, ext: &str) -> Result<String, String> {
     let id = prompt_id.trim();
 
     if id.is_empty() {
@@ -95,6 +96,7 @@ fn validate_export_target_path(path: &Path, canonical_dir: &Path) -> Result<(),
 }
 
 pub fn run(
+    config: &Config,
     ids: Vec<String>,
     format: &str,
     output_dir: Option<String>,
@@ -111,27 +113,23 @@ pub fn run(
         return ExitCode::FAILURE;
     }
 
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open the library (DB bootstrapped from the registry loader)
+    let library = match open_library(config) {
+        Ok(library) => library,
         Err(e) => {
             if use_json {
-                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
             } else {
                 eprintln!("Error opening database: {}", e);
             }
             return ExitCode::FAILURE;
         }
     };
-
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    let db = &library.db;
 
     // Get prompts to export
     let prompts: Vec<Prompt> = if ids.is_empty() || (ids.len() == 1 && ids[0] == "all") {
@@ -293,6 +291,7 @@ pub fn run(
             exported,
             format: format.to_string(),
             output_dir,
+            registry: library.registry.clone(),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -0,0 +1,115 @@
+//! Fork command implementation
+//!
+//! - fork <id> [--as <new id>]: copy a registry prompt into
+//!   `localPrompts.dir` as `<id>.md` (overriding the registry copy unless
+//!   renamed) and record its upstream id, version and content hash
+//! - `jfp status`, `jfp list --forks` and `jfp diff --upstream` report drift
+
+use std::fs;
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use super::{check_writable, fail};
+use super::export::build_safe_export_filename;
+use crate::registry::{open_library, save_fork, to_markdown, ForkRecord, RegistryStatus};
+use crate::types::Config;
+
+#[derive(Serialize)]
+struct ForkOutput<'a> {
+    id: &'a str,
+    upstream_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upstream_version: Option<&'a str>,
+    upstream_hash: &'a str,
+    file: String,
+    #[serde(flatten)]
+    registry: &'a RegistryStatus,
+}
+
+pub fn run(config: &Config, id: &str, local_id: Option<String>, use_json: bool) -> ExitCode {
+    if let Some(code) = check_writable(config, use_json) {
+        return code;
+    }
+
+    let library = match open_library(config) {
+        Ok(library) => library,
+        Err(e) => {
+            return fail(use_json, crate::lock::error_code(&e, "database_error"), &e.to_string());
+        }
+    };
+    let Some(upstream) = library.upstream.iter().find(|p| p.id == id) else {
+        let is_local = matches!(library.db.get_prompt(id), Ok(Some(p)) if p.is_local);
+        return if is_local {
+            fail(use_json, "not_registry", &format!("'{}' is a local prompt, not a registry prompt", id))
+        } else {
+            fail(use_json, "not_found", &format!("Prompt '{}' not found", id))
+        };
+    };
+
+    let local_id = local_id.unwrap_or_else(|| id.to_string());
+    let dir = &config.local_prompts.dir;
+    let target = match build_safe_export_filename(&local_id, "md") {
+        Ok(name) => dir.join(name),
+        Err(e) => return fail(use_json, "invalid_prompt_id", &e),
+    };
+    if target.exists() {
+        return fail(
+            use_json,
+            "already_exists",
+            &format!("{} exists; use 'jfp edit {}'", target.display(), local_id),
+        );
+    }
+
+    let record = match ForkRecord::new(&local_id, upstream) {
+        Ok(record) => record,
+        Err(e) => return fail(use_json, "fork_error", &e.to_string()),
+    };
+    let mut copy = upstream.clone();
+    copy.id = local_id.clone();
+    copy.is_local = true;
+
+    let written = fs::create_dir_all(dir)
+        .and_then(|()| fs::write(&target, to_markdown(&copy)))
+        .map_err(anyhow::Error::from)
+        .and_then(|()| save_fork(dir, &record));
+    if let Err(e) = written {
+        return fail(use_json, "write_error", &format!("{}: {}", target.display(), e));
+    }
+    drop(library);
+    // Index the copy now rather than on the next read
+    let library = match open_library(config) {
+        Ok(library) => library,
+        Err(e) => {
+            return fail(use_json, crate::lock::error_code(&e, "database_error"), &e.to_string());
+        }
+    };
+
+    let output = ForkOutput {
+        id: &record.id,
+        upstream_id: &record.upstream_id,
+        upstream_version: record.upstream_version.as_deref(),
+        upstream_hash: &record.upstream_hash,
+        file: target.display().to_string(),
+        registry: &library.registry,
+    };
+    if use_json {
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        println!(
+            "Forked {}{} to {}",
+            output.upstream_id,
+            output.upstream_version.map(|v| format!("@{}", v)).unwrap_or_default(),
+            output.file
+        );
+        println!("Edit it with 'jfp edit {}'", output.id);
+    }
+
+    ExitCode::SUCCESS
+}
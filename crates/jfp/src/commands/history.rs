@@ -0,0 +1,146 @@
+//! History command implementation
+//!
+//! - history <id>: every recorded revision of a prompt, oldest first
+//! - A revision is appended whenever a refresh or local edit changes the
+//!   prompt's content hash (content and metadata, including `version`)
+
+use std::process::ExitCode;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::registry::{open_library, RegistryStatus};
+use crate::storage::{revision_hash, Database, PromptRevision};
+use crate::types::Config;
+
+#[derive(Serialize)]
+struct HistoryOutput {
+    id: String,
+    revisions: Vec<HistoryEntry>,
+    count: usize,
+    #[serde(flatten)]
+    registry: RegistryStatus,
+}
+
+#[derive(Serialize)]
+struct HistoryEntry {
+    #[serde(flatten)]
+    revision: PromptRevision,
+    title: String,
+    /// Matches the prompt as currently stored
+    current: bool,
+}
+
+/// Revisions of `id`, or `None` when neither history nor the prompt exists
+///
+/// A prompt stored before history was kept, and not written since, is
+/// reported as a single unrecorded revision.
+pub(crate) fn load_history(db: &Database, id: &str) -> Result<Option<Vec<PromptRevision>>> {
+    let revisions = db.prompt_revisions(id)?;
+    if !revisions.is_empty() {
+        return Ok(Some(revisions));
+    }
+
+    let Some(prompt) = db.get_prompt(id)? else {
+        return Ok(None);
+    };
+    Ok(Some(vec![PromptRevision {
+        rev: 1,
+        content_hash: revision_hash(&prompt)?,
+        version: prompt.version.clone(),
+        source: if prompt.is_local { "local" } else { "registry" }.to_string(),
+        recorded_at: None,
+        prompt,
+    }]))
+}
+
+pub fn run(config: &Config, id: &str, use_json: bool) -> ExitCode {
+    let library = match open_library(config) {
+        Ok(library) => library,
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+    let db = &library.db;
+
+    let loaded = load_history(db, id).and_then(|revisions| {
+        let current_hash = db.get_prompt(id)?.map(|p| revision_hash(&p)).transpose()?;
+        Ok(revisions.map(|revisions| (revisions, current_hash)))
+    });
+    let (revisions, current_hash) = match loaded {
+        Ok(Some(loaded)) => loaded,
+        Ok(None) => {
+            if use_json {
+                println!(r#"{{"error": "not_found"}}"#);
+            } else {
+                eprintln!("Prompt '{}' has no history.", id);
+            }
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            if use_json {
+                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+            } else {
+                eprintln!("Error loading history: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Only the newest revision with the stored hash counts as current
+    let current_index = revisions
+        .iter()
+        .rposition(|r| current_hash.as_deref() == Some(r.content_hash.as_str()));
+    let entries: Vec<HistoryEntry> = revisions
+        .into_iter()
+        .enumerate()
+        .map(|(index, revision)| HistoryEntry {
+            title: revision.prompt.title.clone(),
+            current: current_index == Some(index),
+            revision,
+        })
+        .collect();
+
+    if use_json {
+        let output = HistoryOutput {
+            id: id.to_string(),
+            count: entries.len(),
+            revisions: entries,
+            registry: library.registry.clone(),
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        println!("History of {} ({} revisions):\n", id, entries.len());
+        for entry in &entries {
+            let revision = &entry.revision;
+            println!(
+                "{} {:>3}  {}  {:<8}  {:<8}  {}  {}",
+                if entry.current { "*" } else { " " },
+                revision.rev,
+                &revision.content_hash[..12],
+                revision.version.as_deref().unwrap_or("-"),
+                revision.source,
+                revision.recorded_at.as_deref().unwrap_or("(before history)"),
+                entry.title
+            );
+        }
+        println!("\nUse 'jfp diff {} <rev> [rev]' to compare revisions", id);
+    }
+
+    ExitCode::SUCCESS
+}
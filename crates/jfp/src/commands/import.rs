@@ -0,0 +1,212 @@
+//! Import command implementation
+//!
+//! - import <path>: convert a markdown file, or a directory of them, into
+//!   local prompts under `localPrompts.dir`
+//! - Accepts frontmatter prompts and `export --format skill` output (e.g.
+//!   `<name>/SKILL.md`); directories are searched recursively
+//! - Each prompt is written as `<id>.md` with YAML frontmatter; existing
+//!   files are kept unless --force
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use super::export::build_safe_export_filename;
+use super::{check_writable, fail};
+use crate::registry::{parse_markdown_prompt, to_markdown, validate_prompt};
+use crate::types::{Config, Prompt};
+
+#[derive(Serialize)]
+struct ImportOutput {
+    imported: Vec<ImportedPrompt>,
+    skipped: Vec<ImportedPrompt>,
+    errors: Vec<ImportError>,
+    count: usize,
+    dir: String,
+}
+
+#[derive(Serialize)]
+struct ImportedPrompt {
+    id: String,
+    source: String,
+    file: String,
+}
+
+#[derive(Serialize)]
+struct ImportError {
+    source: String,
+    message: String,
+}
+
+pub fn run(config: &Config, path: &str, force: bool, use_json: bool) -> ExitCode {
+    if let Some(code) = check_writable(config, use_json) {
+        return code;
+    }
+
+    let source = Path::new(path);
+    let files = if source.is_dir() {
+        let mut files = Vec::new();
+        if let Err(e) = collect_markdown(source, &mut files) {
+            return fail(use_json, "read_error", &format!("{}: {}", source.display(), e));
+        }
+        files
+    } else if source.is_file() {
+        vec![source.to_path_buf()]
+    } else {
+        return fail(use_json, "not_found", &format!("{} does not exist", source.display()));
+    };
+
+    let dir = &config.local_prompts.dir;
+    if let Err(e) = fs::create_dir_all(dir) {
+        return fail(use_json, "mkdir_error", &format!("{}: {}", dir.display(), e));
+    }
+
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+
+    for file in &files {
+        let source = file.display().to_string();
+        let mut error = |message: String| {
+            errors.push(ImportError {
+                source: source.clone(),
+                message,
+            })
+        };
+
+        let prompt = match read_prompt(file) {
+            Ok(prompt) => prompt,
+            Err(e) => {
+                error(e);
+                continue;
+            }
+        };
+        if !seen.insert(prompt.id.clone()) {
+            error(format!("duplicate prompt id '{}'", prompt.id));
+            continue;
+        }
+        let target = match build_safe_export_filename(&prompt.id, "md") {
+            Ok(name) => dir.join(name),
+            Err(e) => {
+                error(e);
+                continue;
+            }
+        };
+
+        let entry = ImportedPrompt {
+            id: prompt.id.clone(),
+            source: source.clone(),
+            file: target.display().to_string(),
+        };
+        if target.exists() && !force {
+            skipped.push(entry);
+            continue;
+        }
+        match fs::write(&target, to_markdown(&prompt)) {
+            Ok(()) => imported.push(entry),
+            Err(e) => error(format!("{}: {}", target.display(), e)),
+        }
+    }
+
+    let failed = imported.is_empty() && skipped.is_empty() && !errors.is_empty();
+
+    if use_json {
+        let output = ImportOutput {
+            count: imported.len(),
+            imported,
+            skipped,
+            errors,
+            dir: dir.display().to_string(),
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        for entry in &imported {
+            println!("Imported: {} -> {}", entry.id, entry.file);
+        }
+        for entry in &skipped {
+            println!("Skipped: {} ({} exists; use --force to overwrite)", entry.id, entry.file);
+        }
+        for error in &errors {
+            eprintln!("Error: {}: {}", error.source, error.message);
+        }
+        if files.is_empty() {
+            println!("No markdown files found in {}", source.display());
+        } else {
+            println!("\nImported {} prompt(s) into {}", imported.len(), dir.display());
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Parse and validate one markdown file
+fn read_prompt(path: &Path) -> Result<Prompt, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse_markdown_prompt(&text, &default_id(path))
+        .map_err(|e| e.to_string())
+        .and_then(validate_prompt)
+}
+
+/// Id for frontmatter without one: the file stem, or the directory name
+/// for `<name>/SKILL.md`
+fn default_id(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default();
+    let name = if stem.eq_ignore_ascii_case("skill") {
+        path.parent()
+            .and_then(Path::file_name)
+            .unwrap_or(stem)
+    } else {
+        stem
+    };
+    name.to_string_lossy().into_owned()
+}
+
+/// `*.md` files under `dir`, depth-first in name order; hidden entries skipped
+fn collect_markdown(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            !path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_markdown(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_id_uses_skill_directory_name() {
+        assert_eq!(default_id(Path::new("skills/code-review/SKILL.md")), "code-review");
+        assert_eq!(default_id(Path::new("prompts/idea-wizard.md")), "idea-wizard");
+    }
+}
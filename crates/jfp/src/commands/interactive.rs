@@ -1,13 +1,13 @@
 //! Interactive mode fallback implementation.
 //!
-//! This provides a simple searchable terminal picker for bundled prompts
+//! This provides a simple searchable terminal picker over the local library
 //! without requiring the full Phase-5 TUI stack.
 
 use std::io::{self, IsTerminal, Write};
 use std::process::ExitCode;
 
-use crate::registry::bundled_prompts;
-use crate::types::Prompt;
+use crate::registry::open_library;
+use crate::types::{Config, Prompt};
 
 fn prompt_line(prompt: &str) -> io::Result<String> {
     print!("{prompt}");
@@ -71,7 +71,7 @@ fn render_prompt_details(prompt: &Prompt) -> String {
     )
 }
 
-pub fn run(use_json: bool) -> ExitCode {
+pub fn run(config: &Config, use_json: bool) -> ExitCode {
     if use_json {
         println!(
             r#"{{"error":"interactive_requires_tty","message":"Interactive mode requires a terminal and cannot be used with --json"}}"#
@@ -85,7 +85,20 @@ pub fn run(use_json: bool) -> ExitCode {
         return ExitCode::FAILURE;
     }
 
-    let mut prompts = bundled_prompts();
+    let library = match open_library(config) {
+        Ok(library) => library,
+        Err(e) => {
+            eprintln!("Error opening database: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut prompts = match library.db.list_prompts() {
+        Ok(prompts) => prompts,
+        Err(e) => {
+            eprintln!("Error listing prompts: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
     if prompts.is_empty() {
         eprintln!("No prompts available.");
         return ExitCode::FAILURE;
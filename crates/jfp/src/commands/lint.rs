@@ -0,0 +1,429 @@
+//! Lint command implementation
+//!
+//! - lint [paths...]: check prompt files (`*.json`, `*.md`; directories are
+//!   searched recursively), including ids repeated across files
+//! - lint [--local]: check the prompts in the library (local ones only with
+//!   --local)
+//! - --format text|json|sarif; SARIF 2.1.0 is for code-scanning uploads
+//! - Exits non-zero when any finding is an error, so CI can gate on it
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use super::fail;
+use crate::lint::{lint_prompt, Finding, Severity, DUPLICATE_ID, PARSE_ERROR, RULES};
+use crate::registry::{
+    is_bundle_file, load_local_prompts, local_prompt_dirs, open_library, parse_markdown_prompt,
+    parse_prompt_file, RegistryStatus,
+};
+use crate::types::{Config, Prompt};
+
+/// A finding and where it was found
+#[derive(Serialize)]
+struct Located {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(flatten)]
+    finding: Finding,
+}
+
+#[derive(Serialize)]
+struct LintOutput {
+    findings: Vec<Located>,
+    errors: usize,
+    warnings: usize,
+    infos: usize,
+    /// Number of prompts checked
+    checked: usize,
+    /// Set when linting the library rather than files
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    registry: Option<RegistryStatus>,
+}
+
+#[derive(Default)]
+struct Linter {
+    findings: Vec<Located>,
+    checked: usize,
+    /// First file defining each id, for `duplicate-id`
+    seen: HashMap<String, String>,
+    /// File contents, for line numbers
+    texts: HashMap<PathBuf, String>,
+    registry: Option<RegistryStatus>,
+}
+
+impl Linter {
+    fn prompt(&mut self, prompt: &Prompt, file: Option<&Path>) {
+        self.checked += 1;
+        for finding in lint_prompt(prompt) {
+            self.push(finding, Some(&prompt.id), file);
+        }
+    }
+
+    fn push(&mut self, finding: Finding, id: Option<&str>, file: Option<&Path>) {
+        let line = file.and_then(|file| {
+            // Point at what the finding is about, else at the prompt's id
+            let id_line = id.map(|id| {
+                if is_markdown(file) {
+                    format!("id: {}", id)
+                } else {
+                    format!("\"{}\"", id)
+                }
+            });
+            let text = self.text(file);
+            finding
+                .needle
+                .as_deref()
+                .and_then(|needle| line_of(text, needle))
+                .or_else(|| id_line.and_then(|id| line_of(text, &id)))
+        });
+        self.push_at(finding, id, file, line);
+    }
+
+    fn push_at(&mut self, finding: Finding, id: Option<&str>, file: Option<&Path>, line: Option<usize>) {
+        self.findings.push(Located {
+            file: file.map(|file| file.display().to_string()),
+            line,
+            id: id.map(str::to_string),
+            finding,
+        });
+    }
+
+    /// Contents of `file`, read once
+    fn text(&mut self, file: &Path) -> &str {
+        self.texts
+            .entry(file.to_path_buf())
+            .or_insert_with(|| fs::read_to_string(file).unwrap_or_default())
+    }
+
+    fn file(&mut self, path: &Path) {
+        let entries = match fs::read_to_string(path) {
+            // Frontmatter errors carry their own line
+            Ok(text) if is_markdown(path) => {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let parsed = parse_markdown_prompt(&text, &stem);
+                self.texts.insert(path.to_path_buf(), text);
+                match parsed {
+                    Ok(prompt) => vec![Ok(prompt)],
+                    Err(e) => {
+                        let line = Some(e.line);
+                        self.push_at(Finding::new(PARSE_ERROR, e.to_string()), None, Some(path), line);
+                        return;
+                    }
+                }
+            }
+            _ => match parse_prompt_file(path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    self.push(Finding::new(PARSE_ERROR, e), None, Some(path));
+                    return;
+                }
+            },
+        };
+        let file = path.display().to_string();
+        for entry in entries {
+            let prompt = match entry {
+                Ok(prompt) => prompt,
+                Err(e) => {
+                    self.push(Finding::new(PARSE_ERROR, e), None, Some(path));
+                    continue;
+                }
+            };
+            if let Some(first) = self.seen.get(&prompt.id) {
+                let message = format!("id '{}' is also defined in {}", prompt.id, first);
+                self.push(Finding::new(DUPLICATE_ID, message), Some(&prompt.id), Some(path));
+            } else {
+                self.seen.insert(prompt.id.clone(), file.clone());
+            }
+            self.prompt(&prompt, Some(path));
+        }
+    }
+
+    fn output(self) -> LintOutput {
+        let count = |severity| self.findings.iter().filter(|f| f.finding.severity == severity).count();
+        LintOutput {
+            errors: count(Severity::Error),
+            warnings: count(Severity::Warning),
+            infos: count(Severity::Info),
+            checked: self.checked,
+            findings: self.findings,
+            registry: self.registry,
+        }
+    }
+}
+
+pub fn run(config: &Config, paths: Vec<String>, local: bool, format: Option<String>, use_json: bool) -> ExitCode {
+    let format = format.unwrap_or_else(|| if use_json { "json" } else { "text" }.to_string());
+    if !["text", "json", "sarif"].contains(&format.as_str()) {
+        return fail(
+            use_json,
+            "invalid_format",
+            &format!("Unknown format '{}' (expected text, json or sarif)", format),
+        );
+    }
+
+    let mut linter = Linter::default();
+    if paths.is_empty() {
+        let library = match open_library(config) {
+            Ok(library) => library,
+            Err(e) => {
+                return fail(use_json, crate::lock::error_code(&e, "database_error"), &e.to_string());
+            }
+        };
+        let mut prompts = match library.db.list_prompts() {
+            Ok(prompts) => prompts,
+            Err(e) => return fail(use_json, "database_error", &e.to_string()),
+        };
+        if local {
+            prompts.retain(|p| p.is_local);
+        }
+        linter.registry = Some(library.registry.clone());
+        let files = load_local_prompts(&local_prompt_dirs(&config.local_prompts)).files;
+        for prompt in &prompts {
+            let file = files.get(&prompt.id).filter(|_| prompt.is_local);
+            linter.prompt(prompt, file.map(PathBuf::as_path));
+        }
+    } else {
+        for path in &paths {
+            let path = Path::new(path);
+            let mut files = Vec::new();
+            if path.is_dir() {
+                if let Err(e) = collect_prompt_files(path, &mut files) {
+                    return fail(use_json, "read_error", &format!("{}: {}", path.display(), e));
+                }
+            } else if path.exists() {
+                files.push(path.to_path_buf());
+            } else {
+                return fail(use_json, "not_found", &format!("{} does not exist", path.display()));
+            }
+            for file in files {
+                linter.file(&file);
+            }
+        }
+    }
+
+    let output = linter.output();
+    let failed = output.errors > 0;
+    match format.as_str() {
+        "text" => print_text(&output),
+        "sarif" => {
+            if let Some(code) = print_json(&sarif(&output)) {
+                return code;
+            }
+        }
+        _ => {
+            if let Some(code) = print_json(&output) {
+                return code;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn print_text(output: &LintOutput) {
+    for located in &output.findings {
+        let mut location = match (&located.file, located.line) {
+            (Some(file), Some(line)) => format!("{}:{}", file, line),
+            (Some(file), None) => file.clone(),
+            (None, _) => String::new(),
+        };
+        if let Some(id) = &located.id {
+            location = if location.is_empty() {
+                id.clone()
+            } else {
+                format!("{} ({})", location, id)
+            };
+        }
+        println!(
+            "{}: {}[{}]: {}",
+            location,
+            located.finding.severity.as_str(),
+            located.finding.rule,
+            located.finding.message
+        );
+    }
+    if !output.findings.is_empty() {
+        println!();
+    }
+    println!(
+        "Checked {} prompt(s): {} error(s), {} warning(s), {} info",
+        output.checked, output.errors, output.warnings, output.infos
+    );
+}
+
+fn print_json<T: Serialize>(value: &T) -> Option<ExitCode> {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            println!("{}", json);
+            None
+        }
+        Err(e) => {
+            eprintln!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
+            Some(ExitCode::FAILURE)
+        }
+    }
+}
+
+/// SARIF 2.1.0 log with one run
+fn sarif(output: &LintOutput) -> serde_json::Value {
+    let rules: Vec<serde_json::Value> = RULES
+        .iter()
+        .map(|rule| {
+            serde_json::json!({
+                "id": rule.id,
+                "shortDescription": {"text": rule.description},
+                "defaultConfiguration": {"level": rule.severity.sarif_level()},
+            })
+        })
+        .collect();
+
+    let results: Vec<serde_json::Value> = output
+        .findings
+        .iter()
+        .map(|located| {
+            let mut result = serde_json::json!({
+                "ruleId": located.finding.rule,
+                "ruleIndex": RULES.iter().position(|rule| rule.id == located.finding.rule),
+                "level": located.finding.severity.sarif_level(),
+                "message": {"text": located.finding.message},
+            });
+            let mut location = serde_json::Map::new();
+            if let Some(file) = &located.file {
+                let mut physical = serde_json::json!({
+                    "artifactLocation": {"uri": file.replace('\\', "/")},
+                });
+                if let Some(line) = located.line {
+                    physical["region"] = serde_json::json!({"startLine": line});
+                }
+                location.insert("physicalLocation".to_string(), physical);
+            }
+            if let Some(id) = &located.id {
+                location.insert(
+                    "logicalLocations".to_string(),
+                    serde_json::json!([{"name": id, "kind": "object"}]),
+                );
+            }
+            if !location.is_empty() {
+                result["locations"] = serde_json::json!([location]);
+            }
+            result
+        })
+        .collect();
+
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "jfp",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+}
+
+/// 1-based line of the first occurrence of `needle` in `text`
+fn line_of(text: &str, needle: &str) -> Option<usize> {
+    text.find(needle)
+        .map(|offset| text[..offset].matches('\n').count() + 1)
+}
+
+/// `*.json` and `*.md` files under `dir`, depth-first in name order; hidden
+/// entries and bundle files skipped
+fn collect_prompt_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            !path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_prompt_files(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "json" || ext == "md")
+            && !is_bundle_file(&path)
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_files_get_line_numbers_and_duplicate_ids() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let a = dir.path().join("a.md");
+        fs::write(&a, "---\ntitle: A\ntags: [Go]\n---\nUse {{LANG}}\n")?;
+        let b = dir.path().join("b.json");
+        fs::write(&b, r#"[{"id": "a", "title": "B", "content": "x"}, {"title": 1}]"#)?;
+
+        let mut linter = Linter::default();
+        linter.file(&a);
+        linter.file(&b);
+        let output = linter.output();
+        assert_eq!(output.checked, 2);
+
+        let find = |rule: &str| output.findings.iter().find(|f| f.finding.rule == rule).unwrap();
+        assert_eq!(find("tag-format").line, Some(3));
+        assert_eq!(find("undeclared-placeholder").line, Some(5));
+        assert_eq!(find("duplicate-id").file.as_deref(), Some(b.display().to_string().as_str()));
+        assert_eq!(find("parse-error").finding.message.split(':').next(), Some("invalid entry 2"));
+        assert_eq!(output.errors, 2);
+
+        let log = sarif(&output);
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), output.findings.len());
+        assert_eq!(results[0]["locations"][0]["logicalLocations"][0]["name"], "a");
+        Ok(())
+    }
+    #[test]
+    fn test_markdown_findings_point_at_frontmatter_lines() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let broken = dir.path().join("broken.md");
+        fs::write(&broken, "---\ntitle: B\ntags: 5\n---\nbody\n")?;
+        let plain = dir.path().join("plain.md");
+        fs::write(&plain, "---\ntitle: P\nid: plain-id\n---\nbody\n")?;
+
+        let mut linter = Linter::default();
+        linter.file(&broken);
+        linter.file(&plain);
+        let output = linter.output();
+
+        let find = |rule: &str| output.findings.iter().find(|f| f.finding.rule == rule).unwrap();
+        assert_eq!(find("parse-error").line, Some(3));
+        // Findings without a needle fall back to the `id:` line
+        assert_eq!(find("recommended-fields").line, Some(3));
+        Ok(())
+    }
+}
@@ -2,15 +2,17 @@
 //!
 //! From EXISTING_JFP_STRUCTURE.md section 10 (list):
 //! - Options: --category, --tag, --mine, --saved, --json
+//! - --local: only prompts from the local prompts dirs (and other local
+//!   prompts)
+//! - --forks: only forks of registry prompts, with their upstream state
 //! - JSON output: { prompts, count, offline?, offlineAge? }
 
 use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
-use crate::types::PromptSummary;
+use crate::registry::{fork_statuses, local_prompt_dirs, open_library, ForkState, ForkStatus, RegistryStatus};
+use crate::types::{Config, PromptSummary};
 
 /// JSON output for list command
 #[derive(Serialize)]
@@ -18,41 +20,40 @@ struct ListOutput {
     prompts: Vec<PromptSummary>,
     count: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
-    source: Option<String>,
+    forks: Option<Vec<ForkStatus>>,
+    #[serde(flatten)]
+    registry: RegistryStatus,
 }
 
 pub fn run(
+    config: &Config,
     category: Option<String>,
     tag: Option<String>,
     featured: bool,
+    local: bool,
+    forks: bool,
     use_json: bool,
 ) -> ExitCode {
-    // Try to open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open the library (DB bootstrapped from the registry loader)
+    let library = match open_library(config) {
+        Ok(library) => library,
         Err(e) => {
             if use_json {
-                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
             } else {
                 eprintln!("Error opening database: {}", e);
             }
             return ExitCode::FAILURE;
         }
     };
-
-    // Check if database has prompts, if not, seed with bundled
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            if let Err(e) = db.upsert_prompt(prompt) {
-                eprintln!("Warning: Failed to seed prompt {}: {}", prompt.id, e);
-            }
-        }
-    }
+    let db = &library.db;
 
     // List prompts with filters
-    let prompts = match db.list_prompts_filtered(
+    let mut prompts = match db.list_prompts_filtered(
         category.as_deref(),
         tag.as_deref(),
         featured,
@@ -68,13 +69,23 @@ pub fn run(
         }
     };
 
+    if local {
+        prompts.retain(|p| p.is_local);
+    }
+    let forks = forks.then(|| {
+        fork_statuses(&local_prompt_dirs(&config.local_prompts), &library.upstream)
+    });
+    if let Some(forks) = &forks {
+        prompts.retain(|p| p.is_local && forks.iter().any(|fork| fork.id == p.id));
+    }
     let count = prompts.len();
 
     if use_json {
         let output = ListOutput {
             prompts: prompts.iter().map(PromptSummary::from).collect(),
             count,
-            source: Some("local".to_string()),
+            forks,
+            registry: library.registry.clone(),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -86,7 +97,7 @@ pub fn run(
     } else {
         if prompts.is_empty() {
             println!("No prompts found.");
-            if category.is_some() || tag.is_some() || featured {
+            if category.is_some() || tag.is_some() || featured || local || forks.is_some() {
                 println!("Try different filters or run without filters.");
             }
         } else {
@@ -97,6 +108,17 @@ pub fn run(
                 if prompt.featured {
                     print!(" [featured]");
                 }
+                let fork = forks
+                    .iter()
+                    .flatten()
+                    .find(|fork| fork.id == prompt.id);
+                match fork.map(|fork| fork.state) {
+                    Some(ForkState::Changed) => print!(" [fork: upstream changed]"),
+                    Some(ForkState::Removed) => print!(" [fork: upstream removed]"),
+                    Some(ForkState::Current) => print!(" [fork]"),
+                    None if prompt.is_local => print!(" [local]"),
+                    None => {}
+                }
                 println!();
 
                 if let Some(desc) = &prompt.description {
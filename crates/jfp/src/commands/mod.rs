@@ -1,14 +1,25 @@
 //! Command implementations
 
+use std::process::ExitCode;
+
+use crate::types::Config;
+
 pub mod about;
 pub mod bundles;
 pub mod categories;
 pub mod completion;
 pub mod config;
 pub mod copy;
+pub mod db;
+pub mod diff;
 pub mod doctor;
+pub mod edit;
 pub mod export;
+pub mod fork;
+pub mod history;
+pub mod import;
 pub mod interactive;
+pub mod lint;
 pub mod list;
 pub mod open;
 pub mod random;
@@ -20,3 +31,38 @@ pub mod status;
 pub mod suggest;
 pub mod tags;
 pub mod update_cli;
+
+/// Report a command error on stderr (as `{"error", "message"}` JSON with
+/// `use_json`) and fail
+pub(crate) fn fail(use_json: bool, code: &str, message: &str) -> ExitCode {
+    if use_json {
+        eprintln!("{}", serde_json::json!({"error": code, "message": message}));
+    } else {
+        eprintln!("Error: {}", message);
+    }
+    ExitCode::FAILURE
+}
+
+/// Fail under `--read-only` (`database.readOnly`), naming `what` would have
+/// been changed
+pub(crate) fn check_read_only(config: &Config, use_json: bool, what: &str) -> Option<ExitCode> {
+    config.database.read_only.then(|| {
+        fail(use_json, "read_only", &format!("{} cannot be changed with --read-only", what))
+    })
+}
+
+/// Fail unless local prompts can be written: not `--read-only` and local
+/// prompts enabled
+pub(crate) fn check_writable(config: &Config, use_json: bool) -> Option<ExitCode> {
+    if let Some(code) = check_read_only(config, use_json, "Local prompts") {
+        return Some(code);
+    }
+    if !config.local_prompts.enabled {
+        return Some(fail(
+            use_json,
+            "local_prompts_disabled",
+            "Local prompts are disabled (set localPrompts.enabled = true)",
+        ));
+    }
+    None
+}
@@ -8,37 +8,35 @@ use std::process::{Command, ExitCode};
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::registry::{open_library, RegistryStatus};
+use crate::types::Config;
 
 #[derive(Serialize)]
 struct OpenOutput {
     url: String,
     opened: bool,
+    #[serde(flatten)]
+    registry: RegistryStatus,
 }
 
-pub fn run(id: &str, use_json: bool) -> ExitCode {
-    // Open database and verify prompt exists
-    let db = match Database::open() {
-        Ok(db) => db,
+pub fn run(config: &Config, id: &str, use_json: bool) -> ExitCode {
+    // Open the library (DB bootstrapped from the registry loader)
+    let library = match open_library(config) {
+        Ok(library) => library,
         Err(e) => {
             if use_json {
-                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
             } else {
                 eprintln!("Error opening database: {}", e);
             }
             return ExitCode::FAILURE;
         }
     };
-
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    let db = &library.db;
 
     // Check if prompt exists
     let prompt = match db.get_prompt(id) {
@@ -80,6 +78,7 @@ pub fn run(id: &str, use_json: bool) -> ExitCode {
         let output = OpenOutput {
             url: url.clone(),
             opened,
+            registry: library.registry.clone(),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
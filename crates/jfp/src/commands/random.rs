@@ -11,9 +11,8 @@ use std::process::ExitCode;
 use rand::prelude::IndexedRandom;
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
-use crate::types::Prompt;
+use crate::registry::{open_library, RegistryStatus};
+use crate::types::{Config, Prompt};
 
 #[derive(Serialize)]
 struct RandomOutput {
@@ -28,6 +27,8 @@ struct RandomOutput {
     content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     copied: Option<bool>,
+    #[serde(flatten)]
+    registry: Option<RegistryStatus>,
 }
 
 impl From<&Prompt> for RandomOutput {
@@ -40,37 +41,35 @@ impl From<&Prompt> for RandomOutput {
             tags: p.tags.clone(),
             content: p.content.clone(),
             copied: None,
+            registry: None,
         }
     }
 }
 
 pub fn run(
+    config: &Config,
     category: Option<String>,
     tag: Option<String>,
     copy: bool,
     use_json: bool,
 ) -> ExitCode {
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open the library (DB bootstrapped from the registry loader)
+    let library = match open_library(config) {
+        Ok(library) => library,
         Err(e) => {
             if use_json {
-                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
             } else {
                 eprintln!("Error opening database: {}", e);
             }
             return ExitCode::FAILURE;
         }
     };
-
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    let db = &library.db;
 
     // Get filtered prompts
     let prompts = match db.list_prompts_filtered(
@@ -128,6 +127,7 @@ pub fn run(
     if use_json {
         let mut output = RandomOutput::from(prompt);
         output.copied = copied;
+        output.registry = Some(library.registry.clone());
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
             Err(e) => {
@@ -3,57 +3,96 @@
 //! From EXISTING_JFP_STRUCTURE.md section 14 (refresh):
 //! - Refreshes local registry cache from remote
 //! - Falls back to bundled prompts if network fails
+//! - Syncs the DB transactionally: prompts removed upstream are pruned,
+//!   local prompts are left alone
+//! - `--dry-run` previews the sync without writing (the DB is opened
+//!   read-only); `--diff` lists the ids
+//! - Runs under the cross-process sync lock (`sync.lockTimeoutMs`)
+//! - Fails under `--read-only`, except for `--dry-run`
 
 use std::process::ExitCode;
 
 use chrono::Utc;
 use serde::Serialize;
 
-use crate::registry::{bundled_prompts, RegistryLoader};
-use crate::storage::Database;
-use crate::types::RegistrySource;
+use super::{check_read_only, fail};
+use crate::registry::{bundled_registry, import_registry, RegistryLoader};
+use crate::storage::{db_path, Database, SyncDiff};
+use crate::types::{Config, RegistryLoadResult, RegistrySource};
 
 #[derive(Serialize)]
 struct RefreshOutput {
     refreshed: bool,
+    dry_run: bool,
     prompt_count: usize,
     source: String,
+    diff: SyncDiff,
     #[serde(skip_serializing_if = "Option::is_none")]
     message: Option<String>,
 }
 
-pub fn run(use_json: bool) -> ExitCode {
-    // Open database
-    let db = match Database::open() {
+pub fn run(config: &Config, dry_run: bool, show_diff: bool, use_json: bool) -> ExitCode {
+    if !dry_run && let Some(code) = check_read_only(config, use_json, "The registry") {
+        return code;
+    }
+    // A dry run must not create, migrate or write the DB: plan against it
+    // read-only, or against an empty DB when there is none yet
+    let opened = if !dry_run {
+        Database::open()
+    } else if db_path().exists() {
+        Database::open_read_only()
+    } else {
+        Database::in_memory()
+    };
+    let db = match opened {
         Ok(db) => db,
         Err(e) => {
             if use_json {
-                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
-            } else {
-                eprintln!("Error opening database: {}", e);
+                return fail(true, "database_error", &e.to_string());
             }
+            eprintln!("Error opening database: {}", e);
             return ExitCode::FAILURE;
         }
     };
 
-    let loader = RegistryLoader::new();
-    let refresh = loader.refresh();
+    let loader =
+        RegistryLoader::from_config(&config.registry).with_lock_timeout(config.sync.lock_timeout());
 
-    let (prompts, source, message) = match refresh {
+    // Held across the fetch, cache write and DB sync so concurrent
+    // refreshes run one after another; a dry run writes nothing
+    let _lock = if dry_run {
+        None
+    } else {
+        match loader.lock() {
+            Ok(guard) => Some(guard),
+            Err(e) => {
+                if use_json {
+                    eprintln!(
+                    "{}",
+                    serde_json::json!({"error": e.code(), "message": e.to_string()})
+                );
+                } else {
+                    eprintln!("Error: {}", e);
+                }
+                return ExitCode::FAILURE;
+            }
+        }
+    };
+
+    let refresh = if dry_run { loader.preview() } else { loader.refresh() };
+
+    let (result, message) = match refresh {
         Ok(result) => {
-            let source = match result.source {
-                RegistrySource::Remote => "remote",
-                RegistrySource::Cache => "cache",
-                RegistrySource::Bundled => "bundled",
-                RegistrySource::Local => "local",
-            };
             let message = (result.source == RegistrySource::Cache && result.stale)
                 .then_some("Remote refresh failed; using cached registry".to_string());
-            (result.registry.prompts, source.to_string(), message)
+            (result, message)
         }
         Err(e) => (
-            bundled_prompts(),
-            "bundled".to_string(),
+            RegistryLoadResult {
+                registry: bundled_registry(),
+                source: RegistrySource::Bundled,
+                stale: false,
+            },
             Some(format!(
                 "Remote refresh failed; loaded bundled prompts instead ({})",
                 e
@@ -61,36 +100,73 @@ pub fn run(use_json: bool) -> ExitCode {
         ),
     };
 
-    let mut loaded_count = 0;
+    let source = match result.source {
+        RegistrySource::Remote => "remote",
+        RegistrySource::Cache => "cache",
+        RegistrySource::Bundled => "bundled",
+        RegistrySource::Local => "local",
+    }
+    .to_string();
 
-    for prompt in &prompts {
-        if db.upsert_prompt(prompt).is_ok() {
-            loaded_count += 1;
+    // Sync through the same path as the bootstrap so `data_version` tracks
+    // the refreshed registry and read commands don't re-import it.
+    let synced = if dry_run {
+        db.plan_sync(&result.registry.prompts, result.source != RegistrySource::Bundled)
+    } else {
+        import_registry(&db, &result)
+    };
+    let diff = match synced {
+        Ok(diff) => diff,
+        Err(e) => {
+            if use_json {
+                return fail(true, "database_error", &e.to_string());
+            }
+            eprintln!("Error importing registry: {}", e);
+            return ExitCode::FAILURE;
         }
-    }
+    };
 
-    // Update sync timestamp
-    let _ = db.set_meta("last_sync", &Utc::now().to_rfc3339());
+    if !dry_run {
+        // Update sync timestamp
+        let _ = db.set_meta("last_sync", &Utc::now().to_rfc3339());
+    }
 
-    let prompt_count = db.prompt_count().unwrap_or(loaded_count);
+    let prompt_count = if dry_run {
+        result.registry.prompts.len()
+    } else {
+        db.prompt_count().unwrap_or(result.registry.prompts.len())
+    };
 
     if use_json {
         let output = RefreshOutput {
-            refreshed: true,
+            refreshed: !dry_run,
+            dry_run,
             prompt_count,
             source,
+            diff,
             message,
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
-            Err(e) => {
-                eprintln!(r#"{{"error": "serialization_error", "message": "{}"}}"#, e);
-                return ExitCode::FAILURE;
-            }
+            Err(e) => return fail(true, "serialization_error", &e.to_string()),
         }
     } else {
-        println!("Refreshed registry with {} prompts", prompt_count);
+        if dry_run {
+            println!("Dry run: registry has {} prompts (nothing written)", prompt_count);
+        } else {
+            println!("Refreshed registry with {} prompts", prompt_count);
+        }
         println!("Source: {}", source);
+        println!(
+            "Changes: {} added, {} changed, {} removed, {} unchanged",
+            diff.added.len(),
+            diff.changed.len(),
+            diff.removed.len(),
+            diff.unchanged
+        );
+        if show_diff {
+            print_diff(&diff);
+        }
         if let Some(message) = message {
             println!("{}", message);
         }
@@ -98,3 +174,15 @@ pub fn run(use_json: bool) -> ExitCode {
 
     ExitCode::SUCCESS
 }
+
+/// Print the ids behind each change, `git status --short` style
+fn print_diff(diff: &SyncDiff) {
+    for (marker, ids) in [("+", &diff.added), ("~", &diff.changed), ("-", &diff.removed)] {
+        for id in ids {
+            println!("  {} {}", marker, id);
+        }
+    }
+    for id in &diff.local_conflicts {
+        println!("  ! {} (local prompt kept)", id);
+    }
+}
@@ -3,61 +3,116 @@
 //! From EXISTING_JFP_STRUCTURE.md section 9 (render):
 //! - Renders prompt with variable substitution
 //! - Optional --fill for interactive substitution
-//! - Optional --context for file-based context
+//! - Optional --context for file-based variable values
+//! - Optional raw-text context from --stdin or --context-file (stdin wins),
+//!   capped at --max-context bytes and appended as a `## Context` section
 
-use std::collections::HashMap;
-use std::fs;
-use std::io::{self, Write};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read};
 use std::process::ExitCode;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
-use crate::types::Prompt;
+use super::fail;
+use crate::registry::{open_library, RegistryStatus};
+use crate::template::{ask_stdin, cli_values, expand_home, DynamicDefaults, load_context_file, ResolvedValue, Resolver};
+use crate::types::Config;
+
+/// Default --max-context (200KB)
+pub const DEFAULT_MAX_CONTEXT: usize = 204800;
+
+/// Upper bound on --max-context, to bound memory use
+const MAX_CONTEXT_CAP: usize = 10_000_000;
+
+/// How long --stdin waits for input
+const STDIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Render flags beyond the prompt id
+pub struct RenderOptions {
+    pub fill: bool,
+    /// JSON/TOML file of variable values
+    pub context: Option<String>,
+    /// `NAME=VALUE` from --var and --NAME=VALUE
+    pub vars: Vec<String>,
+    pub no_dynamic: bool,
+    /// Read raw-text context from stdin
+    pub stdin: bool,
+    /// Read raw-text context from this file
+    pub context_file: Option<String>,
+    pub max_context: usize,
+}
+
+/// Raw-text context appended to the rendered prompt
+struct Context {
+    text: String,
+    info: ContextInfo,
+}
+
+#[derive(Serialize)]
+struct ContextInfo {
+    /// `stdin` or the file path
+    source: String,
+    truncated: bool,
+    /// Bytes of context included
+    bytes: usize,
+}
 
 #[derive(Serialize)]
 struct RenderOutput {
     id: String,
     title: String,
     rendered: String,
+    /// Values given with --var NAME=VALUE or --NAME=VALUE
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    variables: BTreeMap<String, String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    filled_variables: Option<Vec<FilledVariable>>,
-}
-
-#[derive(Serialize)]
-struct FilledVariable {
-    name: String,
-    value: String,
+    filled_variables: Option<Vec<ResolvedValue>>,
+    /// Placeholders left in `rendered` for lack of a value
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unresolved: Vec<String>,
+    /// Placeholders the prompt does not declare as variables
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    undeclared: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context: Option<ContextInfo>,
+    #[serde(flatten)]
+    registry: RegistryStatus,
 }
 
-pub fn run(
-    id: &str,
-    fill: bool,
-    context: Option<String>,
-    use_json: bool,
-) -> ExitCode {
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+pub fn run(config: &Config, id: &str, options: RenderOptions, use_json: bool) -> ExitCode {
+    let cli_values = match cli_values(&options.vars) {
+        Ok(values) => values,
         Err(e) => {
             if use_json {
-                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                eprintln!("{}", e.to_json());
             } else {
-                eprintln!("Error opening database: {}", e);
+                eprintln!("Error: {}", e);
             }
             return ExitCode::FAILURE;
         }
     };
 
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
+    // Open the library (DB bootstrapped from the registry loader)
+    let library = match open_library(config) {
+        Ok(library) => library,
+        Err(e) => {
+            if use_json {
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
+            } else {
+                eprintln!("Error opening database: {}", e);
+            }
+            return ExitCode::FAILURE;
         }
-    }
+    };
+    let db = &library.db;
 
     // Get prompt
     let prompt = match db.get_prompt(id) {
@@ -80,10 +135,16 @@ pub fn run(
         }
     };
 
-    // Load context file if provided
-    let context_values: HashMap<String, String> = if let Some(path) = &context {
+    let mut resolver = Resolver::new(&prompt)
+        .with_cli(cli_values.clone())
+        .with_env(std::env::vars())
+        .with_config(&config.variables);
+    if !options.no_dynamic && let Some(dynamic) = DynamicDefaults::current() {
+        resolver = resolver.with_dynamic(dynamic);
+    }
+    if let Some(path) = &options.context {
         match load_context_file(path) {
-            Ok(ctx) => ctx,
+            Ok(ctx) => resolver = resolver.with_context(ctx),
             Err(e) => {
                 if use_json {
                     eprintln!(r#"{{"error": "context_error", "message": "{}"}}"#, e);
@@ -93,40 +154,52 @@ pub fn run(
                 return ExitCode::FAILURE;
             }
         }
-    } else {
-        HashMap::new()
-    };
-
-    // Process content
-    let (rendered, filled_variables) = if fill && !prompt.variables.is_empty() {
-        fill_variables(&prompt, &context_values, use_json)
-    } else if !context_values.is_empty() {
-        // Just apply context values without interactive fill
-        let mut content = prompt.content.clone();
-        let mut filled = Vec::new();
+    }
+    // Only ask when someone is there to answer
+    if options.fill && !use_json && atty::is(atty::Stream::Stdin) {
+        resolver = resolver.with_ask(Box::new(ask_stdin));
+    }
 
-        for (name, value) in &context_values {
-            let placeholder = format!("{{{{{}}}}}", name);
-            if content.contains(&placeholder) {
-                content = content.replace(&placeholder, value);
-                filled.push(FilledVariable {
-                    name: name.clone(),
-                    value: value.clone(),
-                });
+    let rendered = match resolver.render() {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            if use_json {
+                eprintln!("{}", e.to_json());
+            } else {
+                eprintln!("Error: {}", e);
             }
+            return e.exit_code();
         }
+    };
 
-        (content, if filled.is_empty() { None } else { Some(filled) })
+    let max_context = options.max_context.min(MAX_CONTEXT_CAP);
+    let context = if options.stdin {
+        read_stdin_context(max_context)
+    } else if let Some(path) = &options.context_file {
+        read_file_context(path, max_context)
     } else {
-        (prompt.content.clone(), None)
+        Ok(None)
+    };
+    let context = match context {
+        Ok(context) => context.filter(|context| !context.text.is_empty()),
+        Err((code, message)) => return fail(use_json, code, &message),
     };
+    let mut content = rendered.content;
+    if let Some(context) = &context {
+        append_context(&mut content, context, max_context);
+    }
 
     if use_json {
         let output = RenderOutput {
             id: prompt.id.clone(),
             title: prompt.title.clone(),
-            rendered,
-            filled_variables,
+            rendered: content,
+            variables: cli_values,
+            filled_variables: Some(rendered.values).filter(|values| !values.is_empty()),
+            unresolved: rendered.unresolved,
+            undeclared: rendered.undeclared,
+            context: context.map(|context| context.info),
+            registry: library.registry.clone(),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -136,128 +209,106 @@ pub fn run(
             }
         }
     } else {
-        println!("{}", rendered);
+        println!("{}", content);
+        if !rendered.unresolved.is_empty() {
+            eprintln!("Warning: no value for {}", rendered.unresolved.join(", "));
+        }
     }
 
     ExitCode::SUCCESS
 }
 
-/// Load context from a JSON or TOML file
-fn load_context_file(path: &str) -> Result<HashMap<String, String>, String> {
-    let content = fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read context file: {}", e))?;
-
-    // Try JSON first
-    if path.ends_with(".json") {
-        let map: HashMap<String, serde_json::Value> = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-        return Ok(map.into_iter()
-            .map(|(k, v)| (k, value_to_string(&v)))
-            .collect());
-    }
-
-    // Try TOML
-    if path.ends_with(".toml") {
-        let map: HashMap<String, toml::Value> = toml::from_str(&content)
-            .map_err(|e| format!("Failed to parse TOML: {}", e))?;
-        return Ok(map.into_iter()
-            .map(|(k, v)| (k, toml_value_to_string(&v)))
-            .collect());
-    }
-
-    // Default: try JSON
-    match serde_json::from_str::<HashMap<String, serde_json::Value>>(&content) {
-        Ok(map) => Ok(map.into_iter()
-            .map(|(k, v)| (k, value_to_string(&v)))
-            .collect()),
+/// Read stdin up to `max + 1024` bytes, giving up after `STDIN_TIMEOUT`
+fn read_stdin_context(max: usize) -> Result<Option<Context>, (&'static str, String)> {
+    let limit = (max + 1024) as u64;
+    let (sender, receiver) = mpsc::channel();
+    // Left running on timeout; the process exits right after
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let read = io::stdin().take(limit).read_to_end(&mut bytes).map(|_| bytes);
+        let _ = sender.send(read);
+    });
+    let bytes = match receiver.recv_timeout(STDIN_TIMEOUT) {
+        Ok(Ok(bytes)) => bytes,
+        Ok(Err(e)) => return Err(("read_error", format!("Failed to read stdin: {}", e))),
         Err(_) => {
-            // Try TOML
-            let map: HashMap<String, toml::Value> = toml::from_str(&content)
-                .map_err(|e| format!("Failed to parse context file: {}", e))?;
-            Ok(map.into_iter()
-                .map(|(k, v)| (k, toml_value_to_string(&v)))
-                .collect())
+            return Err((
+                "stdin_timeout",
+                format!("Timed out waiting for stdin input ({}s)", STDIN_TIMEOUT.as_secs()),
+            ));
         }
-    }
+    };
+    Ok(Some(context_from_bytes(&bytes, max, "stdin".to_string())))
 }
 
-fn value_to_string(v: &serde_json::Value) -> String {
-    match v {
-        serde_json::Value::String(s) => s.clone(),
-        _ => v.to_string(),
-    }
+/// Read up to `max + 1` bytes of the file at `path`
+fn read_file_context(path: &str, max: usize) -> Result<Option<Context>, (&'static str, String)> {
+    let file = File::open(expand_home(path)).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => ("file_not_found", format!("Context file not found: {}", path)),
+        _ => ("read_error", format!("Failed to read context file: {}", e)),
+    })?;
+    let mut bytes = Vec::new();
+    file.take(max as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| ("read_error", format!("Failed to read context file: {}", e)))?;
+    Ok(Some(context_from_bytes(&bytes, max, path.to_string())))
 }
 
-fn toml_value_to_string(v: &toml::Value) -> String {
-    match v {
-        toml::Value::String(s) => s.clone(),
-        _ => v.to_string(),
+/// At most `max` bytes of `bytes` as text, cut on a character boundary
+fn context_from_bytes(bytes: &[u8], max: usize, source: String) -> Context {
+    let truncated = bytes.len() > max;
+    let mut kept = &bytes[..bytes.len().min(max)];
+    if truncated
+        && let Err(e) = std::str::from_utf8(kept)
+        && e.error_len().is_none()
+    {
+        kept = &kept[..e.valid_up_to()];
+    }
+    let text = String::from_utf8_lossy(kept).into_owned();
+    Context {
+        info: ContextInfo {
+            source,
+            truncated,
+            bytes: text.len(),
+        },
+        text,
     }
 }
 
-/// Fill variables interactively, using context as defaults
-fn fill_variables(
-    prompt: &Prompt,
-    context: &HashMap<String, String>,
-    use_json: bool,
-) -> (String, Option<Vec<FilledVariable>>) {
-    let mut content = prompt.content.clone();
-    let mut filled = Vec::new();
-
-    // In JSON mode or non-TTY, use context values only
-    if use_json || !atty::is(atty::Stream::Stdin) {
-        for var in &prompt.variables {
-            let value = context.get(&var.name)
-                .or(var.default.as_ref())
-                .cloned()
-                .unwrap_or_default();
-
-            let placeholder = format!("{{{{{}}}}}", var.name);
-            content = content.replace(&placeholder, &value);
-
-            if !value.is_empty() {
-                filled.push(FilledVariable {
-                    name: var.name.clone(),
-                    value,
-                });
-            }
-        }
-        return (content, Some(filled));
+/// Append `context` as a `## Context` section, noting any truncation
+fn append_context(content: &mut String, context: &Context, max: usize) {
+    content.push_str("\n\n---\n\n## Context\n\n");
+    content.push_str(&context.text);
+    if context.info.truncated {
+        let note = if context.info.source == "stdin" {
+            format!("[Context truncated to {} bytes]", max)
+        } else {
+            format!("[Context from {} truncated to {} bytes]", context.info.source, max)
+        };
+        content.push_str("\n\n");
+        content.push_str(&note);
     }
+}
 
-    for var in &prompt.variables {
-        let context_default = context.get(&var.name);
-        let default = context_default.or(var.default.as_ref());
-
-        let default_hint = default
-            .map(|d| format!(" [{}]", d))
-            .unwrap_or_default();
-
-        let description = var.description.as_ref()
-            .map(|d| format!(" ({})", d))
-            .unwrap_or_default();
-
-        print!("{}{}{}: ", var.name, description, default_hint);
-        io::stdout().flush().ok();
-
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_ok() {
-            let value = input.trim();
-            let value = if value.is_empty() {
-                default.cloned().unwrap_or_default()
-            } else {
-                value.to_string()
-            };
-
-            let placeholder = format!("{{{{{}}}}}", var.name);
-            content = content.replace(&placeholder, &value);
-
-            filled.push(FilledVariable {
-                name: var.name.clone(),
-                value,
-            });
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_section_and_truncation() {
+        let mut content = "Review this".to_string();
+        let context = context_from_bytes(b"diff --git a b", 100, "stdin".to_string());
+        assert!(!context.info.truncated);
+        append_context(&mut content, &context, 100);
+        assert_eq!(content, "Review this\n\n---\n\n## Context\n\ndiff --git a b");
+
+        // The cut falls inside 'é', which is dropped whole
+        let context = context_from_bytes("abcé tail".as_bytes(), 4, "notes.txt".to_string());
+        assert!(context.info.truncated);
+        assert_eq!(context.text, "abc");
+        assert_eq!(context.info.bytes, 3);
+        let mut content = String::new();
+        append_context(&mut content, &context, 4);
+        assert!(content.ends_with("abc\n\n[Context from notes.txt truncated to 4 bytes]"));
     }
-
-    (content, Some(filled))
 }
@@ -8,9 +8,8 @@ use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
-use crate::types::PromptSummary;
+use crate::registry::{open_library, RegistryStatus};
+use crate::types::{Config, PromptSummary};
 
 /// Search result for JSON output
 #[derive(Serialize)]
@@ -27,11 +26,11 @@ struct SearchOutput {
     query: String,
     count: usize,
     authenticated: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    offline: Option<bool>,
+    #[serde(flatten)]
+    registry: RegistryStatus,
 }
 
-pub fn run(query: &str, limit: usize, use_json: bool) -> ExitCode {
+pub fn run(config: &Config, query: &str, limit: usize, use_json: bool) -> ExitCode {
     // Validate limit
     if limit == 0 || limit > 100 {
         if use_json {
@@ -52,27 +51,23 @@ pub fn run(query: &str, limit: usize, use_json: bool) -> ExitCode {
         return ExitCode::FAILURE;
     }
 
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open the library (DB bootstrapped from the registry loader)
+    let library = match open_library(config) {
+        Ok(library) => library,
         Err(e) => {
             if use_json {
-                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
             } else {
                 eprintln!("Error opening database: {}", e);
             }
             return ExitCode::FAILURE;
         }
     };
-
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    let db = &library.db;
 
     // Search using FTS5
     let results = match db.search(query, limit) {
@@ -108,7 +103,7 @@ pub fn run(query: &str, limit: usize, use_json: bool) -> ExitCode {
             query: query.to_string(),
             count: result_count,
             authenticated: false,
-            offline: None,
+            registry: library.registry.clone(),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
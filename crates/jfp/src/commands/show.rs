@@ -8,9 +8,8 @@ use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
-use crate::types::Prompt;
+use crate::registry::{open_library, RegistryStatus};
+use crate::types::{Config, Prompt};
 
 /// Full prompt output for JSON
 #[derive(Serialize)]
@@ -29,6 +28,8 @@ struct ShowOutput {
     version: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     author: Option<String>,
+    #[serde(flatten)]
+    registry: Option<RegistryStatus>,
 }
 
 impl From<&Prompt> for ShowOutput {
@@ -43,11 +44,12 @@ impl From<&Prompt> for ShowOutput {
             featured: p.featured,
             version: p.version.clone(),
             author: p.author.clone(),
+            registry: None,
         }
     }
 }
 
-pub fn run(id: &str, raw: bool, use_json: bool) -> ExitCode {
+pub fn run(config: &Config, id: &str, raw: bool, use_json: bool) -> ExitCode {
     // Validate ID
     if id.trim().is_empty() {
         if use_json {
@@ -58,27 +60,23 @@ pub fn run(id: &str, raw: bool, use_json: bool) -> ExitCode {
         return ExitCode::FAILURE;
     }
 
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open the library (DB bootstrapped from the registry loader)
+    let library = match open_library(config) {
+        Ok(library) => library,
         Err(e) => {
             if use_json {
-                println!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                println!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
             } else {
                 eprintln!("Error opening database: {}", e);
             }
             return ExitCode::FAILURE;
         }
     };
-
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    let db = &library.db;
 
     // Get prompt
     let prompt = match db.get_prompt(id) {
@@ -107,7 +105,8 @@ pub fn run(id: &str, raw: bool, use_json: bool) -> ExitCode {
         // Raw mode: just print content
         print!("{}", prompt.content);
     } else if use_json {
-        let output = ShowOutput::from(&prompt);
+        let mut output = ShowOutput::from(&prompt);
+        output.registry = Some(library.registry.clone());
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
             Err(e) => {
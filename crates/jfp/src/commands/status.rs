@@ -3,18 +3,28 @@
 //! From EXISTING_JFP_STRUCTURE.md section 14 (status):
 //! - Shows registry cache status
 //! - Shows cache freshness, prompt count, last update
+//! - Shows effective registry settings and where each one came from
+//! - Shows local prompts dirs, how many prompts they hold and any warnings
+//! - Flags forks whose upstream changed in the cached registry
 
 use std::process::ExitCode;
 
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 
+use crate::config::{EffectiveValue, LoadedConfig, REGISTRY_KEYS};
+use crate::registry::{
+    bundled_prompts, fork_statuses, load_local_prompts, local_prompt_dirs, ForkState, ForkStatus,
+    RegistryLoader,
+};
 use crate::storage::Database;
 
 #[derive(Serialize)]
 struct StatusOutput {
     database: DatabaseStatus,
     cache: CacheStatus,
+    registry: Vec<EffectiveValue>,
+    local: LocalStatus,
 }
 
 #[derive(Serialize)]
@@ -34,16 +44,30 @@ struct CacheStatus {
     source: String,
 }
 
-pub fn run(use_json: bool) -> ExitCode {
+#[derive(Serialize)]
+struct LocalStatus {
+    enabled: bool,
+    dirs: Vec<String>,
+    prompt_count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    forks: Vec<ForkStatus>,
+}
+
+pub fn run(loaded: &LoadedConfig, use_json: bool) -> ExitCode {
+    let cache_ttl = loaded.config.registry.cache_ttl as i64;
+
     // Get database status
     let db_path = crate::storage::db_path();
     let db_exists = db_path.exists();
 
     let (prompt_count, schema_version, last_sync) = if db_exists {
-        match Database::open() {
+        // Read-only: status must not migrate or seed the DB it reports on
+        match Database::open_read_only() {
             Ok(db) => {
                 let count = db.prompt_count().unwrap_or(0);
-                let version = Some(crate::storage::SCHEMA_VERSION);
+                let version = db.schema_version().ok();
                 let sync = db.get_meta("last_sync").ok();
                 (count, version, sync)
             }
@@ -53,12 +77,12 @@ pub fn run(use_json: bool) -> ExitCode {
         (0, None, None)
     };
 
-    // Determine cache staleness (> 1 hour old or no sync)
+    // Determine cache staleness (older than registry.cacheTtl or no sync)
     let stale = match &last_sync {
         Some(ts) => {
             if let Ok(dt) = ts.parse::<DateTime<Utc>>() {
                 let age = Utc::now().signed_duration_since(dt);
-                age.num_hours() >= 1
+                age.num_seconds() > cache_ttl
             } else {
                 true
             }
@@ -75,6 +99,16 @@ pub fn run(use_json: bool) -> ExitCode {
         "local".to_string()
     };
 
+    let local_config = &loaded.config.local_prompts;
+    let local_dirs = local_prompt_dirs(local_config);
+    let local_prompts = load_local_prompts(&local_dirs);
+    // Cache or bundled only: status never fetches
+    let upstream = RegistryLoader::from_config(&loaded.config.registry)
+        .with_auto_refresh(false)
+        .load()
+        .map(|result| result.registry.prompts)
+        .unwrap_or_else(|_| bundled_prompts());
+
     let output = StatusOutput {
         database: DatabaseStatus {
             path: db_path.display().to_string(),
@@ -87,6 +121,14 @@ pub fn run(use_json: bool) -> ExitCode {
             stale,
             source,
         },
+        registry: loaded.effective(REGISTRY_KEYS),
+        local: LocalStatus {
+            enabled: local_config.enabled,
+            dirs: local_dirs.iter().map(|dir| dir.display().to_string()).collect(),
+            prompt_count: local_prompts.prompts.len(),
+            warnings: local_prompts.warnings.iter().map(ToString::to_string).collect(),
+            forks: fork_statuses(&local_dirs, &upstream),
+        },
     };
 
     if use_json {
@@ -117,6 +159,46 @@ pub fn run(use_json: bool) -> ExitCode {
             println!("  Last sync: never");
         }
 
+        println!("\nRegistry:");
+        for entry in &output.registry {
+            println!("  {}: {} ({})", entry.key, entry.value, entry.origin);
+        }
+
+        println!("\nLocal prompts:");
+        if output.local.enabled {
+            for dir in &output.local.dirs {
+                println!("  Dir: {}", dir);
+            }
+            println!("  Prompts: {}", output.local.prompt_count);
+            for warning in &output.local.warnings {
+                println!("  Warning: {}", warning);
+            }
+            if !output.local.forks.is_empty() {
+                println!("  Forks: {}", output.local.forks.len());
+            }
+            for fork in &output.local.forks {
+                match fork.state {
+                    ForkState::Current => {}
+                    ForkState::Changed => println!(
+                        "  Upstream changed: {} (from {}{}; see 'jfp diff --upstream {}')",
+                        fork.id,
+                        fork.upstream_id,
+                        fork.current_version
+                            .as_deref()
+                            .map(|v| format!(", now {}", v))
+                            .unwrap_or_default(),
+                        fork.id
+                    ),
+                    ForkState::Removed => println!(
+                        "  Upstream removed: {} (from {})",
+                        fork.id, fork.upstream_id
+                    ),
+                }
+            }
+        } else {
+            println!("  Disabled");
+        }
+
         if output.cache.stale {
             println!("\nTip: Run 'jfp refresh' to update the cache");
         }
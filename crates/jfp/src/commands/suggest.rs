@@ -9,8 +9,8 @@ use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::registry::{open_library, RegistryStatus};
+use crate::types::Config;
 
 #[derive(Serialize)]
 struct SuggestOutput {
@@ -18,6 +18,8 @@ struct SuggestOutput {
     suggestions: Vec<Suggestion>,
     #[serde(skip_serializing_if = "Option::is_none")]
     semantic: Option<bool>,
+    #[serde(flatten)]
+    registry: RegistryStatus,
 }
 
 #[derive(Serialize)]
@@ -31,7 +33,7 @@ struct Suggestion {
     reason: Option<String>,
 }
 
-pub fn run(task: &str, limit: usize, semantic: bool, use_json: bool) -> ExitCode {
+pub fn run(config: &Config, task: &str, limit: usize, semantic: bool, use_json: bool) -> ExitCode {
     if semantic {
         if use_json {
             println!(r#"{{"error": "semantic_not_implemented", "message": "Semantic search not yet available"}}"#);
@@ -41,27 +43,23 @@ pub fn run(task: &str, limit: usize, semantic: bool, use_json: bool) -> ExitCode
         // Continue with keyword search
     }
 
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+    // Open the library (DB bootstrapped from the registry loader)
+    let library = match open_library(config) {
+        Ok(library) => library,
         Err(e) => {
             if use_json {
-                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
             } else {
                 eprintln!("Error opening database: {}", e);
             }
             return ExitCode::FAILURE;
         }
     };
-
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    let db = &library.db;
 
     // Search for relevant prompts using FTS5
     let results = match db.search(task, limit) {
@@ -97,6 +95,7 @@ pub fn run(task: &str, limit: usize, semantic: bool, use_json: bool) -> ExitCode
             task: task.to_string(),
             suggestions,
             semantic: if semantic { Some(false) } else { None },
+            registry: library.registry.clone(),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -7,8 +7,8 @@ use std::process::ExitCode;
 
 use serde::Serialize;
 
-use crate::registry::bundled_prompts;
-use crate::storage::Database;
+use crate::registry::{open_library, RegistryStatus};
+use crate::types::Config;
 
 #[derive(Serialize)]
 struct TagOutput {
@@ -20,30 +20,28 @@ struct TagOutput {
 struct TagsOutput {
     tags: Vec<TagOutput>,
     total: usize,
+    #[serde(flatten)]
+    registry: RegistryStatus,
 }
 
-pub fn run(use_json: bool) -> ExitCode {
-    // Open database
-    let db = match Database::open() {
-        Ok(db) => db,
+pub fn run(config: &Config, use_json: bool) -> ExitCode {
+    // Open the library (DB bootstrapped from the registry loader)
+    let library = match open_library(config) {
+        Ok(library) => library,
         Err(e) => {
             if use_json {
-                eprintln!(r#"{{"error": "database_error", "message": "{}"}}"#, e);
+                eprintln!(
+                    r#"{{"error": "{}", "message": "{}"}}"#,
+                    crate::lock::error_code(&e, "database_error"),
+                    e
+                );
             } else {
                 eprintln!("Error opening database: {}", e);
             }
             return ExitCode::FAILURE;
         }
     };
-
-    // Seed if empty
-    let count = db.prompt_count().unwrap_or(0);
-    if count == 0 {
-        let prompts = bundled_prompts();
-        for prompt in &prompts {
-            let _ = db.upsert_prompt(prompt);
-        }
-    }
+    let db = &library.db;
 
     // Get tag counts
     let tags = match db.tag_counts() {
@@ -67,6 +65,7 @@ pub fn run(use_json: bool) -> ExitCode {
                 .map(|(name, count)| TagOutput { name, count })
                 .collect(),
             total,
+            registry: library.registry.clone(),
         };
         match serde_json::to_string_pretty(&output) {
             Ok(json) => println!("{}", json),
@@ -0,0 +1,756 @@
+//! Layered config loading
+//!
+//! From EXISTING_JFP_STRUCTURE.md section 3 (Config System):
+//! - Defaults come from `Config::default()`
+//! - The user config file overrides defaults
+//! - A project `.jfp.toml` (found walking up from the cwd to the git root)
+//!   overrides the user file for the keys in `PROJECT_KEYS` and `variables`;
+//!   relative paths in it resolve against its dir
+//! - Env overrides (`JFP_REGISTRY_URL`, `JFP_CACHE_TTL`, ...) win over the file
+//! - CLI flags (`--no-color`, `--json`) win over everything
+//!
+//! Layers are merged as TOML tables keyed by the serialized (camelCase) names,
+//! so every effective value can report which layer it came from.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize, Serializer};
+use thiserror::Error;
+
+use super::config_dir;
+use crate::types::Config;
+
+/// Env overrides: variable name -> dotted config keys it sets
+const ENV_OVERRIDES: &[(&str, &[&str])] = &[
+    ("JFP_REGISTRY_URL", &["registry.url", "registry.remote"]),
+    ("JFP_CACHE_TTL", &["registry.cacheTtl"]),
+    ("JFP_REGISTRY_TIMEOUT_MS", &["registry.timeoutMs"]),
+    ("JFP_REGISTRY_AUTO_REFRESH", &["registry.autoRefresh"]),
+    ("JFP_LOCK_TIMEOUT_MS", &["sync.lockTimeoutMs"]),
+    ("JFP_READ_ONLY", &["database.readOnly"]),
+];
+
+/// Optional keys that have no value by default (so are absent from the
+/// serialized defaults) but may still be set
+const OPTIONAL_STRING_KEYS: &[&str] = &[
+    "updates.lastCheck",
+    "updates.latestKnownVersion",
+    "localPrompts.projectDir",
+];
+
+/// Path-valued keys; relative values in a project file are project-relative
+const PATH_KEYS: &[&str] = &[
+    "registry.cachePath",
+    "registry.metaPath",
+    "skills.personalDir",
+    "skills.projectDir",
+    "localPrompts.dir",
+    "localPrompts.projectDir",
+];
+
+/// Keys a project `.jfp.toml` may set (plus any `variables.*`); a cloned
+/// repo must not redirect cache paths, the user's skills dir and the like
+const PROJECT_KEYS: &[&str] = &[
+    "registry.url",
+    "skills.projectDir",
+    "skills.preferProject",
+    "localPrompts.projectDir",
+];
+
+/// Table whose keys are free-form (variable names) with string values
+const VARIABLES_SECTION: &str = "variables";
+
+/// Project config file name
+pub const PROJECT_CONFIG_FILE: &str = ".jfp.toml";
+
+/// Registry keys reported by `status` and `doctor`
+pub const REGISTRY_KEYS: &[&str] = &[
+    "registry.url",
+    "registry.cacheTtl",
+    "registry.timeoutMs",
+    "registry.autoRefresh",
+    "registry.cachePath",
+    "registry.metaPath",
+];
+
+/// Get the config file path
+pub fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// Find the nearest `.jfp.toml`, walking up from `start` and stopping at the
+/// git root (the first ancestor containing `.git`)
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidate = dir.join(PROJECT_CONFIG_FILE);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+/// Where an effective config value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigOrigin {
+    Default,
+    File(PathBuf),
+    Project(PathBuf),
+    Env(&'static str),
+    Cli(&'static str),
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "default"),
+            ConfigOrigin::File(path) => write!(f, "file:{}", path.display()),
+            ConfigOrigin::Project(path) => write!(f, "project:{}", path.display()),
+            ConfigOrigin::Env(name) => write!(f, "env:{}", name),
+            ConfigOrigin::Cli(flag) => write!(f, "cli:{}", flag),
+        }
+    }
+}
+
+impl Serialize for ConfigOrigin {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Config loading errors with stable JSON error codes
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse {}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+    #[error("unknown config key: {key}")]
+    InvalidKey { key: String },
+    #[error("invalid value for {key}: {message}")]
+    InvalidValue { key: String, message: String },
+    #[error("{key} cannot be set in project config {}", path.display())]
+    ProjectKey { path: PathBuf, key: String },
+}
+
+impl ConfigError {
+    /// Error code used in JSON output
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConfigError::Read { .. } => "read_error",
+            ConfigError::Parse { .. } => "parse_error",
+            ConfigError::InvalidKey { .. } | ConfigError::ProjectKey { .. } => "invalid_key",
+            ConfigError::InvalidValue { .. } => "invalid_value",
+        }
+    }
+}
+
+/// One effective config value with its origin
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveValue {
+    pub key: String,
+    pub value: toml::Value,
+    pub origin: ConfigOrigin,
+}
+
+/// A config value set by a command-line flag
+#[derive(Debug, Clone)]
+pub struct CliOverride {
+    pub key: &'static str,
+    pub value: toml::Value,
+    pub flag: &'static str,
+}
+
+/// Fully merged config plus per-key origins
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub config: Config,
+    table: toml::Table,
+    origins: BTreeMap<String, ConfigOrigin>,
+    project_path: Option<PathBuf>,
+}
+
+impl LoadedConfig {
+    /// The project `.jfp.toml` that was layered in, if any
+    pub fn project_path(&self) -> Option<&Path> {
+        self.project_path.as_deref()
+    }
+
+    /// Origin of a dotted key (keys no layer touched come from defaults)
+    pub fn origin(&self, key: &str) -> ConfigOrigin {
+        self.origins
+            .get(key)
+            .cloned()
+            .unwrap_or(ConfigOrigin::Default)
+    }
+
+    /// Effective value of a dotted key
+    pub fn get(&self, key: &str) -> Option<&toml::Value> {
+        get_dotted(&self.table, key)
+    }
+
+    /// The whole effective config as a TOML table
+    pub fn table(&self) -> &toml::Table {
+        &self.table
+    }
+
+    /// Every effective leaf value, in dotted-key order
+    pub fn entries(&self) -> Vec<EffectiveValue> {
+        let mut keys = Vec::new();
+        collect_leaf_keys(&self.table, "", &mut keys);
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        self.effective(&keys)
+    }
+
+    /// Effective values and origins for a set of dotted keys
+    pub fn effective(&self, keys: &[&str]) -> Vec<EffectiveValue> {
+        keys.iter()
+            .filter_map(|key| {
+                self.get(key).map(|value| EffectiveValue {
+                    key: key.to_string(),
+                    value: value.clone(),
+                    origin: self.origin(key),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Load config from the default file location, the process environment and
+/// CLI flag overrides
+pub fn load(cli: &[CliOverride]) -> Result<LoadedConfig, ConfigError> {
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| find_project_config(&cwd));
+    load_from(
+        config_path().as_deref(),
+        project.as_deref(),
+        &|name| std::env::var(name).ok(),
+        cli,
+    )
+}
+
+/// Load config from explicit user/project files, env lookup and CLI
+/// overrides (for testing)
+pub fn load_from(
+    path: Option<&Path>,
+    project: Option<&Path>,
+    env: &dyn Fn(&str) -> Option<String>,
+    cli: &[CliOverride],
+) -> Result<LoadedConfig, ConfigError> {
+    let mut table = default_table();
+    let mut origins = BTreeMap::new();
+
+    if let Some(path) = path.filter(|p| p.exists()) {
+        let file_table = read_layer(path)?;
+        let origin = ConfigOrigin::File(path.to_path_buf());
+        merge_table(&mut table, file_table, "", &origin, &mut origins);
+    }
+
+    if let Some(project) = project {
+        let mut project_table = read_layer(project)?;
+        check_project_keys(&project_table, project)?;
+        if let Some(root) = project.parent() {
+            resolve_relative_paths(&mut project_table, root);
+        }
+        let origin = ConfigOrigin::Project(project.to_path_buf());
+        merge_table(&mut table, project_table, "", &origin, &mut origins);
+    }
+
+    for (name, keys) in ENV_OVERRIDES {
+        let Some(raw) = env(name) else {
+            continue;
+        };
+        for key in *keys {
+            let value = parse_typed(key, &raw, get_dotted(&table, key))?;
+            set_dotted(&mut table, key, value);
+            origins.insert(key.to_string(), ConfigOrigin::Env(name));
+        }
+    }
+
+    // Presence-based, like the global --no-color handling.
+    for name in ["JFP_NO_COLOR", "NO_COLOR"] {
+        if env(name).is_some() {
+            set_dotted(&mut table, "output.color", toml::Value::Boolean(false));
+            origins.insert("output.color".to_string(), ConfigOrigin::Env(name));
+            break;
+        }
+    }
+
+    for flag in cli {
+        check_value(flag.key, &flag.value)?;
+        set_dotted(&mut table, flag.key, flag.value.clone());
+        origins.insert(flag.key.to_string(), ConfigOrigin::Cli(flag.flag));
+    }
+
+    let config = Config::deserialize(toml::Value::Table(table.clone())).map_err(|e| {
+        ConfigError::Parse {
+            path: path.map(Path::to_path_buf).unwrap_or_default(),
+            message: e.to_string(),
+        }
+    })?;
+
+    Ok(LoadedConfig {
+        config,
+        table,
+        origins,
+        project_path: project.map(Path::to_path_buf),
+    })
+}
+
+/// Read, parse and validate one config file layer
+fn read_layer(path: &Path) -> Result<toml::Table, ConfigError> {
+    let content = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let table = content
+        .parse::<toml::Table>()
+        .map_err(|e| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+    validate_table(&table)?;
+    Ok(table)
+}
+
+/// Reject project layer keys outside `PROJECT_KEYS` and `variables`
+fn check_project_keys(table: &toml::Table, path: &Path) -> Result<(), ConfigError> {
+    let mut keys = Vec::new();
+    collect_leaf_keys(table, "", &mut keys);
+    let allowed = |key: &str| {
+        PROJECT_KEYS.contains(&key)
+            || key
+                .strip_prefix(VARIABLES_SECTION)
+                .is_some_and(|rest| rest.starts_with('.'))
+    };
+    match keys.into_iter().find(|key| !allowed(key)) {
+        Some(key) => Err(ConfigError::ProjectKey {
+            path: path.to_path_buf(),
+            key,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Rewrite relative path values so they point inside `root`
+fn resolve_relative_paths(table: &mut toml::Table, root: &Path) {
+    for key in PATH_KEYS {
+        let Some(toml::Value::String(raw)) = get_dotted(table, key) else {
+            continue;
+        };
+        let path = Path::new(raw);
+        if path.is_relative() {
+            let resolved = root.join(path).display().to_string();
+            set_dotted(table, key, toml::Value::String(resolved));
+        }
+    }
+}
+
+/// Serialize the defaults into a TOML table (the base layer)
+fn default_table() -> toml::Table {
+    toml::Table::try_from(Config::default()).unwrap_or_default()
+}
+
+/// Template value for a known dotted key (its default, or an empty string for
+/// optional keys); `None` means the key is unknown
+pub fn key_template(key: &str) -> Option<toml::Value> {
+    if OPTIONAL_STRING_KEYS.contains(&key) {
+        return Some(toml::Value::String(String::new()));
+    }
+    if let Some(name) = key
+        .strip_prefix(VARIABLES_SECTION)
+        .and_then(|rest| rest.strip_prefix('.'))
+    {
+        return (!name.is_empty() && !name.contains('.'))
+            .then(|| toml::Value::String(String::new()));
+    }
+    match get_dotted(&default_table(), key)? {
+        toml::Value::Table(_) => None,
+        value => Some(value.clone()),
+    }
+}
+
+/// Parse a raw `jfp config set` value for a known key
+pub fn parse_value(key: &str, raw: &str) -> Result<toml::Value, ConfigError> {
+    let template = key_template(key).ok_or_else(|| ConfigError::InvalidKey {
+        key: key.to_string(),
+    })?;
+    parse_typed(key, raw, Some(&template))
+}
+
+/// Reject unknown keys and values whose type differs from the default's
+pub fn validate_table(table: &toml::Table) -> Result<(), ConfigError> {
+    let mut keys = Vec::new();
+    collect_leaf_keys(table, "", &mut keys);
+    for key in keys {
+        if let Some(value) = get_dotted(table, &key) {
+            check_value(&key, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check a single leaf value against the key's template type
+fn check_value(key: &str, value: &toml::Value) -> Result<(), ConfigError> {
+    let template = key_template(key).ok_or_else(|| ConfigError::InvalidKey {
+        key: key.to_string(),
+    })?;
+
+    let matches = match (&template, value) {
+        (toml::Value::Integer(_), toml::Value::Integer(n)) => *n >= 0,
+        (toml::Value::Float(_), toml::Value::Float(_) | toml::Value::Integer(_)) => true,
+        (template, value) => template.same_type(value),
+    };
+    if matches {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidValue {
+            key: key.to_string(),
+            message: format!("expected {}, got {}", type_name(&template), value),
+        })
+    }
+}
+
+fn type_name(value: &toml::Value) -> &'static str {
+    match value {
+        toml::Value::Integer(_) => "a non-negative integer",
+        toml::Value::Float(_) => "a number",
+        toml::Value::Boolean(_) => "true or false",
+        toml::Value::String(_) => "a string",
+        _ => value.type_str(),
+    }
+}
+
+/// Collect dotted keys of every non-table value
+fn collect_leaf_keys(table: &toml::Table, prefix: &str, keys: &mut Vec<String>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match value {
+            toml::Value::Table(child) => collect_leaf_keys(child, &key, keys),
+            _ => keys.push(key),
+        }
+    }
+}
+
+/// Merge `layer` into `base`, recording the origin of every leaf it sets
+fn merge_table(
+    base: &mut toml::Table,
+    layer: toml::Table,
+    prefix: &str,
+    origin: &ConfigOrigin,
+    origins: &mut BTreeMap<String, ConfigOrigin>,
+) {
+    for (name, value) in layer {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+
+        match (base.get_mut(&name), value) {
+            (Some(toml::Value::Table(base_child)), toml::Value::Table(layer_child)) => {
+                merge_table(base_child, layer_child, &key, origin, origins);
+            }
+            (_, value) => {
+                base.insert(name, value);
+                origins.insert(key, origin.clone());
+            }
+        }
+    }
+}
+
+/// Look up a dotted key (e.g. `registry.cacheTtl`)
+pub fn get_dotted<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let mut parts = key.split('.');
+    let mut current = table.get(parts.next()?)?;
+    for part in parts {
+        current = current.as_table()?.get(part)?;
+    }
+    Some(current)
+}
+
+/// Set a dotted key, creating intermediate tables as needed
+pub fn set_dotted(table: &mut toml::Table, key: &str, value: toml::Value) {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let Some(last) = parts.pop() else {
+        return;
+    };
+
+    let mut current = table;
+    for part in parts {
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
+        }
+        let Some(child) = entry.as_table_mut() else {
+            return;
+        };
+        current = child;
+    }
+    current.insert(last.to_string(), value);
+}
+
+/// Parse a raw string into the type of the value it replaces
+pub fn parse_typed(
+    key: &str,
+    raw: &str,
+    template: Option<&toml::Value>,
+) -> Result<toml::Value, ConfigError> {
+    let invalid = |message: &str| ConfigError::InvalidValue {
+        key: key.to_string(),
+        message: format!("{} (got {:?})", message, raw),
+    };
+
+    match template {
+        Some(toml::Value::Integer(_)) => raw
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|n| *n >= 0)
+            .map(toml::Value::Integer)
+            .ok_or_else(|| invalid("expected a non-negative integer")),
+        Some(toml::Value::Float(_)) => raw
+            .trim()
+            .parse::<f64>()
+            .map(toml::Value::Float)
+            .map_err(|_| invalid("expected a number")),
+        Some(toml::Value::Boolean(_)) => match raw.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(toml::Value::Boolean(true)),
+            "false" | "0" | "no" | "off" => Ok(toml::Value::Boolean(false)),
+            _ => Err(invalid("expected true or false")),
+        },
+        _ => Ok(toml::Value::String(raw.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_defaults_without_file_or_env() {
+        let loaded = load_from(None, None, &no_env, &[]).expect("defaults load");
+        assert_eq!(loaded.config.registry.cache_ttl, 3600);
+        assert_eq!(loaded.config.registry.timeout_ms, 2000);
+        assert_eq!(loaded.origin("registry.url"), ConfigOrigin::Default);
+    }
+
+    #[test]
+    fn test_file_overrides_defaults_with_origin() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[registry]\nurl = \"https://mirror.internal/api/prompts\"\ncacheTtl = 60\n",
+        )
+        .expect("write config");
+
+        let loaded = load_from(Some(&path), None, &no_env, &[]).expect("file load");
+        assert_eq!(
+            loaded.config.registry.url,
+            "https://mirror.internal/api/prompts"
+        );
+        assert_eq!(loaded.config.registry.cache_ttl, 60);
+        // Untouched keys in the same section keep their defaults
+        assert_eq!(loaded.config.registry.timeout_ms, 2000);
+        assert_eq!(loaded.origin("registry.url"), ConfigOrigin::File(path));
+        assert_eq!(loaded.origin("registry.timeoutMs"), ConfigOrigin::Default);
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "[registry]\ncacheTtl = 60\n").expect("write config");
+
+        let env = |name: &str| match name {
+            "JFP_REGISTRY_URL" => Some("https://env.example/api".to_string()),
+            "JFP_CACHE_TTL" => Some("120".to_string()),
+            _ => None,
+        };
+        let loaded = load_from(Some(&path), None, &env, &[]).expect("env load");
+        assert_eq!(loaded.config.registry.url, "https://env.example/api");
+        assert_eq!(loaded.config.registry.remote, "https://env.example/api");
+        assert_eq!(loaded.config.registry.cache_ttl, 120);
+        assert_eq!(
+            loaded.origin("registry.cacheTtl"),
+            ConfigOrigin::Env("JFP_CACHE_TTL")
+        );
+    }
+
+    #[test]
+    fn test_invalid_env_value_is_rejected() {
+        let env = |name: &str| (name == "JFP_CACHE_TTL").then(|| "soon".to_string());
+        let err = load_from(None, None, &env, &[]).expect_err("non-numeric ttl must fail");
+        assert_eq!(err.code(), "invalid_value");
+    }
+
+    #[test]
+    fn test_cli_overrides_env() {
+        let env = |name: &str| (name == "JFP_NO_COLOR").then(String::new);
+        let cli = [CliOverride {
+            key: "output.json",
+            value: toml::Value::Boolean(true),
+            flag: "--json",
+        }];
+        let loaded = load_from(None, None, &env, &cli).expect("cli load");
+        assert!(!loaded.config.output.color);
+        assert!(loaded.config.output.json);
+        assert_eq!(loaded.origin("output.json"), ConfigOrigin::Cli("--json"));
+        assert_eq!(loaded.origin("output.color"), ConfigOrigin::Env("JFP_NO_COLOR"));
+    }
+
+    #[test]
+    fn test_unknown_file_key_is_rejected() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "[registry]
+cacheTTL = 60
+").expect("write config");
+
+        let err = load_from(Some(&path), None, &no_env, &[]).expect_err("unknown key must fail");
+        assert_eq!(err.code(), "invalid_key");
+        assert!(err.to_string().contains("registry.cacheTTL"));
+    }
+
+    #[test]
+    fn test_wrong_file_type_is_rejected() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "[registry]\nautoRefresh = \"yes\"\n").expect("write config");
+
+        let err = load_from(Some(&path), None, &no_env, &[]).expect_err("string bool must fail");
+        assert_eq!(err.code(), "invalid_value");
+    }
+
+    #[test]
+    fn test_parse_value_checks_key_and_type() {
+        assert_eq!(
+            parse_value("registry.cacheTtl", "60").expect("integer"),
+            toml::Value::Integer(60)
+        );
+        assert_eq!(
+            parse_value("registry.autoRefresh", "false").expect("bool"),
+            toml::Value::Boolean(false)
+        );
+        assert_eq!(
+            parse_value("updates.lastCheck", "2026-01-01T00:00:00Z").expect("optional string"),
+            toml::Value::String("2026-01-01T00:00:00Z".to_string())
+        );
+        assert_eq!(
+            parse_value("registry.cacheTtl", "-5").expect_err("negative").code(),
+            "invalid_value"
+        );
+        assert_eq!(
+            parse_value("registry.nope", "1").expect_err("unknown").code(),
+            "invalid_key"
+        );
+        // Section names are not settable leaves
+        assert_eq!(parse_value("registry", "1").expect_err("section").code(), "invalid_key");
+    }
+
+    #[test]
+    fn test_project_config_found_up_to_git_root() {
+        let dir = tempdir().expect("tempdir");
+        let repo = dir.path().join("repo");
+        let nested = repo.join("crates/app/src");
+        fs::create_dir_all(&nested).expect("mkdir nested");
+        fs::create_dir_all(repo.join(".git")).expect("mkdir .git");
+
+        // Above the git root: never picked up
+        fs::write(dir.path().join(PROJECT_CONFIG_FILE), "").expect("write outer");
+        assert_eq!(find_project_config(&nested), None);
+
+        let project = repo.join(PROJECT_CONFIG_FILE);
+        fs::write(&project, "").expect("write project");
+        assert_eq!(find_project_config(&nested), Some(project));
+    }
+
+    #[test]
+    fn test_project_layer_overrides_user_file() {
+        let dir = tempdir().expect("tempdir");
+        let user = dir.path().join("config.toml");
+        fs::write(&user, "[registry]\ncacheTtl = 60\nurl = \"https://user.example/api\"\n")
+            .expect("write user config");
+        let project = dir.path().join(PROJECT_CONFIG_FILE);
+        fs::write(
+            &project,
+            "[registry]\nurl = \"https://pinned.example/api\"\n\n\
+             [skills]\nprojectDir = \"agents/skills\"\npreferProject = true\n\n\
+             [localPrompts]\nprojectDir = \"prompts\"\n\n\
+             [variables]\nLANGUAGE = \"rust\"\n",
+        )
+        .expect("write project config");
+
+        let loaded =
+            load_from(Some(&user), Some(&project), &no_env, &[]).expect("project load");
+        let config = &loaded.config;
+        assert_eq!(config.registry.url, "https://pinned.example/api");
+        assert_eq!(config.registry.cache_ttl, 60);
+        assert_eq!(config.skills.project_dir, dir.path().join("agents/skills"));
+        assert!(config.skills.prefer_project);
+        assert_eq!(
+            config.local_prompts.project_dir,
+            Some(dir.path().join("prompts"))
+        );
+        assert_eq!(config.variables.get("LANGUAGE").map(String::as_str), Some("rust"));
+
+        assert_eq!(
+            loaded.origin("registry.url"),
+            ConfigOrigin::Project(project.clone())
+        );
+        assert_eq!(loaded.origin("registry.cacheTtl"), ConfigOrigin::File(user));
+        assert_eq!(loaded.project_path(), Some(project.as_path()));
+    }
+
+    #[test]
+    fn test_project_layer_cannot_redirect_cache_or_user_dirs() {
+        let dir = tempdir().expect("tempdir");
+        let project = dir.path().join(PROJECT_CONFIG_FILE);
+        for key in [
+            "[registry]\ncachePath = \"/tmp/evil.json\"\n",
+            "[registry]\ncacheTtl = 1\n",
+            "[skills]\npersonalDir = \"/tmp/skills\"\n",
+            "[localPrompts]\ndir = \"/tmp/local\"\n",
+        ] {
+            fs::write(&project, key).expect("write project config");
+            let err = load_from(None, Some(&project), &no_env, &[])
+                .expect_err("project key must be rejected");
+            assert_eq!(err.code(), "invalid_key");
+            assert!(err.to_string().contains("cannot be set in project config"));
+        }
+    }
+
+    #[test]
+    fn test_variable_keys_are_free_form_strings() {
+        assert_eq!(
+            parse_value("variables.LANGUAGE", "rust").expect("variable"),
+            toml::Value::String("rust".to_string())
+        );
+        assert_eq!(
+            parse_value("variables.a.b", "x").expect_err("nested").code(),
+            "invalid_key"
+        );
+    }
+}
@@ -1,5 +1,9 @@
 //! Configuration management
 
+mod loader;
+
+pub use loader::*;
+
 use directories::ProjectDirs;
 use std::path::PathBuf;
 
@@ -0,0 +1,340 @@
+//! Prompt linter
+//!
+//! Rule-based checks over a single `Prompt`:
+//! - id safety (ids `export` could not write as a file name)
+//! - required fields, and recommended ones
+//! - `{{PLACEHOLDER}}` / `PromptVariable` consistency
+//! - tag normalization and duplicates differing only in case
+//! - content size
+//!
+//! Every finding carries a severity; errors are meant to fail CI.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::commands::export::build_safe_export_filename;
+use crate::template::{is_variable_name, scan};
+use crate::types::Prompt;
+
+/// Content above this size is flagged as a warning
+pub const CONTENT_WARN_BYTES: usize = 32 * 1024;
+
+/// Content above this size is an error
+pub const CONTENT_ERROR_BYTES: usize = 100 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+        }
+    }
+
+    /// SARIF `level` for this severity
+    pub fn sarif_level(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "note",
+        }
+    }
+}
+
+/// A lint rule and the severity of its findings
+#[derive(Debug, Clone, Copy)]
+pub struct Rule {
+    pub id: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+}
+
+pub const ID_SAFETY: Rule = Rule {
+    id: "id-safety",
+    severity: Severity::Error,
+    description: "Prompt ids must be a single safe file name (a-z, A-Z, 0-9, -, _, .)",
+};
+pub const REQUIRED_FIELDS: Rule = Rule {
+    id: "required-fields",
+    severity: Severity::Error,
+    description: "Prompts need a non-empty id, title and content",
+};
+pub const RECOMMENDED_FIELDS: Rule = Rule {
+    id: "recommended-fields",
+    severity: Severity::Info,
+    description: "Prompts should have a description and a category",
+};
+pub const UNDECLARED_PLACEHOLDER: Rule = Rule {
+    id: "undeclared-placeholder",
+    severity: Severity::Warning,
+    description: "Every {{PLACEHOLDER}} in the content should be a declared variable",
+};
+pub const UNUSED_VARIABLE: Rule = Rule {
+    id: "unused-variable",
+    severity: Severity::Warning,
+    description: "Declared variables should appear as {{NAME}} in the content",
+};
+pub const INVALID_VARIABLE: Rule = Rule {
+    id: "invalid-variable",
+    severity: Severity::Error,
+    description: "Variable names must match [A-Za-z][A-Za-z0-9_]* and be declared once",
+};
+pub const MALFORMED_PLACEHOLDER: Rule = Rule {
+    id: "malformed-placeholder",
+    severity: Severity::Warning,
+    description: "Text between {{ and }} should be a variable name",
+};
+pub const DUPLICATE_TAG: Rule = Rule {
+    id: "duplicate-tag",
+    severity: Severity::Warning,
+    description: "Tags should be unique, ignoring case",
+};
+pub const TAG_FORMAT: Rule = Rule {
+    id: "tag-format",
+    severity: Severity::Warning,
+    description: "Tags should be lowercase, trimmed and non-empty",
+};
+pub const CONTENT_SIZE: Rule = Rule {
+    id: "content-size",
+    severity: Severity::Warning,
+    description: "Content should stay under 32 KiB (100 KiB is an error)",
+};
+pub const DUPLICATE_ID: Rule = Rule {
+    id: "duplicate-id",
+    severity: Severity::Error,
+    description: "Prompt ids must be unique across the linted files",
+};
+pub const PARSE_ERROR: Rule = Rule {
+    id: "parse-error",
+    severity: Severity::Error,
+    description: "Prompt files must parse as JSON or frontmatter markdown",
+};
+
+/// Every rule, in report order
+pub const RULES: &[Rule] = &[
+    PARSE_ERROR,
+    ID_SAFETY,
+    DUPLICATE_ID,
+    REQUIRED_FIELDS,
+    INVALID_VARIABLE,
+    UNDECLARED_PLACEHOLDER,
+    UNUSED_VARIABLE,
+    MALFORMED_PLACEHOLDER,
+    DUPLICATE_TAG,
+    TAG_FORMAT,
+    CONTENT_SIZE,
+    RECOMMENDED_FIELDS,
+];
+
+/// One problem found in a prompt
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    /// Text the finding is about, used to point at a line in the file
+    #[serde(skip)]
+    pub needle: Option<String>,
+}
+
+impl Finding {
+    pub fn new(rule: Rule, message: impl Into<String>) -> Self {
+        Self {
+            rule: rule.id,
+            severity: rule.severity,
+            message: message.into(),
+            needle: None,
+        }
+    }
+
+    fn at(mut self, needle: impl Into<String>) -> Self {
+        self.needle = Some(needle.into());
+        self
+    }
+
+    fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+}
+
+/// Run every single-prompt rule over `prompt`
+pub fn lint_prompt(prompt: &Prompt) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for (field, value) in [("id", &prompt.id), ("title", &prompt.title), ("content", &prompt.content)] {
+        if value.trim().is_empty() {
+            findings.push(Finding::new(REQUIRED_FIELDS, format!("'{}' is empty", field)));
+        }
+    }
+    if !prompt.id.trim().is_empty()
+        && let Err(e) = build_safe_export_filename(&prompt.id, "md")
+    {
+        findings.push(Finding::new(ID_SAFETY, format!("id '{}': {}", prompt.id, e)).at(&prompt.id));
+    }
+    for (field, value) in [("description", &prompt.description), ("category", &prompt.category)] {
+        if value.as_deref().is_none_or(|v| v.trim().is_empty()) {
+            findings.push(Finding::new(RECOMMENDED_FIELDS, format!("no {}", field)));
+        }
+    }
+
+    check_variables(prompt, &mut findings);
+    check_tags(prompt, &mut findings);
+
+    let size = prompt.content.len();
+    if size > CONTENT_ERROR_BYTES {
+        findings.push(
+            Finding::new(CONTENT_SIZE, format!("content is {} bytes (limit {})", size, CONTENT_ERROR_BYTES))
+                .with_severity(Severity::Error),
+        );
+    } else if size > CONTENT_WARN_BYTES {
+        findings.push(Finding::new(
+            CONTENT_SIZE,
+            format!("content is {} bytes (recommended under {})", size, CONTENT_WARN_BYTES),
+        ));
+    }
+
+    findings
+}
+
+fn check_variables(prompt: &Prompt, findings: &mut Vec<Finding>) {
+    let mut declared = HashSet::new();
+    for var in &prompt.variables {
+        if !is_variable_name(&var.name) {
+            findings.push(
+                Finding::new(INVALID_VARIABLE, format!("variable name '{}' is not valid", var.name))
+                    .at(&var.name),
+            );
+        } else if !declared.insert(var.name.as_str()) {
+            findings.push(
+                Finding::new(INVALID_VARIABLE, format!("variable '{}' is declared twice", var.name))
+                    .at(&var.name),
+            );
+        }
+    }
+
+    let mut used = HashSet::new();
+    for placeholder in scan(&prompt.content) {
+        let raw = format!("{{{{{}}}}}", placeholder);
+        if !is_variable_name(placeholder) {
+            findings.push(
+                Finding::new(MALFORMED_PLACEHOLDER, format!("'{}' is not a valid placeholder", raw)).at(raw),
+            );
+        } else if used.insert(placeholder) && !declared.contains(placeholder) {
+            findings.push(
+                Finding::new(UNDECLARED_PLACEHOLDER, format!("'{}' has no matching variable", raw)).at(raw),
+            );
+        }
+    }
+
+    for name in prompt.variables.iter().map(|var| var.name.as_str()) {
+        if declared.remove(name) && !used.contains(name) {
+            findings.push(
+                Finding::new(UNUSED_VARIABLE, format!("variable '{}' is never used", name)).at(name),
+            );
+        }
+    }
+}
+
+fn check_tags(prompt: &Prompt, findings: &mut Vec<Finding>) {
+    let mut seen = HashSet::new();
+    for tag in &prompt.tags {
+        let normalized = tag.trim().to_lowercase();
+        if normalized.is_empty() {
+            findings.push(Finding::new(TAG_FORMAT, "empty tag"));
+            continue;
+        }
+        if !seen.insert(normalized.clone()) {
+            findings.push(Finding::new(DUPLICATE_TAG, format!("tag '{}' is repeated", tag)).at(tag));
+        } else if *tag != normalized {
+            findings.push(
+                Finding::new(TAG_FORMAT, format!("tag '{}' should be '{}'", tag, normalized)).at(tag),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::bundled_prompts;
+    use crate::types::PromptVariable;
+
+    fn variable(name: &str) -> PromptVariable {
+        PromptVariable {
+            name: name.to_string(),
+            var_type: Default::default(),
+            required: false,
+            description: None,
+            default: None,
+        }
+    }
+
+    fn rules(prompt: &Prompt) -> Vec<&'static str> {
+        lint_prompt(prompt).into_iter().map(|f| f.rule).collect()
+    }
+
+    #[test]
+    fn test_placeholder_and_variable_consistency() {
+        let mut prompt = Prompt::new("p", "P", "Use {{LANG}} on {{CODE}} and {{ bad }} with {{LANG}}");
+        prompt.description = Some("d".to_string());
+        prompt.category = Some("c".to_string());
+        prompt.variables = vec![variable("LANG"), variable("UNUSED"), variable("LANG"), variable("1X")];
+
+        let findings = lint_prompt(&prompt);
+        let messages: Vec<&str> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "variable 'LANG' is declared twice",
+                "variable name '1X' is not valid",
+                "'{{CODE}}' has no matching variable",
+                "'{{ bad }}' is not a valid placeholder",
+                "variable 'UNUSED' is never used",
+            ]
+        );
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert_eq!(findings[2].needle.as_deref(), Some("{{CODE}}"));
+    }
+
+    #[test]
+    fn test_ids_tags_and_size() {
+        let mut prompt = Prompt::new("../escape", "", "x".repeat(CONTENT_ERROR_BYTES + 1));
+        prompt.tags = vec!["API".to_string(), "api".to_string(), " ".to_string()];
+        assert_eq!(
+            rules(&prompt),
+            [
+                "required-fields",
+                "id-safety",
+                "recommended-fields",
+                "recommended-fields",
+                "tag-format",
+                "duplicate-tag",
+                "tag-format",
+                "content-size",
+            ]
+        );
+        let size = lint_prompt(&prompt).into_iter().find(|f| f.rule == "content-size").unwrap();
+        assert_eq!(size.severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_bundled_prompts_have_no_errors() {
+        for prompt in bundled_prompts() {
+            let errors: Vec<Finding> = lint_prompt(&prompt)
+                .into_iter()
+                .filter(|f| f.severity == Severity::Error)
+                .collect();
+            assert!(errors.is_empty(), "{}: {:?}", prompt.id, errors);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Cross-process advisory locking
+//!
+//! From SYNC_STRATEGY.md (Concurrency):
+//! - Lock file at `~/.config/jfp/locks/sync.lock`
+//! - Writers (refresh, registry import, JSONL import/export, cache writes)
+//!   take it exclusively; waiting is bounded by `sync.lockTimeoutMs`
+//! - Re-entrant within a process, so nested writers don't deadlock
+//! - Atomic-write temp files get unique names so writers never share one
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+/// How often a waiting writer retries the lock
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Locks held by this process: path -> (locked file, nesting depth)
+static HELD: LazyLock<Mutex<HashMap<PathBuf, (File, usize)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Counter that keeps temp names unique within a process
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Error)]
+pub enum LockError {
+    #[error(
+        "Timed out after {waited_ms}ms waiting for {}; another jfp process is writing",
+        path.display()
+    )]
+    Timeout { path: PathBuf, waited_ms: u128 },
+    #[error("Failed to lock {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl LockError {
+    /// Stable code for JSON error output
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Timeout { .. } => "lock_timeout",
+            Self::Io { .. } => "lock_error",
+        }
+    }
+}
+
+/// Held lock; released when the outermost guard for the path is dropped
+#[must_use = "the lock is released as soon as the guard is dropped"]
+pub struct LockGuard {
+    path: PathBuf,
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let mut held = HELD.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, depth)) = held.get_mut(&self.path) {
+            *depth -= 1;
+            if *depth == 0 {
+                // Closing the file releases the OS lock
+                held.remove(&self.path);
+            }
+        }
+    }
+}
+
+/// Default sync lock path
+pub fn sync_lock_path() -> PathBuf {
+    crate::config::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("locks")
+        .join("sync.lock")
+}
+
+/// Take the exclusive lock at `path`, waiting up to `timeout`
+pub fn acquire_at(path: &Path, timeout: Duration) -> Result<LockGuard, LockError> {
+    let io_error = |source| LockError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    {
+        let mut held = HELD.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, depth)) = held.get_mut(path) {
+            *depth += 1;
+            return Ok(LockGuard {
+                path: path.to_path_buf(),
+            });
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(io_error)?;
+
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) => {
+                let waited = started.elapsed();
+                if waited >= timeout {
+                    return Err(LockError::Timeout {
+                        path: path.to_path_buf(),
+                        waited_ms: waited.as_millis(),
+                    });
+                }
+                thread::sleep(POLL_INTERVAL.min(timeout - waited));
+            }
+            Err(TryLockError::Error(e)) => return Err(io_error(e)),
+        }
+    }
+
+    HELD.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(path.to_path_buf(), (file, 1));
+    Ok(LockGuard {
+        path: path.to_path_buf(),
+    })
+}
+
+/// JSON error code for `err`: `lock_timeout`/`lock_error` when a lock
+/// failure is in its chain, otherwise `fallback`
+pub fn error_code(err: &anyhow::Error, fallback: &'static str) -> &'static str {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<LockError>())
+        .map_or(fallback, LockError::code)
+}
+
+/// Unique sibling temp path for an atomic write to `path`
+///
+/// Includes the pid and a per-process counter so concurrent writers (in
+/// this or another process) never clobber each other's temp file.
+pub fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let n = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{}.{}.{}.tmp", name, std::process::id(), n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_lock_is_reentrant_in_process() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("locks").join("sync.lock");
+
+        let outer = acquire_at(&path, Duration::ZERO)?;
+        let inner = acquire_at(&path, Duration::ZERO)?;
+        drop(inner);
+        assert!(HELD.lock().unwrap().contains_key(&path));
+        drop(outer);
+        assert!(!HELD.lock().unwrap().contains_key(&path));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lock_times_out_when_held_elsewhere() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("sync.lock");
+
+        // A separate open file description behaves like another process
+        let other = File::create(&path)?;
+        other.lock()?;
+
+        let err = match acquire_at(&path, Duration::from_millis(60)) {
+            Ok(_) => panic!("lock should be busy"),
+            Err(e) => e,
+        };
+        assert_eq!(err.code(), "lock_timeout");
+        assert_eq!(error_code(&anyhow::Error::new(err), "database_error"), "lock_timeout");
+
+        other.unlock()?;
+        drop(acquire_at(&path, Duration::from_millis(60))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_temp_paths_are_unique_siblings() {
+        let path = Path::new("/tmp/jfp/registry.json");
+        let a = temp_path(path);
+        let b = temp_path(path);
+        assert_ne!(a, b);
+        assert_eq!(a.parent(), path.parent());
+        assert!(a.file_name().unwrap().to_string_lossy().starts_with(".registry.json."));
+    }
+}
@@ -5,13 +5,17 @@
 //! and improved performance.
 
 use clap::{CommandFactory, Parser, Subcommand};
+use std::ffi::OsString;
 use std::io::IsTerminal;
 use std::process::ExitCode;
 
 mod commands;
 mod config;
+mod lint;
+mod lock;
 mod registry;
 mod storage;
+mod template;
 mod types;
 
 /// jfp - Agent-optimized CLI for JeffreysPrompts.com
@@ -31,6 +35,10 @@ struct Cli {
     #[arg(long, short, global = true)]
     json: bool,
 
+    /// Open the database read-only; never seed, migrate or import
+    #[arg(long, global = true)]
+    read_only: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -51,6 +59,14 @@ enum Commands {
         /// Show only featured prompts
         #[arg(long)]
         featured: bool,
+
+        /// Show only local prompts
+        #[arg(long)]
+        local: bool,
+
+        /// Show only forks, flagging those whose upstream changed
+        #[arg(long)]
+        forks: bool,
     },
 
     /// Search prompts by keyword
@@ -81,6 +97,14 @@ enum Commands {
         /// Fill template variables interactively
         #[arg(long)]
         fill: bool,
+
+        /// Set a template variable (repeatable; --NAME=VALUE also works)
+        #[arg(long = "var", value_name = "NAME=VALUE")]
+        vars: Vec<String>,
+
+        /// Don't fill CWD, PROJECT_NAME and GIT_* variables automatically
+        #[arg(long)]
+        no_dynamic: bool,
     },
 
     /// Render prompt with variable substitution
@@ -95,6 +119,26 @@ enum Commands {
         /// Context file path for variable substitution
         #[arg(long)]
         context: Option<String>,
+
+        /// Append stdin as a "## Context" section (wins over --context-file)
+        #[arg(long)]
+        stdin: bool,
+
+        /// Append this file's text as a "## Context" section
+        #[arg(long, value_name = "PATH")]
+        context_file: Option<String>,
+
+        /// Max bytes of --stdin/--context-file context
+        #[arg(long, value_name = "BYTES", default_value_t = commands::render::DEFAULT_MAX_CONTEXT)]
+        max_context: usize,
+
+        /// Set a template variable (repeatable; --NAME=VALUE also works)
+        #[arg(long = "var", value_name = "NAME=VALUE")]
+        vars: Vec<String>,
+
+        /// Don't fill CWD, PROJECT_NAME and GIT_* variables automatically
+        #[arg(long)]
+        no_dynamic: bool,
     },
 
     /// Interactive prompt picker (fzf-style)
@@ -119,6 +163,56 @@ enum Commands {
         stdout: bool,
     },
 
+    /// Import markdown prompts (frontmatter or skill exports) as local prompts
+    Import {
+        /// Markdown file or directory to import
+        path: String,
+
+        /// Overwrite existing local prompt files
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Scaffold a local prompt and open it in $VISUAL/$EDITOR
+    New {
+        /// ID for the new prompt (default: the --from ID, or new-prompt)
+        id: Option<String>,
+
+        /// Start from an existing prompt
+        #[arg(long)]
+        from: Option<String>,
+    },
+
+    /// Edit a local prompt in $VISUAL/$EDITOR
+    Edit {
+        /// Local prompt ID
+        id: String,
+    },
+
+    /// Copy a registry prompt into the local prompts dir and track upstream
+    Fork {
+        /// Registry prompt ID
+        id: String,
+
+        /// ID for the local copy (default: same ID, overriding the registry)
+        #[arg(long = "as")]
+        local_id: Option<String>,
+    },
+
+    /// Check prompts for problems (exits non-zero on errors)
+    Lint {
+        /// Prompt files or directories (default: the prompts in the library)
+        paths: Vec<String>,
+
+        /// Only lint local prompts
+        #[arg(long, conflicts_with = "paths")]
+        local: bool,
+
+        /// Output format (text, json, sarif; default: json when --json or piped)
+        #[arg(long, short)]
+        format: Option<String>,
+    },
+
     /// Suggest prompts for a task
     Suggest {
         /// Task description
@@ -142,12 +236,39 @@ enum Commands {
     /// List available bundles
     Bundles,
 
-    /// Show bundle details
+    /// Show bundle details, or manage local bundles
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Bundle {
         /// Bundle ID
+        #[arg(required = true)]
+        id: Option<String>,
+
+        #[command(subcommand)]
+        action: Option<BundleCommand>,
+    },
+
+    /// List recorded revisions of a prompt
+    History {
+        /// Prompt ID
         id: String,
     },
 
+    /// Show a unified diff between two revisions of a prompt
+    Diff {
+        /// Prompt ID
+        id: String,
+
+        /// Older revision (default: the one before TO)
+        from: Option<usize>,
+
+        /// Newer revision (default: the latest)
+        to: Option<usize>,
+
+        /// Compare a fork with its upstream: base, upstream and local
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        upstream: bool,
+    },
+
     /// Get a random prompt
     Random {
         /// Filter by category
@@ -174,13 +295,25 @@ enum Commands {
 
         /// Config value (for set)
         value: Option<String>,
+
+        /// Show where each value comes from (for list)
+        #[arg(long)]
+        show_origin: bool,
     },
 
     /// Show registry cache status
     Status,
 
     /// Refresh local registry cache
-    Refresh,
+    Refresh {
+        /// Preview the sync without writing the cache or database
+        #[arg(long)]
+        dry_run: bool,
+
+        /// List added, changed and removed prompt ids
+        #[arg(long)]
+        diff: bool,
+    },
 
     /// Check for CLI updates
     #[command(name = "update-cli")]
@@ -212,6 +345,110 @@ enum Commands {
 
     /// About JeffreysPrompts
     About,
+
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        action: DbCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BundleCommand {
+    /// Create a local bundle
+    Create {
+        /// Bundle ID
+        id: String,
+
+        /// Member prompt IDs, in order
+        prompts: Vec<String>,
+
+        /// Display title (default: the bundle ID)
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Bundle description
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Bundle author
+        #[arg(long)]
+        author: Option<String>,
+    },
+
+    /// Add prompts to a local bundle (moves prompts already in it)
+    Add {
+        /// Bundle ID
+        id: String,
+
+        /// Prompt IDs to add, in order
+        #[arg(required = true)]
+        prompts: Vec<String>,
+
+        /// Insert at this 0-based position (default: the end)
+        #[arg(long)]
+        position: Option<usize>,
+    },
+
+    /// Remove prompts from a local bundle
+    Remove {
+        /// Bundle ID
+        id: String,
+
+        /// Prompt IDs to remove
+        #[arg(required = true)]
+        prompts: Vec<String>,
+    },
+
+    /// Delete a local bundle
+    Delete {
+        /// Bundle ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DbCommand {
+    /// Apply pending schema migrations
+    Migrate {
+        /// Show applied and pending migrations without running them
+        #[arg(long)]
+        status: bool,
+    },
+
+    /// Rebuild the full-text search index from stored prompts
+    Reindex,
+
+    /// Write every stored prompt to a JSONL backup
+    ExportJsonl {
+        /// Output file (default: ~/.config/jfp/library/library.jsonl)
+        path: Option<String>,
+    },
+
+    /// Restore prompts from a JSONL backup
+    ImportJsonl {
+        /// Input file (default: ~/.config/jfp/library/library.jsonl)
+        path: Option<String>,
+
+        /// How to combine with existing prompts (merge, replace)
+        #[arg(long, default_value = "merge")]
+        mode: String,
+    },
+
+    /// Run the SQLite integrity check and search index consistency check
+    Check,
+
+    /// Compact the database file
+    Vacuum,
+
+    /// Fold the write-ahead log back into the database file
+    Checkpoint,
+
+    /// Rebuild whichever of SQLite and the JSONL library is out of date
+    Recover {
+        /// JSONL library (default: ~/.config/jfp/library/library.jsonl)
+        path: Option<String>,
+    },
 }
 
 fn stylize(text: &str, ansi: &str, no_color: bool) -> String {
@@ -238,6 +475,33 @@ fn resolve_no_color_from_sources(
     cli_no_color || jfp_no_color_present || no_color_present
 }
 
+/// Map global flags onto the config keys they override
+fn cli_overrides(no_color: bool, json: bool, read_only: bool) -> Vec<config::CliOverride> {
+    let mut overrides = Vec::new();
+    if no_color {
+        overrides.push(config::CliOverride {
+            key: "output.color",
+            value: toml::Value::Boolean(false),
+            flag: "--no-color",
+        });
+    }
+    if json {
+        overrides.push(config::CliOverride {
+            key: "output.json",
+            value: toml::Value::Boolean(true),
+            flag: "--json",
+        });
+    }
+    if read_only {
+        overrides.push(config::CliOverride {
+            key: "database.readOnly",
+            value: toml::Value::Boolean(true),
+            flag: "--read-only",
+        });
+    }
+    overrides
+}
+
 fn render_quick_start_help(no_color: bool) -> String {
     let title = stylize("jfp - Jeffrey's Prompts CLI", "1;36", no_color);
     let quick_start = stylize("QUICK START:", "1", no_color);
@@ -263,83 +527,217 @@ fn render_quick_start_help(no_color: bool) -> String {
     )
 }
 
+/// Rewrite `--NAME=VALUE` arguments to render/copy as `--var NAME=VALUE`
+///
+/// Template variables share the flag namespace (as in the TS CLI), so any
+/// `--name=value` that is not one of the command's own options is taken as a
+/// variable, wherever it appears before `--`.
+fn expand_variable_flags(args: Vec<OsString>) -> Vec<OsString> {
+    let cli = Cli::command();
+    // Global options take no values, so the first bare word is the command
+    let Some(pos) = args
+        .iter()
+        .skip(1)
+        .position(|arg| arg.to_str().is_some_and(|arg| !arg.starts_with('-')))
+        .map(|pos| pos + 1)
+    else {
+        return args;
+    };
+    let Some(command) = args[pos]
+        .to_str()
+        .and_then(|name| cli.find_subcommand(name))
+        .filter(|command| ["render", "copy"].contains(&command.get_name()))
+    else {
+        return args;
+    };
+    let known: Vec<&str> = cli
+        .get_arguments()
+        .chain(command.get_arguments())
+        .filter_map(|arg| arg.get_long())
+        .collect();
+
+    let mut expanded = args[..=pos].to_vec();
+    let mut rest = args[pos + 1..].iter();
+    while let Some(arg) = rest.next() {
+        if arg == "--" {
+            expanded.push(arg.clone());
+            expanded.extend(rest.cloned());
+            break;
+        }
+        match arg
+            .to_str()
+            .and_then(|arg| arg.strip_prefix("--"))
+            .and_then(|arg| arg.split_once('='))
+        {
+            Some((name, value)) if !known.contains(&name) && template::is_variable_name(name) => {
+                expanded.push("--var".into());
+                expanded.push(format!("{}={}", name, value).into());
+            }
+            _ => expanded.push(arg.clone()),
+        }
+    }
+    expanded
+}
+
 fn main() -> ExitCode {
-    let cli = Cli::parse();
+    let cli = Cli::parse_from(expand_variable_flags(std::env::args_os().collect()));
 
     // Handle no-color globally (treat NO_COLOR/JFP_NO_COLOR as presence-based toggles).
     let no_color = resolve_no_color(cli.no_color);
 
-    // Determine if JSON output should be used
-    let use_json = cli.json || !std::io::stdout().is_terminal();
-
     // If no command, show help
     let Some(command) = cli.command else {
         print!("{}", render_quick_start_help(no_color));
         return ExitCode::SUCCESS;
     };
 
+    // CLI flags are the top config layer
+    let overrides = cli_overrides(cli.no_color, cli.json, cli.read_only);
+
+    // `jfp config` manages the file itself, so it must work even when the
+    // current file fails to load.
+    if let Commands::Config { action, key, value, show_origin } = command {
+        let use_json = cli.json || !std::io::stdout().is_terminal();
+        return commands::config::run(&action, key, value, show_origin, &overrides, use_json);
+    }
+
+    // Load layered config once; every command reads settings from it.
+    let loaded = match config::load(&overrides) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            if cli.json || !std::io::stdout().is_terminal() {
+                eprintln!(
+                    "{}",
+                    serde_json::json!({"error": e.code(), "message": e.to_string()})
+                );
+            } else {
+                eprintln!("Error loading config: {}", e);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+    let config = &loaded.config;
+
+    // Determine if JSON output should be used
+    let use_json = config.output.json || !std::io::stdout().is_terminal();
+
     // Dispatch to command handlers
     match command {
-        Commands::List { category, tag, featured } => {
-            commands::list::run(category, tag, featured, use_json)
+        Commands::List { category, tag, featured, local, forks } => {
+            commands::list::run(config, category, tag, featured, local, forks, use_json)
         }
         Commands::Search { query, limit } => {
-            commands::search::run(&query, limit, use_json)
+            commands::search::run(config, &query, limit, use_json)
         }
         Commands::Show { id, raw } => {
-            commands::show::run(&id, raw, use_json)
+            commands::show::run(config, &id, raw, use_json)
         }
         Commands::Categories => {
-            commands::categories::run(use_json)
+            commands::categories::run(config, use_json)
         }
         Commands::Tags => {
-            commands::tags::run(use_json)
+            commands::tags::run(config, use_json)
         }
         Commands::About => {
             commands::about::run(use_json)
         }
         Commands::Random { category, tag, copy } => {
-            commands::random::run(category, tag, copy, use_json)
+            commands::random::run(config, category, tag, copy, use_json)
         }
         Commands::Open { id } => {
-            commands::open::run(&id, use_json)
+            commands::open::run(config, &id, use_json)
         }
         Commands::Doctor => {
-            commands::doctor::run(use_json)
+            commands::doctor::run(&loaded, use_json)
         }
         Commands::Completion { shell } => {
             commands::completion::run(&shell, Cli::command())
         }
-        Commands::Config { action, key, value } => {
-            commands::config::run(&action, key, value, use_json)
-        }
+        Commands::Config { .. } => unreachable!("config is dispatched before loading"),
         Commands::Status => {
-            commands::status::run(use_json)
+            commands::status::run(&loaded, use_json)
         }
-        Commands::Copy { id, fill } => {
-            commands::copy::run(&id, fill, use_json)
+        Commands::Copy { id, fill, vars, no_dynamic } => {
+            commands::copy::run(config, &id, fill, &vars, no_dynamic, use_json)
         }
         Commands::Export { ids, format, output_dir, stdout } => {
-            commands::export::run(ids, &format, output_dir, stdout, use_json)
+            commands::export::run(config, ids, &format, output_dir, stdout, use_json)
+        }
+        Commands::Import { path, force } => {
+            commands::import::run(config, &path, force, use_json)
+        }
+        Commands::New { id, from } => commands::edit::new_prompt(config, id, from, use_json),
+        Commands::Edit { id } => commands::edit::edit(config, &id, use_json),
+        Commands::Fork { id, local_id } => commands::fork::run(config, &id, local_id, use_json),
+        Commands::Lint { paths, local, format } => {
+            commands::lint::run(config, paths, local, format, use_json)
         }
-        Commands::Refresh => {
-            commands::refresh::run(use_json)
+        Commands::Refresh { dry_run, diff } => {
+            commands::refresh::run(config, dry_run, diff, use_json)
         }
-        Commands::Render { id, fill, context } => {
-            commands::render::run(&id, fill, context, use_json)
+        Commands::Render {
+            id,
+            fill,
+            context,
+            stdin,
+            context_file,
+            max_context,
+            vars,
+            no_dynamic,
+        } => {
+            let options = commands::render::RenderOptions {
+                fill,
+                context,
+                vars,
+                no_dynamic,
+                stdin,
+                context_file,
+                max_context,
+            };
+            commands::render::run(config, &id, options, use_json)
         }
         Commands::Suggest { task, limit, semantic } => {
-            commands::suggest::run(&task, limit, semantic, use_json)
+            commands::suggest::run(config, &task, limit, semantic, use_json)
         }
         Commands::Bundles => {
-            commands::bundles::list_bundles(use_json)
+            commands::bundles::list_bundles(config, use_json)
         }
-        Commands::Bundle { id } => {
-            commands::bundles::show_bundle(&id, use_json)
+        Commands::Bundle { id, action } => match (id, action) {
+            (_, Some(BundleCommand::Create { id, prompts, title, description, author })) => {
+                commands::bundles::create(config, &id, prompts, title, description, author, use_json)
+            }
+            (_, Some(BundleCommand::Add { id, prompts, position })) => {
+                commands::bundles::add(config, &id, prompts, position, use_json)
+            }
+            (_, Some(BundleCommand::Remove { id, prompts })) => {
+                commands::bundles::remove(config, &id, &prompts, use_json)
+            }
+            (_, Some(BundleCommand::Delete { id })) => commands::bundles::delete(config, &id, use_json),
+            (Some(id), None) => commands::bundles::show_bundle(config, &id, use_json),
+            (None, None) => unreachable!("clap requires a bundle id or subcommand"),
+        },
+        Commands::History { id } => commands::history::run(config, &id, use_json),
+        Commands::Diff { id, from: _, to: _, upstream: true } => {
+            commands::diff::upstream(config, &id, use_json)
+        }
+        Commands::Diff { id, from, to, upstream: false } => {
+            commands::diff::run(config, &id, from, to, use_json)
         }
         Commands::Interactive => {
-            commands::interactive::run(use_json)
+            commands::interactive::run(config, use_json)
         }
+        Commands::Db { action } => match action {
+            DbCommand::Migrate { status } => commands::db::migrate(config, status, use_json),
+            DbCommand::Reindex => commands::db::reindex(config, use_json),
+            DbCommand::ExportJsonl { path } => commands::db::export_jsonl(config, path, use_json),
+            DbCommand::ImportJsonl { path, mode } => {
+                commands::db::import_jsonl(config, path, &mode, use_json)
+            }
+            DbCommand::Check => commands::db::check(use_json),
+            DbCommand::Vacuum => commands::db::vacuum(config, use_json),
+            DbCommand::Checkpoint => commands::db::checkpoint(config, use_json),
+            DbCommand::Recover { path } => commands::db::recover(config, path, use_json),
+        },
         Commands::UpdateCli { check, force } => {
             commands::update_cli::run(check, force, use_json)
         }
@@ -348,7 +746,8 @@ fn main() -> ExitCode {
 
 #[cfg(test)]
 mod tests {
-    use super::{render_quick_start_help, resolve_no_color_from_sources};
+    use super::{expand_variable_flags, render_quick_start_help, resolve_no_color_from_sources};
+    use std::ffi::OsString;
 
     #[test]
     fn quick_start_help_includes_core_sections() {
@@ -381,4 +780,26 @@ mod tests {
         assert!(resolve_no_color_from_sources(false, true, false));
         assert!(resolve_no_color_from_sources(false, false, true));
     }
+
+    #[test]
+    fn expand_variable_flags_only_rewrites_unknown_render_and_copy_options() {
+        let expand = |args: &[&str]| -> Vec<String> {
+            let args = args.iter().map(OsString::from).collect();
+            expand_variable_flags(args)
+                .into_iter()
+                .map(|arg| arg.into_string().unwrap())
+                .collect()
+        };
+
+        assert_eq!(
+            expand(&["jfp", "--json", "render", "id", "--LANG=rust", "--context=c.json", "--fill"]),
+            ["jfp", "--json", "render", "id", "--var", "LANG=rust", "--context=c.json", "--fill"]
+        );
+        assert_eq!(
+            expand(&["jfp", "copy", "--X=a=b", "id", "--", "--Y=1"]),
+            ["jfp", "copy", "--var", "X=a=b", "id", "--", "--Y=1"]
+        );
+        assert_eq!(expand(&["jfp", "show", "--X=1"]), ["jfp", "show", "--X=1"]);
+        assert_eq!(expand(&["jfp", "render", "--1X=1"]), ["jfp", "render", "--1X=1"]);
+    }
 }
@@ -0,0 +1,721 @@
+//! Library bootstrap shared by every prompt-reading command
+//!
+//! From EXISTING_JFP_STRUCTURE.md section 6 (Registry Loader):
+//! - Opens the SQLite store and runs the SWR registry loader
+//! - Imports cache/remote results when newer than the DB's `data_version`
+//! - Seeds bundled prompts only into an empty DB (bundled bundles into a DB
+//!   without any)
+//! - Exposes source/stale so commands can report `offline`/`offlineAge`
+//! - Stale cache with `registry.autoRefresh` spawns a detached `jfp refresh`
+//! - Imports run under the cross-process sync lock
+//! - Local prompts dirs are merged over the registry (later wins by id) and
+//!   only rewritten when their contents change; their bundle files become
+//!   local bundles
+//! - An initialized DB is opened read-only unless there is data to import
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::embedded::bundled_registry;
+use super::loader::RegistryLoader;
+use super::local::{LocalPrompts, LocalWarning};
+use crate::storage::{Database, SyncDiff, SCHEMA_VERSION};
+use crate::types::{Config, Prompt, RegistryLoadResult, RegistrySource};
+
+/// DB metadata key holding the `fetchedAt` of the last imported registry
+pub const DATA_VERSION_KEY: &str = "data_version";
+
+/// DB metadata key holding the URL of the registry `data_version` belongs to
+pub const REGISTRY_URL_KEY: &str = "registry_url";
+
+/// DB metadata key holding the last synced local prompts (`LocalState`)
+pub const LOCAL_PROMPTS_KEY: &str = "local_prompts";
+
+/// Minimum gap between background refreshes spawned by read commands
+const BACKGROUND_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Registry provenance reported alongside command JSON output
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryStatus {
+    pub source: RegistrySource,
+    pub stale: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offline: Option<bool>,
+    /// Seconds since the served registry data was fetched
+    #[serde(rename = "offlineAge", skip_serializing_if = "Option::is_none")]
+    pub offline_age: Option<i64>,
+    /// Registry prompts replaced by a local prompt with the same id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_overrides: Option<usize>,
+    /// Prompts added or changed in the DB while opening it
+    #[serde(skip_serializing_if = "is_zero")]
+    pub imported: usize,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+impl RegistryStatus {
+    fn from_load(result: &RegistryLoadResult, local: &LocalPrompts, imported: usize) -> Self {
+        // Stale cache and bundled fallback both mean we are not serving
+        // fresh registry data.
+        let offline = result.stale || result.source == RegistrySource::Bundled;
+        let offline_age = match result.source {
+            RegistrySource::Cache if offline => age_seconds(&result.registry.meta.fetched_at),
+            _ => None,
+        };
+
+        // `source` stays the registry's provenance; overrides are counted
+        let registry_ids: HashSet<&str> =
+            result.registry.prompts.iter().map(|p| p.id.as_str()).collect();
+        let local_overrides = local
+            .prompts
+            .iter()
+            .filter(|prompt| registry_ids.contains(prompt.id.as_str()))
+            .count();
+
+        Self {
+            source: result.source,
+            stale: result.stale,
+            offline: offline.then_some(true),
+            offline_age,
+            local_overrides: (local_overrides > 0).then_some(local_overrides),
+            imported,
+        }
+    }
+}
+
+/// An opened prompt library: the DB plus where its registry data came from
+pub struct Library {
+    pub db: Database,
+    pub registry: RegistryStatus,
+    /// Problems found in the local prompts dirs
+    pub warnings: Vec<LocalWarning>,
+    /// Registry prompts as loaded, before local prompts override them
+    pub upstream: Vec<Prompt>,
+}
+
+impl Library {
+    fn new(db: Database, result: RegistryLoadResult, local: LocalPrompts, imported: usize) -> Self {
+        Self {
+            db,
+            registry: RegistryStatus::from_load(&result, &local, imported),
+            warnings: local.warnings,
+            upstream: result.registry.prompts,
+        }
+    }
+}
+
+/// Local prompts as last written to the DB
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LocalState {
+    fingerprint: String,
+    /// Ids loaded from the local prompts dirs, so removed files can be pruned
+    ids: Vec<String>,
+    /// Bundle ids loaded from the local prompts dirs
+    #[serde(default)]
+    bundle_ids: Vec<String>,
+}
+
+/// Open the default DB and bring it up to date with the configured registry
+///
+/// With `database.readOnly` (`--read-only`/`JFP_READ_ONLY`) nothing is
+/// written: no network fetch, no import and no background refresh.
+pub fn open_library(config: &Config) -> Result<Library> {
+    let read_only = config.database.read_only;
+    let loader = RegistryLoader::from_config(&config.registry)
+        .with_lock_timeout(config.sync.lock_timeout())
+        .with_auto_refresh(config.registry.auto_refresh && !read_only)
+        .with_local_prompts(&config.local_prompts);
+    let library = open_library_at(&crate::storage::db_path(), &loader, read_only)?;
+
+    for warning in &library.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    if library.registry.stale && loader.auto_refresh() {
+        spawn_background_refresh(&config.registry.cache_path);
+    }
+
+    Ok(library)
+}
+
+/// Open the DB at `path` for a read command
+///
+/// An initialized, current DB is opened read-only and only reopened for
+/// writing when there is registry data or changed local prompts to import.
+/// A missing DB is created and seeded, unless `read_only` forces it, in
+/// which case the registry is served from an in-memory DB.
+pub fn open_library_at(path: &Path, loader: &RegistryLoader, read_only: bool) -> Result<Library> {
+    let result = load_registry(loader);
+    let local = loader.load_local();
+
+    match open_existing_read_only(path, read_only)? {
+        Some(db) if read_only || !has_pending(&db, &result, &local)? => {
+            return Ok(Library::new(db, result, local, 0));
+        }
+        // Data to import: reopen for writing below
+        Some(_) => {}
+        None if read_only => {
+            // Nothing on disk to protect, so no lock either
+            let db = Database::in_memory()?;
+            let imported = apply_pending(&db, &result, &local)?;
+            return Ok(Library::new(db, result, local, imported));
+        }
+        None => {}
+    }
+
+    bootstrap(Database::open_at(path)?, loader, result, local)
+}
+
+/// Bootstrap an already-open DB against a specific loader
+#[cfg(test)]
+pub fn open_library_with(db: Database, loader: &RegistryLoader) -> Result<Library> {
+    let result = load_registry(loader);
+    let local = loader.load_local();
+    bootstrap(db, loader, result, local)
+}
+
+/// Load the registry; an unreadable cache must not make read commands unusable
+fn load_registry(loader: &RegistryLoader) -> RegistryLoadResult {
+    loader.load().unwrap_or_else(|_| RegistryLoadResult {
+        registry: bundled_registry(),
+        source: RegistrySource::Bundled,
+        stale: false,
+    })
+}
+
+/// Open `path` read-only when it exists with a current schema
+///
+/// Otherwise `None`, so the caller initializes it; when `read_only` is
+/// forced an outdated or unreadable DB is an error instead.
+fn open_existing_read_only(path: &Path, read_only: bool) -> Result<Option<Database>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let opened = Database::open_read_only_at(path)
+        .and_then(|db| db.schema_version().map(|version| (db, version)));
+    match opened {
+        Ok((db, SCHEMA_VERSION)) => Ok(Some(db)),
+        Ok((_, version)) if read_only => anyhow::bail!(
+            "Database schema version {} is not current ({}); run 'jfp db migrate' or drop --read-only",
+            version,
+            SCHEMA_VERSION
+        ),
+        Err(e) if read_only => Err(e),
+        _ => Ok(None),
+    }
+}
+
+fn bootstrap(
+    db: Database,
+    loader: &RegistryLoader,
+    result: RegistryLoadResult,
+    local: LocalPrompts,
+) -> Result<Library> {
+    let imported = if has_pending(&db, &result, &local)? {
+        // Re-checked under the lock: a concurrent writer may have imported it
+        let _lock = loader.lock()?;
+        apply_pending(&db, &result, &local)?
+    } else {
+        0
+    };
+
+    Ok(Library::new(db, result, local, imported))
+}
+
+/// Whether the registry or the local prompts dirs have data the DB lacks
+fn has_pending(db: &Database, result: &RegistryLoadResult, local: &LocalPrompts) -> Result<bool> {
+    Ok(should_import(db, result)? || local_changed(db, local))
+}
+
+/// Import whatever is pending, registry first so local prompts win by id;
+/// returns the number of prompts added or changed
+fn apply_pending(db: &Database, result: &RegistryLoadResult, local: &LocalPrompts) -> Result<usize> {
+    let mut imported = 0;
+    if should_import(db, result)? {
+        let diff = import_registry(db, result)?;
+        imported += diff.added.len() + diff.changed.len();
+    }
+    if local_changed(db, local) {
+        imported += import_local(db, result, local)?;
+    }
+    Ok(imported)
+}
+
+/// Write the local prompts dirs' prompts and bundles over the registry rows
+///
+/// Prompts and bundles whose files were removed are deleted, and their
+/// registry copy (if any) is restored from `result`.
+pub fn import_local(db: &Database, result: &RegistryLoadResult, local: &LocalPrompts) -> Result<usize> {
+    let previous = local_state(db).unwrap_or_default();
+    let ids: HashSet<&str> = local.prompts.iter().map(|p| p.id.as_str()).collect();
+    let stale: Vec<String> = previous
+        .ids
+        .into_iter()
+        .filter(|id| !ids.contains(id.as_str()))
+        .collect();
+    let bundle_ids: HashSet<&str> = local.bundles.iter().map(|b| b.id.as_str()).collect();
+    let stale_bundles: Vec<String> = previous
+        .bundle_ids
+        .into_iter()
+        .filter(|id| !bundle_ids.contains(id.as_str()))
+        .collect();
+
+    let mut imported = db.sync_local_prompts(&local.prompts, &stale)?;
+    if !stale.is_empty() {
+        imported += db.sync_prompts(&result.registry.prompts, false)?.added.len();
+    }
+    // After the prompts, so members resolve
+    db.sync_local_bundles(&local.bundles, &stale_bundles)?;
+    if !stale_bundles.is_empty() {
+        db.sync_bundles(&result.registry.bundles, false)?;
+    }
+
+    let state = LocalState {
+        fingerprint: local.fingerprint(),
+        ids: local.prompts.iter().map(|p| p.id.clone()).collect(),
+        bundle_ids: local.bundles.iter().map(|b| b.id.clone()).collect(),
+    };
+    db.set_meta(LOCAL_PROMPTS_KEY, &serde_json::to_string(&state)?)?;
+    Ok(imported)
+}
+
+/// Local prompts state recorded by the last `import_local`
+fn local_state(db: &Database) -> Option<LocalState> {
+    let state = db.get_meta(LOCAL_PROMPTS_KEY).ok()?;
+    serde_json::from_str(&state).ok()
+}
+
+/// Whether the local prompts dirs differ from what the DB last saw
+fn local_changed(db: &Database, local: &LocalPrompts) -> bool {
+    match local_state(db) {
+        Some(state) => state.fingerprint != local.fingerprint(),
+        None => !local.prompts.is_empty() || !local.bundles.is_empty(),
+    }
+}
+
+/// Sync a registry load result into the DB and advance `data_version`
+///
+/// Cache/remote results are the full registry, so rows missing from them are
+/// pruned. Bundled prompts are only a subset: they never prune and never
+/// advance `data_version`, so any real cache wins later. Bundles are written
+/// after prompts so their members resolve.
+pub fn import_registry(db: &Database, result: &RegistryLoadResult) -> Result<SyncDiff> {
+    let full_registry = result.source != RegistrySource::Bundled;
+    let diff = db.sync_prompts(&result.registry.prompts, full_registry)?;
+    db.sync_bundles(&result.registry.bundles, full_registry)?;
+
+    if full_registry {
+        db.set_meta(DATA_VERSION_KEY, &result.registry.meta.fetched_at)?;
+        if let Some(url) = &result.registry.meta.url {
+            db.set_meta(REGISTRY_URL_KEY, url)?;
+        }
+    }
+
+    Ok(diff)
+}
+
+/// Start a detached `jfp refresh` so the next command sees fresh data
+///
+/// Throttled through a stamp file next to the cache; failures are ignored
+/// because the current command already has usable (stale) data.
+fn spawn_background_refresh(cache_path: &Path) {
+    let stamp = cache_path.with_extension("refresh.stamp");
+    let recent = fs::metadata(&stamp)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < BACKGROUND_REFRESH_INTERVAL);
+    if recent {
+        return;
+    }
+
+    if let Some(parent) = stamp.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if fs::write(&stamp, Utc::now().to_rfc3339()).is_err() {
+        return;
+    }
+
+    if let Ok(exe) = std::env::current_exe() {
+        let _ = Command::new(exe)
+            .args(["refresh", "--json"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+    }
+}
+
+/// Decide whether a load result carries data the DB has not seen yet
+fn should_import(db: &Database, result: &RegistryLoadResult) -> Result<bool> {
+    if db.prompt_count()? == 0 {
+        return Ok(true);
+    }
+
+    if result.source == RegistrySource::Bundled {
+        // Only to seed bundles into a DB that predates them
+        return Ok(!result.registry.bundles.is_empty() && db.bundle_count()? == 0);
+    }
+
+    let Ok(data_version) = db.get_meta(DATA_VERSION_KEY) else {
+        return Ok(true);
+    };
+    // Another registry's rows (a project pin, or leaving one) are never
+    // current, however recent; DBs from before the URL was recorded match
+    let stored_url = db.get_meta(REGISTRY_URL_KEY).ok();
+    if let (Some(stored), Some(url)) = (stored_url, &result.registry.meta.url)
+        && stored != *url
+    {
+        return Ok(true);
+    }
+
+    Ok(is_newer(&result.registry.meta.fetched_at, &data_version))
+}
+
+/// Compare two RFC 3339 timestamps; unparsable DB markers count as older
+fn is_newer(candidate: &str, current: &str) -> bool {
+    let Ok(candidate) = DateTime::parse_from_rfc3339(candidate) else {
+        return false;
+    };
+    match DateTime::parse_from_rfc3339(current) {
+        Ok(current) => candidate > current,
+        Err(_) => true,
+    }
+}
+
+fn age_seconds(timestamp: &str) -> Option<i64> {
+    let fetched = DateTime::parse_from_rfc3339(timestamp).ok()?;
+    Some(Utc::now().signed_duration_since(fetched).num_seconds().max(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+    use crate::registry::{bundled_bundles, bundled_prompts};
+    use crate::types::{Bundle, LocalPromptsConfig, Prompt};
+    use tempfile::tempdir;
+
+    fn write_cache(dir: &Path, prompts: &[Prompt], fetched_at: &str) -> Result<()> {
+        fs::write(dir.join("registry.json"), serde_json::to_string(prompts)?)?;
+        let meta = serde_json::json!({
+            "etag": null,
+            "fetched_at": fetched_at,
+            "prompt_count": prompts.len(),
+        });
+        fs::write(dir.join("registry.meta.json"), meta.to_string())?;
+        Ok(())
+    }
+
+    fn loader_in(dir: &Path) -> RegistryLoader {
+        RegistryLoader::with_paths(dir.join("registry.json"), dir.join("registry.meta.json"))
+    }
+
+    #[test]
+    fn test_empty_db_is_seeded_from_bundled() -> Result<()> {
+        let dir = tempdir()?;
+        let library = open_library_with(Database::in_memory()?, &loader_in(dir.path()))?;
+
+        assert_eq!(library.registry.source, RegistrySource::Bundled);
+        assert_eq!(library.registry.offline, Some(true));
+        assert_eq!(library.registry.imported, bundled_prompts().len());
+        assert!(library.db.get_meta(DATA_VERSION_KEY).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_newer_cache_is_imported_over_bundled_seed() -> Result<()> {
+        let dir = tempdir()?;
+        let db = Database::in_memory()?;
+        for prompt in bundled_prompts() {
+            db.upsert_prompt(&prompt)?;
+        }
+
+        let fetched_at = Utc::now().to_rfc3339();
+        write_cache(
+            dir.path(),
+            &[Prompt::new("from-cache", "From Cache", "cached content")],
+            &fetched_at,
+        )?;
+
+        let library = open_library_with(db, &loader_in(dir.path()))?;
+        assert_eq!(library.registry.source, RegistrySource::Cache);
+        assert!(!library.registry.stale);
+        assert_eq!(library.registry.offline, None);
+        assert_eq!(library.registry.imported, 1);
+        assert!(library.db.get_prompt("from-cache")?.is_some());
+        // The bundled seed is not part of the registry, so it is pruned
+        assert_eq!(library.db.prompt_count()?, 1);
+        assert_eq!(library.db.get_meta(DATA_VERSION_KEY)?, fetched_at);
+        Ok(())
+    }
+
+    #[test]
+    fn test_switching_registry_urls_reimports_each_registry() -> Result<()> {
+        let dir = tempdir()?;
+        let db = Database::in_memory()?;
+        let registry = |name: &str, prompt: &str, fetched_at: &str| -> Result<RegistryLoader> {
+            let url = format!("https://{}.example/api/prompts", name);
+            let cache = dir.path().join(format!("{}.json", name));
+            let meta = dir.path().join(format!("{}.meta.json", name));
+            fs::write(&cache, serde_json::to_string(&[Prompt::new(prompt, prompt, "content")])?)?;
+            let meta_json = serde_json::json!({
+                "url": url,
+                "etag": null,
+                "fetched_at": fetched_at,
+                "prompt_count": 1,
+            });
+            fs::write(&meta, meta_json.to_string())?;
+            Ok(RegistryLoader::with_paths(cache, meta).with_url(&url))
+        };
+        let now = Utc::now();
+        let main = registry("main", "from-main", &now.to_rfc3339())?;
+        // The pinned mirror was fetched earlier, yet its rows still win
+        let earlier = (now - chrono::Duration::minutes(5)).to_rfc3339();
+        let pinned = registry("pinned", "from-pinned", &earlier)?;
+
+        let library = open_library_with(db, &main)?;
+        assert!(library.db.get_prompt("from-main")?.is_some());
+        let library = open_library_with(library.db, &pinned)?;
+        assert_eq!(library.registry.imported, 1);
+        assert!(library.db.get_prompt("from-main")?.is_none());
+        assert!(library.db.get_prompt("from-pinned")?.is_some());
+        let library = open_library_with(library.db, &main)?;
+        assert!(library.db.get_prompt("from-main")?.is_some());
+        assert_eq!(library.db.get_meta(REGISTRY_URL_KEY)?, "https://main.example/api/prompts");
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundles_follow_the_registry_payload() -> Result<()> {
+        let dir = tempdir()?;
+        let library = open_library_with(Database::in_memory()?, &loader_in(dir.path()))?;
+        let ids: Vec<String> = library.db.list_bundles()?.into_iter().map(|b| b.id).collect();
+        let bundled: Vec<String> = bundled_bundles().into_iter().map(|b| b.id).collect();
+        assert_eq!(ids, bundled);
+
+        let mut bundle = Bundle::new("from-cache", "From Cache");
+        bundle.prompts = vec!["p2".to_string(), "p1".to_string()];
+        let payload = serde_json::json!({
+            "prompts": [Prompt::new("p1", "P1", "one"), Prompt::new("p2", "P2", "two")],
+            "bundles": [bundle],
+        });
+        fs::write(dir.path().join("registry.json"), payload.to_string())?;
+        let meta = serde_json::json!({
+            "etag": null,
+            "fetched_at": Utc::now().to_rfc3339(),
+            "prompt_count": 2,
+        });
+        fs::write(dir.path().join("registry.meta.json"), meta.to_string())?;
+
+        let library = open_library_with(library.db, &loader_in(dir.path()))?;
+        let bundles = library.db.list_bundles()?;
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(bundles[0].id, "from-cache");
+        assert_eq!(bundles[0].prompts, ["p2", "p1"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_prompts_override_registry_and_fall_back_when_removed() -> Result<()> {
+        let dir = tempdir()?;
+        let local_dir = dir.path().join("local");
+        fs::create_dir_all(&local_dir)?;
+        let db_path = dir.path().join("jfp.db");
+        let loader = loader_in(dir.path()).with_local_prompts(&LocalPromptsConfig {
+            enabled: true,
+            dir: local_dir.clone(),
+            project_dir: None,
+        });
+
+        let target = bundled_prompts().remove(0);
+        let mut override_prompt = target.clone();
+        override_prompt.content = "my local take".to_string();
+        let prompts = [override_prompt, Prompt::new("mine", "Mine", "only local")];
+        fs::write(local_dir.join("mine.json"), serde_json::to_string(&prompts)?)?;
+
+        let library = open_library_at(&db_path, &loader, false)?;
+        assert_eq!(library.registry.source, RegistrySource::Bundled);
+        assert_eq!(library.registry.local_overrides, Some(1));
+        let stored = library.db.get_prompt(&target.id)?.expect("override stored");
+        assert!(stored.is_local);
+        assert_eq!(stored.content, "my local take");
+        assert!(library.db.get_prompt("mine")?.is_some_and(|p| p.is_local));
+        drop(library);
+
+        // Unchanged dirs leave the DB alone
+        assert_eq!(open_library_at(&db_path, &loader, false)?.registry.imported, 0);
+
+        fs::remove_file(local_dir.join("mine.json"))?;
+        let library = open_library_at(&db_path, &loader, false)?;
+        assert!(library.db.get_prompt("mine")?.is_none());
+        assert_eq!(library.db.get_prompt(&target.id)?, Some(target));
+        assert_eq!(library.registry.source, RegistrySource::Bundled);
+        assert_eq!(library.registry.local_overrides, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_bundle_files_override_registry_bundles() -> Result<()> {
+        let dir = tempdir()?;
+        let local_dir = dir.path().join("local");
+        fs::create_dir_all(&local_dir)?;
+        let db_path = dir.path().join("jfp.db");
+        let loader = loader_in(dir.path()).with_local_prompts(&LocalPromptsConfig {
+            enabled: true,
+            dir: local_dir.clone(),
+            project_dir: None,
+        });
+
+        let registry_bundle = bundled_bundles().remove(0);
+        let member = bundled_prompts().remove(0).id;
+        let bundles = serde_json::json!([
+            {"id": registry_bundle.id, "title": "My take", "prompts": [member]},
+            {"id": "mine", "title": "Mine", "prompts": [member]},
+        ]);
+        fs::write(local_dir.join("mine.bundle.json"), bundles.to_string())?;
+
+        let library = open_library_at(&db_path, &loader, false)?;
+        let stored = library.db.get_bundle(&registry_bundle.id)?.expect("override stored");
+        assert!(stored.is_local);
+        assert_eq!(stored.title, "My take");
+        let mine = library.db.get_bundle("mine")?.expect("local bundle stored");
+        assert!(mine.is_local);
+        assert_eq!(mine.prompts, [member]);
+        drop(library);
+
+        fs::remove_file(local_dir.join("mine.bundle.json"))?;
+        let library = open_library_at(&db_path, &loader, false)?;
+        assert!(library.db.get_bundle("mine")?.is_none());
+        let restored = library.db.get_bundle(&registry_bundle.id)?.expect("registry copy");
+        assert!(!restored.is_local);
+        assert_eq!(restored.title, registry_bundle.title);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_not_reimported_when_db_is_current() -> Result<()> {
+        let dir = tempdir()?;
+        let fetched_at = Utc::now().to_rfc3339();
+        write_cache(
+            dir.path(),
+            &[Prompt::new("from-cache", "From Cache", "cached content")],
+            &fetched_at,
+        )?;
+
+        let first = open_library_with(Database::in_memory()?, &loader_in(dir.path()))?;
+        assert_eq!(first.registry.imported, 1);
+
+        let second = open_library_with(first.db, &loader_in(dir.path()))?;
+        assert_eq!(second.registry.imported, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_cache_reports_offline_age() -> Result<()> {
+        let dir = tempdir()?;
+        let fetched_at = (Utc::now() - chrono::Duration::hours(3)).to_rfc3339();
+        write_cache(
+            dir.path(),
+            &[Prompt::new("old", "Old", "old content")],
+            &fetched_at,
+        )?;
+
+        let library = open_library_with(Database::in_memory()?, &loader_in(dir.path()))?;
+        assert!(library.registry.stale);
+        assert_eq!(library.registry.offline, Some(true));
+        assert!(library.registry.offline_age.is_some_and(|age| age >= 3 * 3600));
+        Ok(())
+    }
+
+    #[test]
+    fn test_initialized_db_is_opened_read_only() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("jfp.db");
+        let loader = loader_in(dir.path());
+
+        let first = open_library_at(&db_path, &loader, false)?;
+        assert_eq!(first.registry.imported, bundled_prompts().len());
+        drop(first);
+
+        let second = open_library_at(&db_path, &loader, false)?;
+        assert_eq!(second.registry.imported, 0);
+        assert_eq!(second.db.prompt_count()?, bundled_prompts().len());
+        assert!(second.db.set_meta("probe", "x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_cache_reopens_for_import() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("jfp.db");
+        let loader = loader_in(dir.path());
+        drop(open_library_at(&db_path, &loader, false)?);
+
+        write_cache(
+            dir.path(),
+            &[Prompt::new("from-cache", "From Cache", "cached content")],
+            &Utc::now().to_rfc3339(),
+        )?;
+        let library = open_library_at(&db_path, &loader, false)?;
+        assert_eq!(library.registry.imported, 1);
+        assert!(library.db.get_prompt("from-cache")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_forced_read_only_never_writes() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("jfp.db");
+        let loader = loader_in(dir.path());
+
+        // Missing DB: served from memory, nothing created on disk
+        let library = open_library_at(&db_path, &loader, true)?;
+        assert_eq!(library.db.prompt_count()?, bundled_prompts().len());
+        assert!(!db_path.exists());
+        drop(library);
+
+        // Existing DB with newer cache data: not imported
+        drop(open_library_at(&db_path, &loader, false)?);
+        write_cache(
+            dir.path(),
+            &[Prompt::new("from-cache", "From Cache", "cached content")],
+            &Utc::now().to_rfc3339(),
+        )?;
+        let library = open_library_at(&db_path, &loader, true)?;
+        assert_eq!(library.registry.imported, 0);
+        assert!(library.db.get_prompt("from-cache")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_forced_read_only_rejects_outdated_schema() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("jfp.db");
+        drop(Database::open_at(&db_path)?);
+        rusqlite::Connection::open(&db_path)?.execute(
+            "UPDATE registry_meta SET value = '1' WHERE key = 'schema_version'",
+            [],
+        )?;
+
+        let err = match open_library_at(&db_path, &loader_in(dir.path()), true) {
+            Ok(_) => panic!("outdated schema should be rejected"),
+            Err(e) => e,
+        };
+        assert!(err.to_string().contains("jfp db migrate"));
+        Ok(())
+    }
+}
@@ -3,7 +3,12 @@
 //! When network and cache are unavailable, these bundled prompts
 //! ensure basic functionality.
 
-use crate::types::Prompt;
+use crate::types::{Bundle, Prompt, Registry};
+
+/// Bundled registry: the fallback prompts and bundles
+pub fn bundled_registry() -> Registry {
+    Registry::new(bundled_prompts()).with_bundles(bundled_bundles())
+}
 
 /// Get bundled prompts as fallback
 pub fn bundled_prompts() -> Vec<Prompt> {
@@ -213,6 +218,43 @@ Suggest improvements for each area."#
     ]
 }
 
+/// Get bundled bundles as fallback
+///
+/// Also served when a registry payload carries no `bundles`. Every member
+/// is a bundled prompt.
+pub fn bundled_bundles() -> Vec<Bundle> {
+    let bundle = |id: &str, title: &str, description: &str, prompts: &[&str]| Bundle {
+        description: Some(description.to_string()),
+        version: Some("1.0.0".to_string()),
+        prompt_count: prompts.len(),
+        featured: id == "getting-started",
+        author: Some("JeffreysPrompts".to_string()),
+        prompts: prompts.iter().map(|id| id.to_string()).collect(),
+        ..Bundle::new(id, title)
+    };
+
+    vec![
+        bundle(
+            "getting-started",
+            "Getting Started",
+            "Essential prompts for new users",
+            &["code-review", "debug", "explain-code"],
+        ),
+        bundle(
+            "quality-essentials",
+            "Quality Essentials",
+            "Core prompts for code quality and refactoring",
+            &["write-tests", "refactor", "optimize"],
+        ),
+        bundle(
+            "docs-and-design",
+            "Docs & Design",
+            "Prompts for documentation and API design",
+            &["documentation", "api-design", "explain-code"],
+        ),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -238,4 +280,28 @@ mod tests {
         let featured: Vec<_> = prompts.iter().filter(|p| p.featured).collect();
         assert!(!featured.is_empty());
     }
+
+    #[test]
+    fn test_bundled_bundle_ids_are_unique() {
+        let mut seen = std::collections::HashSet::new();
+        for bundle in bundled_bundles() {
+            assert!(seen.insert(bundle.id.clone()), "duplicate bundle id found: {}", bundle.id);
+        }
+    }
+
+    #[test]
+    fn test_bundled_bundle_members_are_bundled_prompts() {
+        let ids: Vec<String> = bundled_prompts().into_iter().map(|p| p.id).collect();
+        for bundle in bundled_bundles() {
+            assert_eq!(bundle.prompt_count, bundle.prompts.len());
+            for prompt_id in &bundle.prompts {
+                assert!(
+                    ids.contains(prompt_id),
+                    "bundle '{}' references missing prompt id '{}'",
+                    bundle.id,
+                    prompt_id
+                );
+            }
+        }
+    }
 }
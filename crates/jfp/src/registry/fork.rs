@@ -0,0 +1,187 @@
+//! Local forks of registry prompts
+//!
+//! - `jfp fork <id>` copies a registry prompt into the local prompts dir and
+//!   records it in `.forks/<local id>.json` next to the copy: upstream id,
+//!   version and content hash, plus the upstream prompt as forked (the base
+//!   for three-way diffs)
+//! - Drift is checked against the registry as currently loaded, so a
+//!   refresh that brings a new upstream version flags the fork
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::local::load_local_prompts;
+use crate::storage::revision_hash;
+use crate::types::Prompt;
+
+/// Directory (inside a local prompts dir) holding fork records
+const FORKS_DIR: &str = ".forks";
+
+/// What a local prompt was forked from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForkRecord {
+    /// Id of the local copy
+    pub id: String,
+    pub upstream_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream_version: Option<String>,
+    /// `revision_hash` of the upstream prompt when forked
+    pub upstream_hash: String,
+    pub forked_at: String,
+    /// The upstream prompt when forked
+    pub base: Prompt,
+}
+
+impl ForkRecord {
+    /// Record a fork of `upstream` saved locally as `id`
+    pub fn new(id: impl Into<String>, upstream: &Prompt) -> Result<Self> {
+        Ok(Self {
+            id: id.into(),
+            upstream_id: upstream.id.clone(),
+            upstream_version: upstream.version.clone(),
+            upstream_hash: revision_hash(upstream)?,
+            forked_at: chrono::Utc::now().to_rfc3339(),
+            base: upstream.clone(),
+        })
+    }
+}
+
+/// How a fork's upstream compares with the registry now
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForkState {
+    /// Upstream is unchanged since the fork
+    Current,
+    /// Upstream has a new revision
+    Changed,
+    /// Upstream is no longer in the registry
+    Removed,
+}
+
+/// A fork checked against the current registry
+#[derive(Debug, Clone, Serialize)]
+pub struct ForkStatus {
+    pub id: String,
+    pub upstream_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream_version: Option<String>,
+    /// Upstream version in the registry now, when it changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_version: Option<String>,
+    pub forked_at: String,
+    pub state: ForkState,
+}
+
+impl ForkStatus {
+    /// Compare `record` with the registry prompts in `upstream`
+    pub fn check(record: &ForkRecord, upstream: &[Prompt]) -> Self {
+        let current = upstream.iter().find(|p| p.id == record.upstream_id);
+        let state = match current {
+            None => ForkState::Removed,
+            Some(prompt) if revision_hash(prompt).ok().as_ref() == Some(&record.upstream_hash) => {
+                ForkState::Current
+            }
+            Some(_) => ForkState::Changed,
+        };
+
+        Self {
+            id: record.id.clone(),
+            upstream_id: record.upstream_id.clone(),
+            upstream_version: record.upstream_version.clone(),
+            current_version: match state {
+                ForkState::Changed => current.and_then(|p| p.version.clone()),
+                _ => None,
+            },
+            forked_at: record.forked_at.clone(),
+            state,
+        }
+    }
+}
+
+/// Path of the fork record for local prompt `id` in local prompts dir `dir`
+pub fn fork_record_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(FORKS_DIR).join(format!("{}.json", id))
+}
+
+/// Write `record` into local prompts dir `dir`
+pub fn save_fork(dir: &Path, record: &ForkRecord) -> Result<PathBuf> {
+    let path = fork_record_path(dir, &record.id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(record)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Fork records in `dirs`, later dirs winning by id; unreadable records are
+/// skipped
+pub fn load_forks(dirs: &[PathBuf]) -> Vec<ForkRecord> {
+    let mut forks: Vec<ForkRecord> = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir.join(FORKS_DIR)) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let Some(record) = fs::read_to_string(&path)
+                .ok()
+                .and_then(|text| serde_json::from_str::<ForkRecord>(&text).ok())
+            else {
+                continue;
+            };
+            forks.retain(|fork| fork.id != record.id);
+            forks.push(record);
+        }
+    }
+    forks
+}
+
+/// Forks in `dirs` whose local copy still exists, checked against `upstream`
+pub fn fork_statuses(dirs: &[PathBuf], upstream: &[Prompt]) -> Vec<ForkStatus> {
+    let local = load_local_prompts(dirs);
+    load_forks(dirs)
+        .iter()
+        .filter(|record| local.files.contains_key(&record.id))
+        .map(|record| ForkStatus::check(record, upstream))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_fork_drift_follows_upstream() -> Result<()> {
+        let dir = tempdir()?;
+        let mut upstream = Prompt::new("review", "Review", "v1 content");
+        upstream.version = Some("1.0.0".to_string());
+        save_fork(dir.path(), &ForkRecord::new("review", &upstream)?)?;
+
+        let forks = load_forks(&[dir.path().to_path_buf()]);
+        assert_eq!(forks.len(), 1);
+        assert_eq!(forks[0].base, upstream);
+
+        let status = ForkStatus::check(&forks[0], std::slice::from_ref(&upstream));
+        assert_eq!(status.state, ForkState::Current);
+
+        let mut updated = upstream.clone();
+        updated.content = "v2 content".to_string();
+        updated.version = Some("1.1.0".to_string());
+        let status = ForkStatus::check(&forks[0], &[updated]);
+        assert_eq!(status.state, ForkState::Changed);
+        assert_eq!(status.current_version.as_deref(), Some("1.1.0"));
+
+        assert_eq!(ForkStatus::check(&forks[0], &[]).state, ForkState::Removed);
+        Ok(())
+    }
+}
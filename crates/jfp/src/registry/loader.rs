@@ -7,7 +7,7 @@
 
 use std::fs;
 use std::io::{BufReader, BufWriter};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use anyhow::{Context, Result};
@@ -16,23 +16,23 @@ use reqwest::blocking::Client;
 use reqwest::header::{ACCEPT, ETAG, IF_NONE_MATCH, USER_AGENT};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use super::embedded::bundled_prompts;
-use crate::config;
-use crate::types::{Prompt, Registry, RegistryLoadResult, RegistrySource};
-
-/// Default cache TTL in seconds
-const DEFAULT_CACHE_TTL: u64 = 3600;
-
-/// Default API timeout in milliseconds
-const DEFAULT_TIMEOUT_MS: u64 = 2000;
-
-/// Registry API URL
-const REGISTRY_URL: &str = "https://jeffreysprompts.com/api/prompts";
+use super::embedded::{bundled_bundles, bundled_registry};
+use super::local::{load_local_prompts, local_prompt_dirs, LocalPrompts};
+use crate::lock::{self, LockError, LockGuard};
+use crate::types::{
+    Bundle, LocalPromptsConfig, Prompt, Registry, RegistryConfig, RegistryLoadResult,
+    RegistryMeta, RegistrySource, SyncConfig,
+};
 
 /// Cached registry metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CacheMeta {
+    /// Registry URL the cache was fetched from; `None` in caches written
+    /// before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
     #[serde(default)]
     version: Option<String>,
     etag: Option<String>,
@@ -46,55 +46,135 @@ struct RegistryApiPayload {
     #[serde(default)]
     version: Option<String>,
     prompts: Vec<Prompt>,
+    #[serde(default)]
+    bundles: Option<Vec<Bundle>>,
+}
+
+/// Registry data as stored in the cache file
+///
+/// `bundles` is `None` when the registry does not serve bundles; the
+/// bundled set is used in that case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedRegistry {
+    prompts: Vec<Prompt>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bundles: Option<Vec<Bundle>>,
+}
+
+/// Cache file layouts: an object with prompts and bundles, or the bare
+/// prompt array written by older versions
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CacheFile {
+    Registry(CachedRegistry),
+    Prompts(Vec<Prompt>),
 }
 
 struct RemoteFetchResult {
     /// `None` indicates a 304 Not Modified response.
-    prompts: Option<Vec<Prompt>>,
+    data: Option<CachedRegistry>,
     etag: Option<String>,
     version: Option<String>,
 }
 
 /// Registry loader with caching
 pub struct RegistryLoader {
+    url: String,
     cache_path: PathBuf,
     meta_path: PathBuf,
     cache_ttl: Duration,
     timeout: Duration,
+    auto_refresh: bool,
+    lock_path: PathBuf,
+    lock_timeout: Duration,
+    /// Local prompts dirs, in merge order; empty when disabled
+    local_dirs: Vec<PathBuf>,
 }
 
 impl RegistryLoader {
-    /// Create a new registry loader with default paths
+    /// Create a new registry loader with default settings
     pub fn new() -> Self {
-        let config_dir = config::config_dir().unwrap_or_else(|| PathBuf::from("."));
+        Self::from_config(&RegistryConfig::default())
+    }
+
+    /// Create a loader from the effective registry config
+    ///
+    /// A registry other than the default (e.g. a project pin) gets its own
+    /// cache and meta files, so switching between projects never overwrites
+    /// another registry's cache.
+    pub fn from_config(config: &RegistryConfig) -> Self {
         Self {
-            cache_path: config_dir.join("registry.json"),
-            meta_path: config_dir.join("registry.meta.json"),
-            cache_ttl: Duration::from_secs(DEFAULT_CACHE_TTL),
-            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+            url: config.url.clone(),
+            cache_path: url_keyed_path(&config.cache_path, &config.url),
+            meta_path: url_keyed_path(&config.meta_path, &config.url),
+            cache_ttl: Duration::from_secs(config.cache_ttl),
+            timeout: Duration::from_millis(config.timeout_ms),
+            auto_refresh: config.auto_refresh,
+            lock_path: lock::sync_lock_path(),
+            lock_timeout: SyncConfig::default().lock_timeout(),
+            local_dirs: Vec::new(),
         }
     }
 
     /// Create with custom paths (for testing)
+    ///
+    /// Auto-refresh is off so tests never touch the network, and the sync
+    /// lock sits next to the cache rather than in the user's config dir.
+    #[cfg(test)]
     pub fn with_paths(cache_path: PathBuf, meta_path: PathBuf) -> Self {
         Self {
+            lock_path: cache_path.with_file_name("sync.lock"),
             cache_path,
             meta_path,
-            cache_ttl: Duration::from_secs(DEFAULT_CACHE_TTL),
-            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+            auto_refresh: false,
+            ..Self::new()
         }
     }
 
-    /// Set cache TTL
-    pub fn with_ttl(mut self, ttl: Duration) -> Self {
-        self.cache_ttl = ttl;
+    /// Whether stale or missing caches should be refreshed from remote
+    pub fn auto_refresh(&self) -> bool {
+        self.auto_refresh
+    }
+
+    /// Enable or disable fetching when the cache is missing or stale
+    pub fn with_auto_refresh(mut self, auto_refresh: bool) -> Self {
+        self.auto_refresh = auto_refresh;
+        self
+    }
+
+    /// Set how long cache writes wait for the sync lock
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Merge prompts from the local prompts dirs (user, then project)
+    pub fn with_local_prompts(mut self, config: &LocalPromptsConfig) -> Self {
+        self.local_dirs = local_prompt_dirs(config);
         self
     }
 
-    /// Set request timeout
-    #[allow(dead_code)]
-    pub fn with_timeout(mut self, timeout: Duration) -> Self {
-        self.timeout = timeout;
+    /// Read the local prompts dirs; never fails, problems become warnings
+    pub fn load_local(&self) -> LocalPrompts {
+        load_local_prompts(&self.local_dirs)
+    }
+
+    /// Take the cross-process sync lock guarding the cache and DB
+    pub fn lock(&self) -> Result<LockGuard, LockError> {
+        lock::acquire_at(&self.lock_path, self.lock_timeout)
+    }
+
+    /// Set the registry URL
+    #[cfg(test)]
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    /// Set cache TTL
+    #[cfg(test)]
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
         self
     }
 
@@ -106,13 +186,13 @@ impl RegistryLoader {
     /// 3. Fall back to bundled
     pub fn load(&self) -> Result<RegistryLoadResult> {
         // Check cache first
-        if let Some((prompts, meta)) = self.load_cache()? {
+        if let Some((data, meta)) = self.load_cache()? {
             let stale = self.is_stale(&meta);
 
             if !stale {
                 // Cache is fresh, use it
                 return Ok(RegistryLoadResult {
-                    registry: Registry::new(prompts),
+                    registry: cached_registry(data, &meta),
                     source: RegistrySource::Cache,
                     stale: false,
                 });
@@ -121,31 +201,34 @@ impl RegistryLoader {
             // Cache is stale but exists - return stale data
             // In async context, we'd trigger background refresh here
             return Ok(RegistryLoadResult {
-                registry: Registry::new(prompts),
+                registry: cached_registry(data, &meta),
                 source: RegistrySource::Cache,
                 stale: true,
             });
         }
 
-        // No cache - use bundled as fallback
+        // No cache - fetch once when allowed, otherwise use bundled
+        if self.auto_refresh {
+            return self.load_sync();
+        }
+
         Ok(RegistryLoadResult {
-            registry: Registry::new(bundled_prompts()),
+            registry: bundled_registry(),
             source: RegistrySource::Bundled,
             stale: false,
         })
     }
 
     /// Load registry synchronously, attempting remote fetch
-    #[allow(dead_code)]
     pub fn load_sync(&self) -> Result<RegistryLoadResult> {
         // Try to load from cache first
         let cached = self.load_cache()?;
 
         // Check if cache is fresh
-        if let Some((prompts, meta)) = &cached {
+        if let Some((data, meta)) = &cached {
             if !self.is_stale(meta) {
                 return Ok(RegistryLoadResult {
-                    registry: Registry::new(prompts.clone()),
+                    registry: cached_registry(data.clone(), meta),
                     source: RegistrySource::Cache,
                     stale: false,
                 });
@@ -157,28 +240,28 @@ impl RegistryLoader {
 
         match self.fetch_remote(etag) {
             Ok(remote) => {
-                if let Some(prompts) = remote.prompts {
+                if let Some(data) = remote.data {
                     // Got new data - save to cache
-                    self.save_cache(&prompts, remote.etag, remote.version)?;
+                    let meta = self.save_cache(&data, remote.etag, remote.version)?;
                     Ok(RegistryLoadResult {
-                        registry: Registry::new(prompts),
+                        registry: cached_registry(data, &meta),
                         source: RegistrySource::Remote,
                         stale: false,
                     })
                 } else {
                     // 304 Not Modified - cache is still valid
-                    if let Some((prompts, _)) = cached {
+                    if let Some((data, meta)) = cached {
                         // Update cache timestamp
-                        self.touch_cache()?;
+                        let meta = self.touch_cache(&meta)?;
                         Ok(RegistryLoadResult {
-                            registry: Registry::new(prompts),
+                            registry: cached_registry(data, &meta),
                             source: RegistrySource::Cache,
                             stale: false,
                         })
                     } else {
                         // This shouldn't happen (304 without cache)
                         Ok(RegistryLoadResult {
-                            registry: Registry::new(bundled_prompts()),
+                            registry: bundled_registry(),
                             source: RegistrySource::Bundled,
                             stale: false,
                         })
@@ -187,15 +270,15 @@ impl RegistryLoader {
             }
             Err(_) => {
                 // Network error - use cache if available, else bundled
-                if let Some((prompts, _)) = cached {
+                if let Some((data, meta)) = cached {
                     Ok(RegistryLoadResult {
-                        registry: Registry::new(prompts),
+                        registry: cached_registry(data, &meta),
                         source: RegistrySource::Cache,
                         stale: true,
                     })
                 } else {
                     Ok(RegistryLoadResult {
-                        registry: Registry::new(bundled_prompts()),
+                        registry: bundled_registry(),
                         source: RegistrySource::Bundled,
                         stale: false,
                     })
@@ -206,36 +289,63 @@ impl RegistryLoader {
 
     /// Force refresh from remote
     pub fn refresh(&self) -> Result<RegistryLoadResult> {
+        self.fetch_latest(true)
+    }
+
+    /// Fetch the latest registry like `refresh`, without writing the cache
+    ///
+    /// Used by `jfp refresh --dry-run`.
+    pub fn preview(&self) -> Result<RegistryLoadResult> {
+        self.fetch_latest(false)
+    }
+
+    fn fetch_latest(&self, persist: bool) -> Result<RegistryLoadResult> {
         let cached = self.load_cache()?;
         let etag = cached.as_ref().and_then(|(_, m)| m.etag.as_deref());
 
         match self.fetch_remote(etag) {
             Ok(remote) => {
-                if let Some(prompts) = remote.prompts {
-                    self.save_cache(&prompts, remote.etag, remote.version)?;
+                if let Some(data) = remote.data {
+                    let meta = if persist {
+                        self.save_cache(&data, remote.etag, remote.version)?
+                    } else {
+                        CacheMeta {
+                            url: Some(self.url.clone()),
+                            version: remote.version,
+                            etag: remote.etag,
+                            fetched_at: Utc::now().to_rfc3339(),
+                            prompt_count: data.prompts.len(),
+                        }
+                    };
                     Ok(RegistryLoadResult {
-                        registry: Registry::new(prompts),
+                        registry: cached_registry(data, &meta),
                         source: RegistrySource::Remote,
                         stale: false,
                     })
-                } else if let Some((prompts, _)) = cached {
+                } else if let Some((data, meta)) = cached {
                     // 304 Not Modified - refresh still succeeds using cached data.
-                    self.touch_cache()?;
+                    let meta = if persist { self.touch_cache(&meta)? } else { meta };
                     Ok(RegistryLoadResult {
-                        registry: Registry::new(prompts),
+                        registry: cached_registry(data, &meta),
                         source: RegistrySource::Cache,
                         stale: false,
                     })
-                } else {
+                } else if persist {
                     // Unexpected 304 without cache
                     self.load()
+                } else {
+                    Ok(RegistryLoadResult {
+                        registry: bundled_registry(),
+                        source: RegistrySource::Bundled,
+                        stale: false,
+                    })
                 }
             }
             Err(e) => {
                 // Network error - fall back to cache if available.
-                if let Some((prompts, _)) = cached {
+                if let Some((data, meta)) = cached {
                     Ok(RegistryLoadResult {
-                        registry: Registry::new(prompts),
+                        registry: cached_registry(data, &meta),
                         source: RegistrySource::Cache,
                         stale: true,
                     })
@@ -246,8 +356,8 @@ impl RegistryLoader {
         }
     }
 
-    /// Load prompts from cache
-    fn load_cache(&self) -> Result<Option<(Vec<Prompt>, CacheMeta)>> {
+    /// Load prompts and bundles from cache
+    fn load_cache(&self) -> Result<Option<(CachedRegistry, CacheMeta)>> {
         if !self.cache_path.exists() {
             return Ok(None);
         }
@@ -255,55 +365,81 @@ impl RegistryLoader {
         let file =
             fs::File::open(&self.cache_path).context("Failed to open registry cache")?;
         let reader = BufReader::new(file);
-        let prompts: Vec<Prompt> =
+        let cache: CacheFile =
             serde_json::from_reader(reader).context("Failed to parse registry cache")?;
+        let data = match cache {
+            CacheFile::Registry(data) => data,
+            CacheFile::Prompts(prompts) => CachedRegistry {
+                prompts,
+                bundles: None,
+            },
+        };
 
-        // Load metadata
+        // Load metadata. Without a readable meta file, fall back to the cache
+        // file's mtime so the cache does not look freshly fetched on every load.
+        let fallback_meta = || CacheMeta {
+            url: None,
+            version: None,
+            etag: None,
+            fetched_at: fs::metadata(&self.cache_path)
+                .and_then(|m| m.modified())
+                .map(DateTime::<Utc>::from)
+                .unwrap_or_else(|_| Utc::now())
+                .to_rfc3339(),
+            prompt_count: data.prompts.len(),
+        };
         let meta = if self.meta_path.exists() {
             let meta_file = fs::File::open(&self.meta_path)?;
-            serde_json::from_reader(BufReader::new(meta_file)).unwrap_or_else(|_| CacheMeta {
-                version: None,
-                etag: None,
-                fetched_at: Utc::now().to_rfc3339(),
-                prompt_count: prompts.len(),
-            })
+            serde_json::from_reader(BufReader::new(meta_file)).unwrap_or_else(|_| fallback_meta())
         } else {
-            CacheMeta {
-                version: None,
-                etag: None,
-                fetched_at: Utc::now().to_rfc3339(),
-                prompt_count: prompts.len(),
-            }
+            fallback_meta()
+        };
+
+        // A cache of another registry (the URL was changed or pinned) is a
+        // miss: neither its data nor its etag belong to this URL
+        if meta.url.as_ref().is_some_and(|url| *url != self.url) {
+            return Ok(None);
+        }
+        let meta = CacheMeta {
+            url: Some(self.url.clone()),
+            ..meta
         };
 
-        Ok(Some((prompts, meta)))
+        Ok(Some((data, meta)))
     }
 
-    /// Save prompts to cache
-    fn save_cache(&self, prompts: &[Prompt], etag: Option<String>, version: Option<String>) -> Result<()> {
+    /// Save prompts and bundles to cache
+    fn save_cache(
+        &self,
+        data: &CachedRegistry,
+        etag: Option<String>,
+        version: Option<String>,
+    ) -> Result<CacheMeta> {
         // Ensure directory exists
         if let Some(parent) = self.cache_path.parent() {
             fs::create_dir_all(parent)?;
         }
+        let _lock = self.lock()?;
 
         // Atomic write via temp file
-        let temp_path = self.cache_path.with_extension("tmp");
+        let temp_path = lock::temp_path(&self.cache_path);
         {
             let file = fs::File::create(&temp_path)?;
             let writer = BufWriter::new(file);
-            serde_json::to_writer_pretty(writer, prompts)?;
+            serde_json::to_writer_pretty(writer, data)?;
         }
         fs::rename(&temp_path, &self.cache_path)?;
 
         // Save metadata
         let meta = CacheMeta {
+            url: Some(self.url.clone()),
             version,
             etag,
             fetched_at: Utc::now().to_rfc3339(),
-            prompt_count: prompts.len(),
+            prompt_count: data.prompts.len(),
         };
 
-        let temp_meta = self.meta_path.with_extension("tmp");
+        let temp_meta = lock::temp_path(&self.meta_path);
         {
             let file = fs::File::create(&temp_meta)?;
             let writer = BufWriter::new(file);
@@ -311,25 +447,24 @@ impl RegistryLoader {
         }
         fs::rename(&temp_meta, &self.meta_path)?;
 
-        Ok(())
+        Ok(meta)
     }
 
     /// Update cache timestamp without re-fetching
-    fn touch_cache(&self) -> Result<()> {
-        if let Ok(file) = fs::File::open(&self.meta_path) {
-            if let Ok(mut meta) = serde_json::from_reader::<_, CacheMeta>(BufReader::new(file)) {
-                meta.fetched_at = Utc::now().to_rfc3339();
-
-                let temp_meta = self.meta_path.with_extension("tmp");
-                {
-                    let file = fs::File::create(&temp_meta)?;
-                    let writer = BufWriter::new(file);
-                    serde_json::to_writer(writer, &meta)?;
-                }
-                fs::rename(&temp_meta, &self.meta_path)?;
-            }
+    fn touch_cache(&self, meta: &CacheMeta) -> Result<CacheMeta> {
+        let mut meta = meta.clone();
+        meta.fetched_at = Utc::now().to_rfc3339();
+
+        let _lock = self.lock()?;
+        let temp_meta = lock::temp_path(&self.meta_path);
+        {
+            let file = fs::File::create(&temp_meta)?;
+            let writer = BufWriter::new(file);
+            serde_json::to_writer(writer, &meta)?;
         }
-        Ok(())
+        fs::rename(&temp_meta, &self.meta_path)?;
+
+        Ok(meta)
     }
 
     /// Check if cache is stale
@@ -350,7 +485,7 @@ impl RegistryLoader {
             .context("Failed to build registry HTTP client")?;
 
         let mut req = client
-            .get(REGISTRY_URL)
+            .get(&self.url)
             .header(ACCEPT, "application/json")
             .header(USER_AGENT, format!("jfp/{}", env!("CARGO_PKG_VERSION")));
 
@@ -362,7 +497,7 @@ impl RegistryLoader {
 
         if resp.status() == StatusCode::NOT_MODIFIED {
             return Ok(RemoteFetchResult {
-                prompts: None,
+                data: None,
                 etag: None,
                 version: None,
             });
@@ -381,35 +516,54 @@ impl RegistryLoader {
         let payload: RegistryApiPayload = resp.json().context("Failed to parse registry JSON")?;
 
         Ok(RemoteFetchResult {
-            prompts: Some(payload.prompts),
+            data: Some(CachedRegistry {
+                prompts: payload.prompts,
+                bundles: payload.bundles,
+            }),
             etag: response_etag,
             version: payload.version,
         })
     }
 }
 
-impl Default for RegistryLoader {
-    fn default() -> Self {
-        Self::new()
+/// `path` for the default registry URL; otherwise the file name gets a
+/// short hash of `url` (`registry.meta.json` -> `registry-<hash>.meta.json`)
+fn url_keyed_path(path: &Path, url: &str) -> PathBuf {
+    if url == RegistryConfig::default().url {
+        return path.to_path_buf();
     }
+    let hash = format!("{:x}", Sha256::digest(url.as_bytes()));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = match name.split_once('.') {
+        Some((stem, rest)) => format!("{}-{}.{}", stem, &hash[..12], rest),
+        None => format!("{}-{}", name, &hash[..12]),
+    };
+    path.with_file_name(name)
 }
 
-/// Get the cache file path
-#[allow(dead_code)]
-pub fn cache_path() -> PathBuf {
-    config::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("registry.json")
+/// Build a registry that keeps the cache's fetch metadata (fetchedAt, etag)
+/// so callers can compare it against what the DB last imported.
+fn cached_registry(data: CachedRegistry, meta: &CacheMeta) -> Registry {
+    Registry {
+        meta: RegistryMeta {
+            version: meta.version.clone().unwrap_or_else(|| "1.0.0".to_string()),
+            etag: meta.etag.clone(),
+            fetched_at: meta.fetched_at.clone(),
+            prompt_count: data.prompts.len(),
+            url: meta.url.clone(),
+        },
+        prompts: data.prompts,
+        bundles: data.bundles.unwrap_or_else(bundled_bundles),
+    }
 }
 
-/// Get the metadata file path
-#[allow(dead_code)]
-pub fn meta_path() -> PathBuf {
-    config::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("registry.meta.json")
+impl Default for RegistryLoader {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -431,6 +585,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_loader_from_config() {
+        let dir = tempdir().expect("tempdir");
+        let config = RegistryConfig {
+            url: "https://mirror.example/api/prompts".to_string(),
+            cache_path: dir.path().join("cache.json"),
+            meta_path: dir.path().join("cache.meta.json"),
+            cache_ttl: 60,
+            timeout_ms: 500,
+            auto_refresh: false,
+            ..RegistryConfig::default()
+        };
+
+        let loader = RegistryLoader::from_config(&config);
+        assert_eq!(loader.url, "https://mirror.example/api/prompts");
+        // A non-default registry gets its own cache files
+        let name = |path: &Path| path.file_name().unwrap().to_string_lossy().into_owned();
+        let cache = name(&loader.cache_path);
+        let hash = &cache["cache-".len()..cache.len() - ".json".len()];
+        assert_eq!(loader.cache_path, dir.path().join(format!("cache-{}.json", hash)));
+        assert_eq!(loader.meta_path, dir.path().join(format!("cache-{}.meta.json", hash)));
+        assert_eq!(
+            RegistryLoader::from_config(&RegistryConfig::default()).cache_path,
+            RegistryConfig::default().cache_path
+        );
+        assert_eq!(loader.cache_ttl, Duration::from_secs(60));
+        assert_eq!(loader.timeout, Duration::from_millis(500));
+        assert!(!loader.auto_refresh());
+    }
+
     #[test]
     fn test_loader_uses_cache() -> Result<()> {
         let dir = tempdir()?;
@@ -442,6 +626,7 @@ mod tests {
         fs::write(&cache, serde_json::to_string(&prompts)?)?;
 
         let cache_meta = CacheMeta {
+            url: None,
             version: None,
             etag: None,
             fetched_at: Utc::now().to_rfc3339(),
@@ -472,6 +657,7 @@ mod tests {
         // Write old metadata (2 hours ago)
         let old_time = Utc::now() - chrono::Duration::hours(2);
         let cache_meta = CacheMeta {
+            url: None,
             version: None,
             etag: None,
             fetched_at: old_time.to_rfc3339(),
@@ -486,4 +672,29 @@ mod tests {
         assert!(result.stale);
         Ok(())
     }
+
+    #[test]
+    fn test_cache_of_another_url_is_a_miss() -> Result<()> {
+        let dir = tempdir()?;
+        let cache = dir.path().join("registry.json");
+        let meta = dir.path().join("registry.meta.json");
+        let loader =
+            |url: &str| RegistryLoader::with_paths(cache.clone(), meta.clone()).with_url(url);
+
+        let first = loader("https://one.example/api/prompts");
+        let data = CachedRegistry {
+            prompts: vec![Prompt::new("from-one", "From One", "content")],
+            bundles: None,
+        };
+        first.save_cache(&data, Some("\"one\"".to_string()), None)?;
+        let result = first.load()?;
+        assert_eq!(result.source, RegistrySource::Cache);
+        assert_eq!(result.registry.prompts[0].id, "from-one");
+
+        // Switching URLs neither serves the old data nor reuses its etag
+        let second = loader("https://two.example/api/prompts");
+        assert!(second.load_cache()?.is_none());
+        assert_eq!(second.load()?.source, RegistrySource::Bundled);
+        Ok(())
+    }
 }
@@ -0,0 +1,402 @@
+//! Local prompts directory
+//!
+//! From EXISTING_JFP_STRUCTURE.md section 6 (Local Prompts Validation):
+//! - `*.json` files in `localPrompts.dir` (then `localPrompts.projectDir`)
+//! - Each file holds a single prompt or an array of prompts
+//! - `*.md` files hold one prompt as YAML frontmatter plus body (see
+//!   `markdown`)
+//! - `*.bundle.json` files hold a single bundle or an array of bundles
+//! - Invalid entries are skipped with a warning naming the file
+//! - Local prompts win over registry prompts with the same id
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::markdown::parse_markdown_prompt;
+use crate::types::{Bundle, LocalPromptsConfig, Prompt};
+
+/// Suffix of local bundle files
+pub const BUNDLE_FILE_SUFFIX: &str = ".bundle.json";
+
+/// Problem with one local prompts file; the entry (or file) is skipped
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalWarning {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for LocalWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+/// Prompts and bundles read from the local prompts dirs
+#[derive(Debug, Default)]
+pub struct LocalPrompts {
+    /// Valid prompts, marked `is_local`, unique by id (later dirs win)
+    pub prompts: Vec<Prompt>,
+    /// Valid bundles, marked `is_local`, unique by id (later dirs win)
+    pub bundles: Vec<Bundle>,
+    pub warnings: Vec<LocalWarning>,
+    /// File each prompt was read from, by id
+    pub files: HashMap<String, PathBuf>,
+}
+
+impl LocalPrompts {
+    /// Stable hash of the loaded prompts and bundles, so unchanged dirs
+    /// skip the DB write
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for prompt in &self.prompts {
+            // Prompt serialization cannot fail: no maps with non-string keys
+            hasher.update(serde_json::to_vec(prompt).unwrap_or_default());
+            hasher.update(b"\n");
+        }
+        if !self.bundles.is_empty() {
+            // Kept out of the hash when absent so older states stay valid
+            hasher.update(serde_json::to_vec(&self.bundles).unwrap_or_default());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Configured local prompts dirs in merge order: user dir, then project
+/// dir; empty when local prompts are disabled
+pub fn local_prompt_dirs(config: &LocalPromptsConfig) -> Vec<PathBuf> {
+    if !config.enabled {
+        return Vec::new();
+    }
+    std::iter::once(config.dir.clone())
+        .chain(config.project_dir.clone())
+        .collect()
+}
+
+/// Load local prompts from `dirs` in order; missing dirs are skipped
+///
+/// Files are read in name order. Within one dir a repeated id is a warning;
+/// across dirs the later dir overrides silently (project over user).
+pub fn load_local_prompts(dirs: &[PathBuf]) -> LocalPrompts {
+    let mut local = LocalPrompts::default();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut bundle_index: HashMap<String, usize> = HashMap::new();
+
+    for dir in dirs {
+        let mut seen_in_dir: HashMap<String, PathBuf> = HashMap::new();
+        let (bundle_files, prompt_files): (Vec<PathBuf>, Vec<PathBuf>) =
+            local_files(dir, &mut local.warnings)
+                .into_iter()
+                .partition(|path| is_bundle_file(path));
+
+        for path in bundle_files {
+            for bundle in load_bundle_file(&path, &mut local.warnings) {
+                match bundle_index.get(&bundle.id) {
+                    Some(&i) => local.bundles[i] = bundle,
+                    None => {
+                        bundle_index.insert(bundle.id.clone(), local.bundles.len());
+                        local.bundles.push(bundle);
+                    }
+                }
+            }
+        }
+
+        for path in prompt_files {
+            for prompt in load_file(&path, &mut local.warnings) {
+                if let Some(first) = seen_in_dir.get(&prompt.id) {
+                    local.warnings.push(LocalWarning {
+                        path: path.clone(),
+                        message: format!(
+                            "duplicate prompt id '{}' (also in {}); using this one",
+                            prompt.id,
+                            first.display()
+                        ),
+                    });
+                }
+                seen_in_dir.insert(prompt.id.clone(), path.clone());
+                local.files.insert(prompt.id.clone(), path.clone());
+
+                match index.get(&prompt.id) {
+                    Some(&i) => local.prompts[i] = prompt,
+                    None => {
+                        index.insert(prompt.id.clone(), local.prompts.len());
+                        local.prompts.push(prompt);
+                    }
+                }
+            }
+        }
+    }
+
+    local
+}
+
+/// Read one prompts file strictly: any invalid entry is an error
+pub fn read_prompt_file(path: &Path) -> Result<Vec<Prompt>, String> {
+    let mut warnings = Vec::new();
+    let prompts = load_file(path, &mut warnings);
+    match warnings.into_iter().next() {
+        Some(warning) => Err(warning.message),
+        None if prompts.is_empty() => Err("file holds no prompts".to_string()),
+        None => Ok(prompts),
+    }
+}
+
+/// Whether `path` names a local bundle file rather than a prompts file
+pub fn is_bundle_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(BUNDLE_FILE_SUFFIX))
+}
+
+/// `*.json` (bundle files included) and `*.md` files directly in `dir`,
+/// sorted by name; dotfiles (editor swap and temp files) are skipped
+fn local_files(dir: &Path, warnings: &mut Vec<LocalWarning>) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(e) => {
+            warnings.push(LocalWarning {
+                path: dir.to_path_buf(),
+                message: format!("cannot read directory: {}", e),
+            });
+            return Vec::new();
+        }
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path.extension().is_some_and(|ext| ext == "json" || ext == "md")
+                && !path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Parse one file into its valid prompts, warning about the rest
+fn load_file(path: &Path, warnings: &mut Vec<LocalWarning>) -> Vec<Prompt> {
+    let mut warn = |message: String| {
+        warnings.push(LocalWarning {
+            path: path.to_path_buf(),
+            message,
+        })
+    };
+
+    let entries = match parse_prompt_file(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn(e);
+            return Vec::new();
+        }
+    };
+
+    let mut prompts = Vec::new();
+    for entry in entries {
+        let validated = entry.and_then(|prompt| {
+            let id = prompt.id.clone();
+            validate_prompt(prompt).map_err(|e| format!("invalid prompt '{}': {}", id, e))
+        });
+        match validated {
+            Ok(prompt) => prompts.push(prompt),
+            Err(e) => warn(e),
+        }
+    }
+    prompts
+}
+
+/// Parse one bundle file into its valid bundles, warning about the rest
+fn load_bundle_file(path: &Path, warnings: &mut Vec<LocalWarning>) -> Vec<Bundle> {
+    let mut warn = |message: String| {
+        warnings.push(LocalWarning {
+            path: path.to_path_buf(),
+            message,
+        })
+    };
+
+    let value = match fs::read_to_string(path)
+        .map_err(|e| format!("cannot read file: {}", e))
+        .and_then(|text| {
+            serde_json::from_str::<Value>(&text).map_err(|e| format!("invalid JSON: {}", e))
+        }) {
+        Ok(value) => value,
+        Err(e) => {
+            warn(e);
+            return Vec::new();
+        }
+    };
+    let entries = match value {
+        Value::Array(entries) => entries,
+        entry => vec![entry],
+    };
+
+    let mut bundles = Vec::new();
+    for (i, entry) in entries.into_iter().enumerate() {
+        let label = match entry.get("id").and_then(Value::as_str) {
+            Some(id) => format!("bundle '{}'", id),
+            None => format!("entry {}", i + 1),
+        };
+        let validated = serde_json::from_value::<Bundle>(entry)
+            .map_err(|e| e.to_string())
+            .and_then(validate_bundle)
+            .map_err(|e| format!("invalid {}: {}", label, e));
+        match validated {
+            Ok(bundle) => bundles.push(bundle),
+            Err(e) => warn(e),
+        }
+    }
+    bundles
+}
+
+/// Parse one prompts file without validating its prompts
+///
+/// `Err` is a problem with the whole file (unreadable, bad JSON or bad
+/// frontmatter); otherwise there is one result per entry, with errors
+/// naming the entry.
+pub fn parse_prompt_file(path: &Path) -> Result<Vec<Result<Prompt, String>>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read file: {}", e))?;
+
+    if path.extension().is_some_and(|ext| ext == "md") {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let prompt = parse_markdown_prompt(&text, &stem).map_err(|e| e.to_string())?;
+        return Ok(vec![Ok(prompt)]);
+    }
+
+    let value = serde_json::from_str::<Value>(&text).map_err(|e| format!("invalid JSON: {}", e))?;
+    let entries = match value {
+        Value::Array(entries) => entries,
+        entry => vec![entry],
+    };
+
+    Ok(entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let label = match entry.get("id").and_then(Value::as_str) {
+                Some(id) => format!("prompt '{}'", id),
+                None => format!("entry {}", i + 1),
+            };
+            serde_json::from_value(entry).map_err(|e| format!("invalid {}: {}", label, e))
+        })
+        .collect())
+}
+
+/// Check the fields every local prompt needs and mark it local
+pub fn validate_prompt(mut prompt: Prompt) -> Result<Prompt, String> {
+    for (field, value) in [
+        ("id", &prompt.id),
+        ("title", &prompt.title),
+        ("content", &prompt.content),
+    ] {
+        if value.trim().is_empty() {
+            return Err(format!("'{}' must not be empty", field));
+        }
+    }
+    if prompt.id.chars().any(char::is_whitespace) {
+        return Err("'id' must not contain whitespace".to_string());
+    }
+    prompt.is_local = true;
+    Ok(prompt)
+}
+
+/// Check the fields every local bundle needs and mark it local
+fn validate_bundle(mut bundle: Bundle) -> Result<Bundle, String> {
+    if bundle.id.trim().is_empty() || bundle.title.trim().is_empty() {
+        return Err("'id' and 'title' must not be empty".to_string());
+    }
+    if bundle.id.chars().any(char::is_whitespace) {
+        return Err("'id' must not contain whitespace".to_string());
+    }
+    bundle.prompt_count = bundle.prompts.len();
+    bundle.is_local = true;
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_single_and_array_files_with_warnings() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let single = Prompt::new("one", "One", "first");
+        fs::write(dir.path().join("a.json"), serde_json::to_string(&single)?)?;
+        let array = serde_json::json!([
+            {"id": "two", "title": "Two", "content": "second"},
+            {"id": "bad", "title": "Bad"},
+            {"id": "blank", "title": "Blank", "content": "  "},
+        ]);
+        fs::write(dir.path().join("b.json"), array.to_string())?;
+        fs::write(dir.path().join("c.json"), "{ not json")?;
+        fs::write(dir.path().join("d.md"), "---\ntitle: From Markdown\n---\nbody\n")?;
+        fs::write(dir.path().join("e.md"), "---\ntitle: [oops\n---\nbody\n")?;
+        fs::write(dir.path().join("notes.txt"), "ignored")?;
+
+        let local = load_local_prompts(&[dir.path().to_path_buf()]);
+        let ids: Vec<&str> = local.prompts.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["one", "two", "d"]);
+        assert!(local.prompts.iter().all(|p| p.is_local));
+
+        let messages: Vec<String> = local.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(messages.len(), 4);
+        assert!(messages[0].contains("b.json: invalid prompt 'bad': missing field `content`"));
+        assert!(messages[1].contains("invalid prompt 'blank': 'content' must not be empty"));
+        assert!(messages[2].contains("c.json: invalid JSON"));
+        assert!(messages[3].contains("e.md: line 2: invalid frontmatter"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_files() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let bundles = serde_json::json!([
+            {"id": "review", "title": "Review", "prompts": ["one", "two"]},
+            {"id": "untitled"},
+        ]);
+        fs::write(dir.path().join("mine.bundle.json"), bundles.to_string())?;
+        fs::write(dir.path().join("one.json"), r#"{"id":"one","title":"One","content":"x"}"#)?;
+
+        let local = load_local_prompts(&[dir.path().to_path_buf()]);
+        assert_eq!(local.prompts.len(), 1);
+        assert_eq!(local.bundles.len(), 1);
+        let bundle = &local.bundles[0];
+        assert_eq!(bundle.id, "review");
+        assert_eq!(bundle.prompt_count, 2);
+        assert!(bundle.is_local);
+        assert_eq!(local.warnings.len(), 1);
+        assert!(local.warnings[0].to_string().contains("invalid bundle 'untitled'"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_later_dirs_override_by_id() -> anyhow::Result<()> {
+        let user = tempdir()?;
+        let project = tempdir()?;
+        let write = |dir: &Path, content: &str| {
+            fs::write(
+                dir.join("p.json"),
+                serde_json::to_string(&Prompt::new("shared", "Shared", content)).unwrap(),
+            )
+        };
+        write(user.path(), "from user")?;
+        write(project.path(), "from project")?;
+
+        let dirs = [
+            user.path().to_path_buf(),
+            project.path().join("missing"),
+            project.path().to_path_buf(),
+        ];
+        let local = load_local_prompts(&dirs);
+        assert_eq!(local.prompts.len(), 1);
+        assert_eq!(local.prompts[0].content, "from project");
+        assert!(local.warnings.is_empty());
+        Ok(())
+    }
+}
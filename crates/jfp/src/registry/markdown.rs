@@ -0,0 +1,308 @@
+//! Markdown prompt files
+//!
+//! - Local prompts as `*.md` with YAML frontmatter (id, title, category,
+//!   tags, variables, ...) and the body as content
+//! - Files written by `jfp export --format skill` (SKILL.md style) are read
+//!   back through their `## Metadata` / `## Prompt` sections
+//! - Errors carry the 1-based file line they refer to
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Prompt, PromptVariable};
+
+/// Parse error in a markdown prompt file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownError {
+    /// 1-based line in the file
+    pub line: usize,
+    pub message: String,
+}
+
+impl MarkdownError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for MarkdownError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Prompt metadata as written in the frontmatter
+#[derive(Debug, Serialize, Deserialize)]
+struct Frontmatter {
+    /// Defaults to the file name without `.md`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    variables: Vec<PromptVariable>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    featured: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+}
+
+/// Parse a markdown prompt: frontmatter format, or a skill export
+///
+/// `default_id` is used when the frontmatter has no `id`.
+pub fn parse_markdown_prompt(text: &str, default_id: &str) -> Result<Prompt, MarkdownError> {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.first().is_some_and(|line| is_fence(line, "---")) {
+        parse_frontmatter(&lines, default_id)
+    } else if is_skill_export(&lines) {
+        parse_skill(&lines)
+    } else {
+        Err(MarkdownError::new(
+            1,
+            "expected YAML frontmatter starting with '---'",
+        ))
+    }
+}
+
+/// Render a prompt as frontmatter markdown; `parse_markdown_prompt` reads it back
+pub fn to_markdown(prompt: &Prompt) -> String {
+    let frontmatter = Frontmatter {
+        id: Some(prompt.id.clone()),
+        title: prompt.title.clone(),
+        description: prompt.description.clone(),
+        category: prompt.category.clone(),
+        tags: prompt.tags.clone(),
+        variables: prompt.variables.clone(),
+        featured: prompt.featured,
+        version: prompt.version.clone(),
+        author: prompt.author.clone(),
+    };
+    // Plain strings, lists and structs always serialize
+    let yaml = serde_norway::to_string(&frontmatter).unwrap_or_default();
+    format!("---\n{}---\n\n{}\n", yaml, prompt.content.trim_end())
+}
+
+fn is_fence(line: &str, fence: &str) -> bool {
+    line.trim_end() == fence
+}
+
+fn parse_frontmatter(lines: &[&str], default_id: &str) -> Result<Prompt, MarkdownError> {
+    let Some(close) = lines
+        .iter()
+        .skip(1)
+        .position(|line| is_fence(line, "---"))
+        .map(|i| i + 1)
+    else {
+        return Err(MarkdownError::new(1, "frontmatter is not closed by '---'"));
+    };
+
+    let yaml = lines[1..close].join("\n");
+    if yaml.trim().is_empty() {
+        return Err(MarkdownError::new(2, "frontmatter is empty"));
+    }
+    let meta: Frontmatter = serde_norway::from_str(&yaml).map_err(|e| {
+        // YAML starts on file line 2
+        let line = e.location().map_or(2, |loc| loc.line() + 1);
+        let message = e.to_string();
+        let message = match message.rsplit_once(" at line ") {
+            Some((message, _)) => message.to_string(),
+            None => message,
+        };
+        MarkdownError::new(line, format!("invalid frontmatter: {}", message))
+    })?;
+
+    let content = lines[close + 1..].join("\n").trim().to_string();
+    if content.is_empty() {
+        // First line after the closing fence
+        return Err(MarkdownError::new(close + 2, "prompt body is empty"));
+    }
+
+    let mut prompt = Prompt::new(
+        meta.id.unwrap_or_else(|| default_id.to_string()),
+        meta.title,
+        content,
+    );
+    prompt.description = meta.description;
+    prompt.category = meta.category;
+    prompt.tags = meta.tags;
+    prompt.variables = meta.variables;
+    prompt.featured = meta.featured;
+    prompt.version = meta.version;
+    prompt.author = meta.author;
+    Ok(prompt)
+}
+
+fn is_skill_export(lines: &[&str]) -> bool {
+    lines.iter().any(|line| is_fence(line, "## Metadata"))
+        && lines.iter().any(|line| line.starts_with("- **ID**:"))
+}
+
+/// Read back the layout written by `export --format skill`
+fn parse_skill(lines: &[&str]) -> Result<Prompt, MarkdownError> {
+    let mut title = None;
+    let mut description = None;
+    let mut id = None;
+    let mut category = None;
+    let mut tags = Vec::new();
+    let mut variables = Vec::new();
+    let mut prompt_line = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(rest) = line.strip_prefix("# ") {
+            title.get_or_insert_with(|| rest.trim().to_string());
+        } else if let Some(rest) = line.strip_prefix("> ") {
+            description.get_or_insert_with(|| rest.trim().to_string());
+        } else if let Some(rest) = line.strip_prefix("- **ID**:") {
+            id = Some(rest.trim().to_string());
+        } else if let Some(rest) = line.strip_prefix("- **Category**:") {
+            category = Some(rest.trim().to_string());
+        } else if let Some(rest) = line.strip_prefix("- **Tags**:") {
+            tags = rest
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+        } else if let Some(rest) = line.strip_prefix("- `{{") {
+            let Some((name, rest)) = rest.split_once("}}`") else {
+                return Err(MarkdownError::new(i + 1, "unterminated variable name"));
+            };
+            variables.push(skill_variable(name, rest));
+        } else if is_fence(line, "## Prompt") {
+            prompt_line = Some(i);
+            break;
+        }
+    }
+
+    let Some(title) = title else {
+        return Err(MarkdownError::new(1, "missing '# <title>' heading"));
+    };
+    let Some(id) = id.filter(|id| !id.is_empty()) else {
+        return Err(MarkdownError::new(1, "missing '- **ID**:' metadata line"));
+    };
+    let Some(prompt_line) = prompt_line else {
+        return Err(MarkdownError::new(lines.len(), "missing '## Prompt' section"));
+    };
+
+    // Content sits between the first fence after the heading and the last one
+    let open = lines[prompt_line + 1..]
+        .iter()
+        .position(|line| line.starts_with("```"))
+        .map(|i| prompt_line + 1 + i);
+    let close = lines.iter().rposition(|line| is_fence(line, "```"));
+    let (Some(open), Some(close)) = (open, close.filter(|&close| Some(close) > open)) else {
+        return Err(MarkdownError::new(
+            prompt_line + 1,
+            "'## Prompt' must be followed by a fenced code block",
+        ));
+    };
+
+    let mut prompt = Prompt::new(id, title, lines[open + 1..close].join("\n"));
+    prompt.description = description;
+    prompt.category = category;
+    prompt.tags = tags;
+    prompt.variables = variables;
+    Ok(prompt)
+}
+
+/// `rest` is what follows `` `{{NAME}}` ``: `: description (default: value)`
+fn skill_variable(name: &str, rest: &str) -> PromptVariable {
+    let mut rest = rest.trim().trim_start_matches(':').trim();
+    let mut default = None;
+    if let Some((before, value)) = rest.rsplit_once("(default: ")
+        && let Some(value) = value.strip_suffix(')')
+    {
+        default = Some(value.to_string());
+        rest = before.trim();
+    }
+
+    PromptVariable {
+        name: name.to_string(),
+        var_type: Default::default(),
+        required: false,
+        description: (!rest.is_empty()).then(|| rest.to_string()),
+        default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VariableType;
+
+    #[test]
+    fn test_frontmatter_roundtrip() {
+        let text = "---\n\
+                    id: review\n\
+                    title: Code Review\n\
+                    category: coding\n\
+                    tags: [review, rust]\n\
+                    variables:\n  \
+                      - name: LANG\n    \
+                        type: select\n    \
+                        required: true\n\
+                    ---\n\
+                    \n\
+                    Review this {{LANG}} code.\n";
+        let prompt = parse_markdown_prompt(text, "ignored").unwrap();
+        assert_eq!(prompt.id, "review");
+        assert_eq!(prompt.category.as_deref(), Some("coding"));
+        assert_eq!(prompt.tags, ["review", "rust"]);
+        assert_eq!(prompt.variables[0].var_type, VariableType::Select);
+        assert_eq!(prompt.content, "Review this {{LANG}} code.");
+
+        assert_eq!(parse_markdown_prompt(&to_markdown(&prompt), "x").unwrap(), prompt);
+    }
+
+    #[test]
+    fn test_frontmatter_errors_carry_file_lines() {
+        let err = |text: &str| parse_markdown_prompt(text, "stem").unwrap_err().to_string();
+
+        assert_eq!(err("# Just markdown\n"), "line 1: expected YAML frontmatter starting with '---'");
+        assert_eq!(err("---\ntitle: T\nbody\n"), "line 1: frontmatter is not closed by '---'");
+        assert!(err("---\ntitle: T\ntags: [a\n---\nbody\n").starts_with("line 4: invalid frontmatter"));
+        assert!(err("---\ntitle: T\nfeatured: maybe\n---\nbody\n").starts_with("line 3: invalid frontmatter"));
+        assert_eq!(err("---\ntitle: T\n---\n\n"), "line 4: prompt body is empty");
+
+        let prompt = parse_markdown_prompt("---\ntitle: T\n---\nbody", "stem").unwrap();
+        assert_eq!(prompt.id, "stem");
+    }
+
+    #[test]
+    fn test_skill_export_is_read_back() {
+        let text = "# Code Review\n\n\
+                    > Reviews code\n\n\
+                    ## Metadata\n\n\
+                    - **ID**: review\n\
+                    - **Category**: coding\n\
+                    - **Tags**: review, rust\n\n\
+                    ## Variables\n\n\
+                    - `{{LANG}}`: Language (default: rust)\n\
+                    - `{{FILE}}`\n\n\
+                    ## Prompt\n\n\
+                    ```\n\
+                    Review this:\n\
+                    ```inner```\n\
+                    ```\n";
+        let prompt = parse_markdown_prompt(text, "ignored").unwrap();
+        assert_eq!(prompt.id, "review");
+        assert_eq!(prompt.title, "Code Review");
+        assert_eq!(prompt.description.as_deref(), Some("Reviews code"));
+        assert_eq!(prompt.tags, ["review", "rust"]);
+        assert_eq!(prompt.variables[0].description.as_deref(), Some("Language"));
+        assert_eq!(prompt.variables[0].default.as_deref(), Some("rust"));
+        assert_eq!(prompt.variables[1].description, None);
+        assert_eq!(prompt.content, "Review this:\n```inner```");
+    }
+}
@@ -3,8 +3,16 @@
 //! From EXISTING_JFP_STRUCTURE.md section 6 (Registry Loader)
 //! Implements SWR (stale-while-revalidate) caching pattern.
 
+mod bootstrap;
 mod loader;
 mod embedded;
+mod fork;
+mod local;
+mod markdown;
 
+pub use bootstrap::*;
 pub use loader::*;
 pub use embedded::*;
+pub use fork::*;
+pub use local::*;
+pub use markdown::*;
@@ -5,20 +5,118 @@
 //! - Busy timeout for lock handling
 //! - Transactions for multi-step writes
 
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use anyhow::Result;
-use rusqlite::{params, Connection, OptionalExtension};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use super::schema::{CREATE_SCHEMA, SCHEMA_VERSION};
-use crate::types::{Prompt, PromptVariable, VariableType};
+use super::schema::{Migration, MIGRATIONS, SCHEMA_VERSION};
+use crate::types::{Bundle, Prompt, PromptVariable, VariableType};
 
 /// Database wrapper with connection management
 pub struct Database {
     conn: Connection,
 }
 
+/// Applied/pending state of one migration, for `jfp db migrate --status`
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    pub version: i32,
+    pub description: &'static str,
+    pub applied: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_at: Option<String>,
+}
+
+/// Outcome of running pending migrations
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MigrationReport {
+    pub from_version: i32,
+    pub to_version: i32,
+    pub applied: Vec<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<PathBuf>,
+}
+
+/// One recorded version of a prompt (`prompt_revisions` row)
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptRevision {
+    /// 1-based position in the prompt's history
+    pub rev: usize,
+    pub content_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// `registry` or `local`
+    pub source: String,
+    /// `None` for a prompt stored before history was kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recorded_at: Option<String>,
+    /// The prompt as it was at this revision
+    #[serde(skip)]
+    pub prompt: Prompt,
+}
+
+/// Differences between incoming registry prompts and the DB's registry rows
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncDiff {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    /// Registry rows no longer present upstream
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+    /// Incoming ids that collide with local prompts (local wins)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub local_conflicts: Vec<String>,
+}
+
+impl SyncDiff {
+    /// Whether applying the diff would change the DB
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Result of comparing `prompts_fts` with `prompts`
+#[derive(Debug, Clone, Serialize)]
+pub struct FtsConsistency {
+    pub prompt_count: usize,
+    pub fts_rows: usize,
+    /// Prompts with no FTS row
+    pub missing: Vec<String>,
+    /// FTS rows whose prompt no longer exists
+    pub orphaned: Vec<String>,
+    /// FTS rows whose text differs from the prompt row
+    pub stale: Vec<String>,
+}
+
+impl FtsConsistency {
+    pub fn is_consistent(&self) -> bool {
+        self.prompt_count == self.fts_rows
+            && self.missing.is_empty()
+            && self.orphaned.is_empty()
+            && self.stale.is_empty()
+    }
+}
+
+/// DB-side sync marker (`sync_meta`, SYNC_STRATEGY.md)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyncMeta {
+    pub schema_version: i32,
+    pub last_synced_at: String,
+    pub source_of_truth: String,
+    /// Content hash of the JSONL store at the last sync
+    pub jsonl_sha256: String,
+    pub record_count: usize,
+}
+
 /// Get the default database path
 pub fn db_path() -> PathBuf {
     crate::config::cache_dir()
@@ -35,6 +133,42 @@ impl Database {
 
     /// Open or create the database at a specific path
     pub fn open_at(path: &Path) -> Result<Self> {
+        let db = Self::connect(path)?;
+
+        // Bring the schema up to date
+        db.migrate()?;
+
+        Ok(db)
+    }
+
+    /// Open the default database read-only (see `open_read_only_at`)
+    pub fn open_read_only() -> Result<Self> {
+        Self::open_read_only_at(&db_path())
+    }
+
+    /// Open an existing database with `SQLITE_OPEN_READ_ONLY`
+    ///
+    /// Sets no pragmas, runs no migrations and never creates the file, so
+    /// many parallel readers don't contend for the write lock.
+    pub fn open_read_only_at(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_context(|| format!("Failed to open {:?} read-only", path))?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        Ok(Self { conn })
+    }
+
+    /// Open the default database without running migrations
+    ///
+    /// Used by `jfp db migrate` so it can back up and report each step.
+    pub fn open_unmigrated() -> Result<Self> {
+        Self::connect(&db_path())
+    }
+
+    /// Open a connection with the standard pragmas
+    fn connect(path: &Path) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
@@ -50,17 +184,10 @@ impl Database {
         conn.pragma_update(None, "foreign_keys", "ON")?;
         conn.busy_timeout(Duration::from_secs(5))?;
 
-        let db = Self {
-            conn,
-        };
-
-        // Initialize schema if needed
-        db.init_schema()?;
-
-        Ok(db)
+        Ok(Self { conn })
     }
 
-    /// Open an in-memory database (for testing)
+    /// Open an in-memory database (tests and `--read-only` without a DB)
     pub fn in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
         conn.pragma_update(None, "foreign_keys", "ON")?;
@@ -69,293 +196,487 @@ impl Database {
             conn,
         };
 
-        db.init_schema()?;
+        db.migrate()?;
         Ok(db)
     }
 
-    /// Initialize the database schema
-    fn init_schema(&self) -> Result<()> {
-        // Check current version
-        let version: i32 = self
+    /// Current schema version (0 for a brand-new file)
+    pub fn schema_version(&self) -> Result<i32> {
+        let has_meta: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'registry_meta')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_meta {
+            return Ok(0);
+        }
+
+        let version = self
             .conn
             .query_row(
                 "SELECT value FROM registry_meta WHERE key = 'schema_version'",
                 [],
-                |row| row.get::<_, String>(0).map(|s| s.parse().unwrap_or(0)),
+                |row| row.get::<_, String>(0),
             )
-            .unwrap_or(0);
-
-        if version < SCHEMA_VERSION {
-            self.conn.execute_batch(CREATE_SCHEMA)?;
-            self.conn.execute(
-                "INSERT OR REPLACE INTO registry_meta (key, value) VALUES ('schema_version', ?)",
-                params![SCHEMA_VERSION.to_string()],
-            )?;
+            .optional()?;
+        Ok(version.and_then(|v| v.parse().ok()).unwrap_or(0))
+    }
+
+    /// Applied/pending state of every known migration
+    pub fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        let current = self.schema_version()?;
+        MIGRATIONS
+            .iter()
+            .map(|m| {
+                let applied = m.version <= current;
+                let applied_at = if applied {
+                    self.get_meta(&migration_key(m.version)).ok()
+                } else {
+                    None
+                };
+                Ok(MigrationStatus {
+                    version: m.version,
+                    description: m.description,
+                    applied,
+                    applied_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Run every pending migration in order, one transaction per step
+    ///
+    /// An existing database is backed up (`VACUUM INTO`) before the first
+    /// pending step, so a failed migration leaves a restorable copy.
+    pub fn migrate(&self) -> Result<MigrationReport> {
+        let from_version = self.schema_version()?;
+        if from_version > SCHEMA_VERSION {
+            anyhow::bail!(
+                "database schema version {} is newer than this jfp supports ({})",
+                from_version,
+                SCHEMA_VERSION
+            );
         }
 
+        let pending: Vec<&Migration> = MIGRATIONS
+            .iter()
+            .filter(|m| m.version > from_version)
+            .collect();
+        let mut report = MigrationReport {
+            from_version,
+            to_version: from_version,
+            ..MigrationReport::default()
+        };
+        if pending.is_empty() {
+            return Ok(report);
+        }
+
+        if from_version > 0 {
+            report.backup_path = self.backup(from_version)?;
+        }
+
+        for migration in pending {
+            self.apply_migration(migration).with_context(|| {
+                let backup = report
+                    .backup_path
+                    .as_ref()
+                    .map(|p| format!("; backup at {}", p.display()))
+                    .unwrap_or_default();
+                format!("migration {} failed{}", migration.version, backup)
+            })?;
+            report.applied.push(migration.version);
+            report.to_version = migration.version;
+        }
+
+        Ok(report)
+    }
+
+    /// Apply one migration and record it, atomically
+    fn apply_migration(&self, migration: &Migration) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute_batch(migration.sql)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO registry_meta (key, value) VALUES ('schema_version', ?)",
+            params![migration.version.to_string()],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO registry_meta (key, value) VALUES (?, ?)",
+            params![migration_key(migration.version), Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
         Ok(())
     }
 
+    /// Copy a file-backed DB to `<db>.v<version>.bak`; in-memory DBs are skipped
+    fn backup(&self, version: i32) -> Result<Option<PathBuf>> {
+        let Some(path) = self.conn.path().filter(|p| !p.is_empty()) else {
+            return Ok(None);
+        };
+
+        let backup_path = PathBuf::from(format!("{}.v{}.bak", path, version));
+        if backup_path.exists() {
+            std::fs::remove_file(&backup_path)?;
+        }
+        self.conn
+            .execute(
+                "VACUUM INTO ?",
+                params![backup_path.display().to_string()],
+            )
+            .context("failed to back up database before migrating")?;
+        Ok(Some(backup_path))
+    }
+
     /// Insert or update a prompt
+    #[cfg(test)]
     pub fn upsert_prompt(&self, prompt: &Prompt) -> Result<()> {
-        let tags_text = prompt.tags.join(" ");
+        let tx = self.conn.unchecked_transaction()?;
+        write_prompt(&tx, prompt)?;
+        tx.commit()?;
+        Ok(())
+    }
 
-        self.conn.execute(
-            r#"
-            INSERT INTO prompts (id, title, content, description, category, tags_text, featured, version, author, saved_at, is_local)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            ON CONFLICT(id) DO UPDATE SET
-                title = excluded.title,
-                content = excluded.content,
-                description = excluded.description,
-                category = excluded.category,
-                tags_text = excluded.tags_text,
-                featured = excluded.featured,
-                version = excluded.version,
-                author = excluded.author,
-                saved_at = excluded.saved_at,
-                is_local = excluded.is_local,
-                updated_at = datetime('now')
-            "#,
-            params![
-                &prompt.id,
-                &prompt.title,
-                &prompt.content,
-                &prompt.description,
-                &prompt.category,
-                &tags_text,
-                prompt.featured as i32,
-                &prompt.version,
-                &prompt.author,
-                &prompt.saved_at,
-                prompt.is_local as i32,
-            ],
-        )?;
+    /// Bulk insert prompts (in a transaction)
+    ///
+    /// Writes exactly the same rows as `upsert_prompt`; statements are
+    /// prepared once and reused for every prompt.
+    pub fn bulk_upsert_prompts(&mut self, prompts: &[Prompt]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for prompt in prompts {
+            write_prompt(&tx, prompt)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
 
-        // Update tags (normalized)
-        self.conn.execute(
-            "DELETE FROM prompt_tags WHERE prompt_id = ?",
-            params![&prompt.id],
-        )?;
+    /// Transactionally replace every stored prompt (registry and local) with
+    /// `prompts`; used by `jfp db import-jsonl --mode replace`
+    pub fn replace_prompts(&mut self, prompts: &[Prompt]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM prompts_fts", [])?;
+        // Tags, variables and bundle membership cascade
+        tx.execute("DELETE FROM prompts", [])?;
+        for prompt in prompts {
+            write_prompt(&tx, prompt)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
 
-        for tag in &prompt.tags {
-            self.conn.execute(
-                "INSERT INTO prompt_tags (prompt_id, tag) VALUES (?, ?)",
-                params![&prompt.id, tag],
-            )?;
+    /// Diff incoming registry prompts against the current non-local rows
+    ///
+    /// With `prune` off (e.g. the bundled fallback, which is only a subset of
+    /// the registry), nothing is reported as removed.
+    pub fn plan_sync(&self, incoming: &[Prompt], prune: bool) -> Result<SyncDiff> {
+        let mut local_ids = HashSet::new();
+        let mut stored = HashMap::new();
+        for prompt in self.list_prompts()? {
+            if prompt.is_local {
+                local_ids.insert(prompt.id);
+            } else {
+                stored.insert(prompt.id.clone(), prompt);
+            }
         }
 
-        // Update variables
-        self.conn.execute(
-            "DELETE FROM prompt_variables WHERE prompt_id = ?",
-            params![&prompt.id],
-        )?;
+        let mut diff = SyncDiff::default();
+        let mut seen = HashSet::new();
+        for prompt in incoming {
+            if !seen.insert(prompt.id.as_str()) {
+                continue;
+            }
+            if local_ids.contains(&prompt.id) {
+                diff.local_conflicts.push(prompt.id.clone());
+                continue;
+            }
+            match stored.get(&prompt.id) {
+                None => diff.added.push(prompt.id.clone()),
+                Some(existing) if *existing != *stored_form(prompt) => {
+                    diff.changed.push(prompt.id.clone())
+                }
+                Some(_) => diff.unchanged += 1,
+            }
+        }
 
-        for var in &prompt.variables {
-            self.conn.execute(
-                r#"
-                INSERT INTO prompt_variables (prompt_id, name, var_type, required, description, default_value)
-                VALUES (?, ?, ?, ?, ?, ?)
-                "#,
-                params![
-                    &prompt.id,
-                    &var.name,
-                    var_type_to_str(&var.var_type),
-                    var.required as i32,
-                    &var.description,
-                    &var.default,
-                ],
-            )?;
+        if prune {
+            diff.removed = stored
+                .into_keys()
+                .filter(|id| !seen.contains(id.as_str()))
+                .collect();
+            diff.removed.sort();
         }
 
-        // Update FTS index
-        self.conn.execute(
-            "DELETE FROM prompts_fts WHERE id = ?",
-            params![&prompt.id],
-        )?;
+        Ok(diff)
+    }
 
-        self.conn.execute(
-            r#"
-            INSERT INTO prompts_fts (id, title, description, content, tags_text)
-            VALUES (?, ?, ?, ?, ?)
-            "#,
-            params![
-                &prompt.id,
-                &prompt.title,
-                &prompt.description,
-                &prompt.content,
-                &tags_text,
-            ],
-        )?;
+    /// Transactionally apply a registry sync: write added/changed prompts and
+    /// delete removed registry rows; local prompts are never touched
+    pub fn sync_prompts(&self, incoming: &[Prompt], prune: bool) -> Result<SyncDiff> {
+        let diff = self.plan_sync(incoming, prune)?;
+        if diff.is_empty() {
+            return Ok(diff);
+        }
 
-        Ok(())
+        let write: HashSet<&str> = diff
+            .added
+            .iter()
+            .chain(&diff.changed)
+            .map(String::as_str)
+            .collect();
+
+        let tx = self.conn.unchecked_transaction()?;
+        for prompt in incoming {
+            if write.contains(prompt.id.as_str()) {
+                write_prompt(&tx, prompt)?;
+            }
+        }
+        for id in &diff.removed {
+            tx.prepare_cached("DELETE FROM prompts_fts WHERE id = ?")?
+                .execute(params![id])?;
+            // Tags, variables and bundle membership cascade
+            tx.prepare_cached("DELETE FROM prompts WHERE id = ? AND is_local = 0")?
+                .execute(params![id])?;
+        }
+        tx.commit()?;
+
+        Ok(diff)
     }
 
-    /// Bulk insert prompts (in a transaction)
-    pub fn bulk_upsert_prompts(&mut self, prompts: &[Prompt]) -> Result<()> {
-        let tx = self.conn.transaction()?;
+    /// Transactionally write prompts from the local prompts dirs
+    ///
+    /// `stale` ids (loaded from the dirs before but gone now) are deleted
+    /// first when still local; every prompt in `prompts` is written as local,
+    /// overriding a registry row with the same id. Returns the number of
+    /// prompts added or changed.
+    pub fn sync_local_prompts(&self, prompts: &[Prompt], stale: &[String]) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        for id in stale {
+            let deleted = tx
+                .prepare_cached("DELETE FROM prompts WHERE id = ? AND is_local = 1")?
+                .execute(params![id])?;
+            if deleted > 0 {
+                tx.prepare_cached("DELETE FROM prompts_fts WHERE id = ?")?
+                    .execute(params![id])?;
+            }
+        }
 
+        let mut written = 0;
         for prompt in prompts {
-            tx.execute(
-                r#"
-                INSERT INTO prompts (id, title, content, description, category, featured, version, author, saved_at, is_local)
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                ON CONFLICT(id) DO UPDATE SET
-                    title = excluded.title,
-                    content = excluded.content,
-                    description = excluded.description,
-                    category = excluded.category,
-                    featured = excluded.featured,
-                    version = excluded.version,
-                    author = excluded.author,
-                    saved_at = excluded.saved_at,
-                    is_local = excluded.is_local,
-                    updated_at = datetime('now')
-                "#,
-                params![
-                    &prompt.id,
-                    &prompt.title,
-                    &prompt.content,
-                    &prompt.description,
-                    &prompt.category,
-                    prompt.featured as i32,
-                    &prompt.version,
-                    &prompt.author,
-                    &prompt.saved_at,
-                    prompt.is_local as i32,
-                ],
-            )?;
-
-            // Tags
-            tx.execute(
-                "DELETE FROM prompt_tags WHERE prompt_id = ?",
-                params![&prompt.id],
-            )?;
-            for tag in &prompt.tags {
-                tx.execute(
-                    "INSERT INTO prompt_tags (prompt_id, tag) VALUES (?, ?)",
-                    params![&prompt.id, tag],
-                )?;
+            if self.get_prompt(&prompt.id)?.as_ref() != Some(prompt) {
+                write_prompt(&tx, prompt)?;
+                written += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(written)
+    }
+
+    /// Transactionally write registry bundles, ordered as given
+    ///
+    /// Each bundle's membership is rewritten with member order as `position`;
+    /// members missing from the DB are skipped. With `prune`, registry
+    /// bundles absent from `incoming` are deleted. Local bundles are never
+    /// touched, and win over incoming bundles with the same id. Returns the
+    /// number of bundles written.
+    pub fn sync_bundles(&self, incoming: &[Bundle], prune: bool) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let bundle_ids = |sql: &str| -> Result<Vec<String>> {
+            let ids = tx
+                .prepare(sql)?
+                .query_map([], |row| row.get(0))?
+                .collect::<std::result::Result<_, _>>()?;
+            Ok(ids)
+        };
+        let local_ids: HashSet<String> = bundle_ids("SELECT id FROM bundles WHERE is_local = 1")?
+            .into_iter()
+            .collect();
+        let stored = bundle_ids("SELECT id FROM bundles WHERE is_local = 0")?;
+
+        let mut seen = HashSet::new();
+        for bundle in incoming {
+            if local_ids.contains(&bundle.id) || !seen.insert(bundle.id.as_str()) {
+                continue;
+            }
+            let bundle = Bundle {
+                is_local: false,
+                ..bundle.clone()
+            };
+            write_bundle(&tx, &bundle, seen.len() - 1)?;
+        }
+
+        if prune {
+            for id in stored.iter().filter(|id| !seen.contains(id.as_str())) {
+                // Membership cascades
+                tx.execute("DELETE FROM bundles WHERE id = ? AND is_local = 0", params![id])?;
             }
         }
 
         tx.commit()?;
-        Ok(())
+        Ok(seen.len())
     }
 
-    /// Get a prompt by ID
-    pub fn get_prompt(&self, id: &str) -> Result<Option<Prompt>> {
-        let prompt = self
-            .conn
+    /// Create or update a local bundle
+    ///
+    /// Keeps the bundle's list position; new bundles go after existing local
+    /// bundles. Fails if `id` belongs to a registry bundle.
+    pub fn save_local_bundle(&self, bundle: &Bundle) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let existing: Option<(i64, bool)> = tx
             .query_row(
-                r#"
-                SELECT id, title, content, description, category, featured, version, author, saved_at, is_local
-                FROM prompts WHERE id = ?
-                "#,
-                params![id],
-                |row| {
-                    Ok(Prompt {
-                        id: row.get(0)?,
-                        title: row.get(1)?,
-                        content: row.get(2)?,
-                        description: row.get(3)?,
-                        category: row.get(4)?,
-                        tags: Vec::new(), // Filled below
-                        variables: Vec::new(), // Filled below
-                        featured: row.get::<_, i32>(5)? != 0,
-                        version: row.get(6)?,
-                        author: row.get(7)?,
-                        saved_at: row.get(8)?,
-                        is_local: row.get::<_, i32>(9)? != 0,
-                    })
-                },
+                "SELECT position, is_local FROM bundles WHERE id = ?",
+                params![bundle.id],
+                |row| Ok((row.get(0)?, row.get::<_, i32>(1)? != 0)),
             )
             .optional()?;
 
-        let Some(mut prompt) = prompt else {
-            return Ok(None);
+        let position = match existing {
+            Some((_, false)) => {
+                anyhow::bail!("Bundle '{}' comes from the registry and cannot be edited", bundle.id)
+            }
+            Some((position, true)) => position,
+            None => tx.query_row(
+                "SELECT COALESCE(MAX(position) + 1, 0) FROM bundles WHERE is_local = 1",
+                [],
+                |row| row.get(0),
+            )?,
         };
 
-        // Load tags
-        prompt.tags = self.get_prompt_tags(&prompt.id)?;
+        let bundle = Bundle {
+            is_local: true,
+            ..bundle.clone()
+        };
+        write_bundle(&tx, &bundle, position as usize)?;
+        tx.commit()?;
+        Ok(())
+    }
 
-        // Load variables
-        prompt.variables = self.get_prompt_variables(&prompt.id)?;
+    /// Transactionally write bundles from the local prompts dirs
+    ///
+    /// `stale` ids (loaded from the dirs before but gone now) are deleted
+    /// first when still local; every bundle in `bundles` is written as local,
+    /// overriding a registry bundle with the same id and keeping its list
+    /// position. Returns the number of bundles written.
+    pub fn sync_local_bundles(&self, bundles: &[Bundle], stale: &[String]) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        for id in stale {
+            // Membership cascades
+            tx.execute("DELETE FROM bundles WHERE id = ? AND is_local = 1", params![id])?;
+        }
 
-        Ok(Some(prompt))
+        for bundle in bundles {
+            let position: i64 = match tx
+                .query_row("SELECT position FROM bundles WHERE id = ?", params![bundle.id], |row| {
+                    row.get(0)
+                })
+                .optional()?
+            {
+                Some(position) => position,
+                None => tx.query_row(
+                    "SELECT COALESCE(MAX(position) + 1, 0) FROM bundles WHERE is_local = 1",
+                    [],
+                    |row| row.get(0),
+                )?,
+            };
+            let bundle = Bundle {
+                is_local: true,
+                ..bundle.clone()
+            };
+            write_bundle(&tx, &bundle, position as usize)?;
+        }
+        tx.commit()?;
+        Ok(bundles.len())
     }
 
-    /// Get tags for a prompt
-    fn get_prompt_tags(&self, prompt_id: &str) -> Result<Vec<String>> {
-        let mut stmt = self
+    /// Delete a local bundle; returns false when there is no local bundle `id`
+    pub fn delete_local_bundle(&self, id: &str) -> Result<bool> {
+        let deleted = self
             .conn
-            .prepare("SELECT tag FROM prompt_tags WHERE prompt_id = ?")?;
-        let tags = stmt
-            .query_map(params![prompt_id], |row| row.get(0))?
-            .collect::<std::result::Result<Vec<String>, _>>()?;
-        Ok(tags)
+            .execute("DELETE FROM bundles WHERE id = ? AND is_local = 1", params![id])?;
+        Ok(deleted > 0)
     }
 
-    /// Get variables for a prompt
-    fn get_prompt_variables(&self, prompt_id: &str) -> Result<Vec<PromptVariable>> {
-        let mut stmt = self.conn.prepare(
+    /// Compare `prompts_fts` against `prompts`
+    pub fn fts_consistency(&self) -> Result<FtsConsistency> {
+        let prompt_count = self.prompt_count()?;
+        let fts_rows: usize = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM prompts_fts", [], |row| row.get(0))?;
+
+        let ids = |sql: &str| -> Result<Vec<String>> {
+            let mut stmt = self.conn.prepare(sql)?;
+            let ids = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<std::result::Result<Vec<String>, _>>()?;
+            Ok(ids)
+        };
+
+        let missing = ids(
+            "SELECT id FROM prompts WHERE id NOT IN (SELECT id FROM prompts_fts) ORDER BY id",
+        )?;
+        let orphaned = ids(
+            "SELECT DISTINCT id FROM prompts_fts WHERE id NOT IN (SELECT id FROM prompts) ORDER BY id",
+        )?;
+        let stale = ids(
             r#"
-            SELECT name, var_type, required, description, default_value
-            FROM prompt_variables WHERE prompt_id = ?
+            SELECT DISTINCT p.id FROM prompts p
+            JOIN prompts_fts f ON f.id = p.id
+            WHERE f.title IS NOT p.title
+               OR f.description IS NOT p.description
+               OR f.content IS NOT p.content
+               OR f.tags_text IS NOT p.tags_text
+            ORDER BY p.id
             "#,
         )?;
 
-        let vars = stmt
-            .query_map(params![prompt_id], |row| {
-                Ok(PromptVariable {
-                    name: row.get(0)?,
-                    var_type: str_to_var_type(&row.get::<_, String>(1)?),
-                    required: row.get::<_, i32>(2)? != 0,
-                    description: row.get(3)?,
-                    default: row.get(4)?,
-                })
-            })?
-            .collect::<std::result::Result<Vec<_>, _>>()?;
-
-        Ok(vars)
+        Ok(FtsConsistency {
+            prompt_count,
+            fts_rows,
+            missing,
+            orphaned,
+            stale,
+        })
     }
 
-    /// List all prompts
-    pub fn list_prompts(&self) -> Result<Vec<Prompt>> {
-        let mut stmt = self.conn.prepare(
+    /// Rebuild `prompts_fts` (and `tags_text`) from `prompts`/`prompt_tags`
+    ///
+    /// Returns the number of indexed prompts.
+    pub fn reindex_fts(&self) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute_batch(
             r#"
-            SELECT id, title, content, description, category, featured, version, author, saved_at, is_local
-            FROM prompts ORDER BY title
+            UPDATE prompts SET tags_text = COALESCE((
+                SELECT group_concat(tag, ' ') FROM (
+                    SELECT tag FROM prompt_tags
+                    WHERE prompt_id = prompts.id
+                    ORDER BY rowid
+                )
+            ), '');
+            DELETE FROM prompts_fts;
             "#,
         )?;
+        let indexed = tx.execute(
+            r#"
+            INSERT INTO prompts_fts (id, title, description, content, tags_text)
+            SELECT id, title, description, content, tags_text FROM prompts
+            "#,
+            [],
+        )?;
+        tx.commit()?;
+        Ok(indexed)
+    }
 
-        let prompts = stmt
-            .query_map([], |row| {
-                Ok(Prompt {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    content: row.get(2)?,
-                    description: row.get(3)?,
-                    category: row.get(4)?,
-                    tags: Vec::new(),
-                    variables: Vec::new(),
-                    featured: row.get::<_, i32>(5)? != 0,
-                    version: row.get(6)?,
-                    author: row.get(7)?,
-                    saved_at: row.get(8)?,
-                    is_local: row.get::<_, i32>(9)? != 0,
-                })
-            })?
-            .collect::<std::result::Result<Vec<_>, _>>()?;
-
-        // Load tags for each prompt
-        let mut result = Vec::with_capacity(prompts.len());
-        for mut prompt in prompts {
-            prompt.tags = self.get_prompt_tags(&prompt.id)?;
-            result.push(prompt);
-        }
+    /// Get a prompt by ID
+    pub fn get_prompt(&self, id: &str) -> Result<Option<Prompt>> {
+        let sql = format!("SELECT {} FROM prompts p WHERE p.id = ?", PROMPT_COLUMNS);
+        let prompt = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_row(params![id], prompt_from_row)
+            .optional()?;
+        Ok(prompt)
+    }
 
-        Ok(result)
+    /// List all prompts
+    pub fn list_prompts(&self) -> Result<Vec<Prompt>> {
+        self.list_prompts_filtered(None, None, false)
     }
 
     /// List prompts with optional filters
@@ -369,17 +690,17 @@ impl Database {
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
         if let Some(cat) = category {
-            conditions.push("category = ?");
+            conditions.push("p.category = ?");
             params_vec.push(Box::new(cat.to_string()));
         }
 
         if let Some(t) = tag {
-            conditions.push("id IN (SELECT prompt_id FROM prompt_tags WHERE tag = ?)");
+            conditions.push("p.id IN (SELECT prompt_id FROM prompt_tags WHERE tag = ?)");
             params_vec.push(Box::new(t.to_string()));
         }
 
         if featured_only {
-            conditions.push("featured = 1");
+            conditions.push("p.featured = 1");
         }
 
         let where_clause = if conditions.is_empty() {
@@ -389,43 +710,18 @@ impl Database {
         };
 
         let sql = format!(
-            r#"
-            SELECT id, title, content, description, category, featured, version, author, saved_at, is_local
-            FROM prompts {} ORDER BY title
-            "#,
-            where_clause
+            "SELECT {} FROM prompts p {} ORDER BY p.title",
+            PROMPT_COLUMNS, where_clause
         );
 
-        let mut stmt = self.conn.prepare(&sql)?;
+        let mut stmt = self.conn.prepare_cached(&sql)?;
         let params: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
 
         let prompts = stmt
-            .query_map(params.as_slice(), |row| {
-                Ok(Prompt {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    content: row.get(2)?,
-                    description: row.get(3)?,
-                    category: row.get(4)?,
-                    tags: Vec::new(),
-                    variables: Vec::new(),
-                    featured: row.get::<_, i32>(5)? != 0,
-                    version: row.get(6)?,
-                    author: row.get(7)?,
-                    saved_at: row.get(8)?,
-                    is_local: row.get::<_, i32>(9)? != 0,
-                })
-            })?
+            .query_map(params.as_slice(), prompt_from_row)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
-        // Load tags for each prompt
-        let mut result = Vec::with_capacity(prompts.len());
-        for mut prompt in prompts {
-            prompt.tags = self.get_prompt_tags(&prompt.id)?;
-            result.push(prompt);
-        }
-
-        Ok(result)
+        Ok(prompts)
     }
 
     /// Get category counts
@@ -477,52 +773,100 @@ impl Database {
         Ok(count as usize)
     }
 
+    /// Get a bundle with its member ids in `position` order
+    pub fn get_bundle(&self, id: &str) -> Result<Option<Bundle>> {
+        let sql = format!("SELECT {} FROM bundles b WHERE b.id = ?", BUNDLE_COLUMNS);
+        let bundle = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_row(params![id], bundle_from_row)
+            .optional()?;
+        Ok(bundle)
+    }
+
+    /// List bundles: registry bundles, then local ones, each in `position`
+    /// order
+    pub fn list_bundles(&self) -> Result<Vec<Bundle>> {
+        let sql = format!(
+            "SELECT {} FROM bundles b ORDER BY b.is_local, b.position, b.id",
+            BUNDLE_COLUMNS
+        );
+        let bundles = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_map([], bundle_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(bundles)
+    }
+
+    /// Recorded revisions of prompt `id`, oldest first
+    pub fn prompt_revisions(&self, id: &str) -> Result<Vec<PromptRevision>> {
+        let mut stmt = self.conn.prepare_cached(
+            r#"
+            SELECT content_hash, version, source, recorded_at, snapshot
+            FROM prompt_revisions WHERE prompt_id = ? ORDER BY id
+            "#,
+        )?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok(PromptRevision {
+                    rev: 0,
+                    content_hash: row.get(0)?,
+                    version: row.get(1)?,
+                    source: row.get(2)?,
+                    recorded_at: row.get(3)?,
+                    prompt: json_column(row, 4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(rows
+            .into_iter()
+            .enumerate()
+            .map(|(index, revision)| PromptRevision {
+                rev: index + 1,
+                ..revision
+            })
+            .collect())
+    }
+
+    /// Get bundle count
+    pub fn bundle_count(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM bundles", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
     /// Full-text search using FTS5
     pub fn search(&self, query: &str, limit: usize) -> Result<Vec<(Prompt, f64)>> {
         // BM25 weights: id=5, title=3, description=2, content=1, tags=2
-        let mut stmt = self.conn.prepare(
+        let sql = format!(
             r#"
-            SELECT p.id, p.title, p.content, p.description, p.category,
-                   p.featured, p.version, p.author, p.saved_at, p.is_local,
-                   bm25(prompts_fts, 5.0, 3.0, 2.0, 1.0, 2.0) as score
-            FROM prompts_fts f
+            SELECT {}, f.score
+            FROM (
+                SELECT id, bm25(prompts_fts, 5.0, 3.0, 2.0, 1.0, 2.0) AS score
+                FROM prompts_fts
+                WHERE prompts_fts MATCH ?
+                ORDER BY score
+                LIMIT ?
+            ) f
             JOIN prompts p ON f.id = p.id
-            WHERE prompts_fts MATCH ?
-            ORDER BY score
-            LIMIT ?
+            ORDER BY f.score
             "#,
-        )?;
+            PROMPT_COLUMNS
+        );
 
-        let results = stmt
+        let results = self
+            .conn
+            .prepare_cached(&sql)?
             .query_map(params![query, limit as i64], |row| {
-                Ok((
-                    Prompt {
-                        id: row.get(0)?,
-                        title: row.get(1)?,
-                        content: row.get(2)?,
-                        description: row.get(3)?,
-                        category: row.get(4)?,
-                        tags: Vec::new(),
-                        variables: Vec::new(),
-                        featured: row.get::<_, i32>(5)? != 0,
-                        version: row.get(6)?,
-                        author: row.get(7)?,
-                        saved_at: row.get(8)?,
-                        is_local: row.get::<_, i32>(9)? != 0,
-                    },
-                    row.get::<_, f64>(10)?,
-                ))
+                // Negate because BM25 returns negative scores
+                Ok((prompt_from_row(row)?, -row.get::<_, f64>(PROMPT_COLUMN_COUNT)?))
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
-        // Load tags for each result
-        let mut final_results = Vec::with_capacity(results.len());
-        for (mut prompt, score) in results {
-            prompt.tags = self.get_prompt_tags(&prompt.id)?;
-            final_results.push((prompt, -score)); // Negate because BM25 returns negative scores
-        }
-
-        Ok(final_results)
+        Ok(results)
     }
 
     /// Run integrity check
@@ -534,12 +878,17 @@ impl Database {
     }
 
     /// Checkpoint WAL
-    #[allow(dead_code)]
     pub fn checkpoint(&self) -> Result<()> {
         self.conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")?;
         Ok(())
     }
 
+    /// Rebuild the database file, reclaiming free pages
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
     /// Get metadata value
     pub fn get_meta(&self, key: &str) -> Result<String> {
         let value: String = self.conn.query_row(
@@ -558,6 +907,347 @@ impl Database {
         )?;
         Ok(())
     }
+
+    /// Read the sync marker, if a sync has ever been recorded
+    pub fn sync_meta(&self) -> Result<Option<SyncMeta>> {
+        let meta = self
+            .conn
+            .query_row(
+                r#"
+                SELECT schema_version, last_synced_at, source_of_truth, jsonl_sha256, record_count
+                FROM sync_meta WHERE id = 1
+                "#,
+                [],
+                |row| {
+                    Ok(SyncMeta {
+                        schema_version: row.get(0)?,
+                        last_synced_at: row.get(1)?,
+                        source_of_truth: row.get(2)?,
+                        jsonl_sha256: row.get(3)?,
+                        record_count: row.get::<_, i64>(4)? as usize,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(meta)
+    }
+
+    /// Replace the sync marker
+    pub fn set_sync_meta(&self, meta: &SyncMeta) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT OR REPLACE INTO sync_meta
+                (id, schema_version, last_synced_at, source_of_truth, jsonl_sha256, record_count)
+            VALUES (1, ?, ?, ?, ?, ?)
+            "#,
+            params![
+                meta.schema_version,
+                meta.last_synced_at,
+                meta.source_of_truth,
+                meta.jsonl_sha256,
+                meta.record_count as i64
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// `prompt` as it reads back from the DB: `prompt_tags` keeps the first of
+/// any repeated tag, so duplicates are dropped in order
+fn stored_form(prompt: &Prompt) -> Cow<'_, Prompt> {
+    let mut seen = HashSet::new();
+    if prompt.tags.iter().all(|tag| seen.insert(tag.as_str())) {
+        return Cow::Borrowed(prompt);
+    }
+    let mut normalized = prompt.clone();
+    let mut seen = HashSet::new();
+    normalized.tags.retain(|tag| seen.insert(tag.clone()));
+    Cow::Owned(normalized)
+}
+
+/// Write one prompt's rows: `prompts` (with `tags_text`), `prompt_tags`,
+/// `prompt_variables` and `prompts_fts`, recording a revision first
+///
+/// Shared by the single and bulk upsert paths so both produce identical rows.
+/// Statements go through the connection's prepared-statement cache.
+fn write_prompt(conn: &Connection, prompt: &Prompt) -> Result<()> {
+    let prompt = &*stored_form(prompt);
+    record_revision(conn, prompt)?;
+    let tags_text = prompt.tags.join(" ");
+
+    conn.prepare_cached(
+        r#"
+        INSERT INTO prompts (id, title, content, description, category, tags_text, featured, version, author, saved_at, is_local)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            title = excluded.title,
+            content = excluded.content,
+            description = excluded.description,
+            category = excluded.category,
+            tags_text = excluded.tags_text,
+            featured = excluded.featured,
+            version = excluded.version,
+            author = excluded.author,
+            saved_at = excluded.saved_at,
+            is_local = excluded.is_local,
+            updated_at = datetime('now')
+        "#,
+    )?
+    .execute(params![
+        &prompt.id,
+        &prompt.title,
+        &prompt.content,
+        &prompt.description,
+        &prompt.category,
+        &tags_text,
+        prompt.featured as i32,
+        &prompt.version,
+        &prompt.author,
+        &prompt.saved_at,
+        prompt.is_local as i32,
+    ])?;
+
+    // Update tags (normalized)
+    conn.prepare_cached("DELETE FROM prompt_tags WHERE prompt_id = ?")?
+        .execute(params![&prompt.id])?;
+    let mut insert_tag =
+        conn.prepare_cached("INSERT OR IGNORE INTO prompt_tags (prompt_id, tag) VALUES (?, ?)")?;
+    for tag in &prompt.tags {
+        insert_tag.execute(params![&prompt.id, tag])?;
+    }
+
+    // Update variables
+    conn.prepare_cached("DELETE FROM prompt_variables WHERE prompt_id = ?")?
+        .execute(params![&prompt.id])?;
+    let mut insert_var = conn.prepare_cached(
+        r#"
+        INSERT INTO prompt_variables (prompt_id, name, var_type, required, description, default_value)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )?;
+    for var in &prompt.variables {
+        insert_var.execute(params![
+            &prompt.id,
+            &var.name,
+            var_type_to_str(&var.var_type),
+            var.required as i32,
+            &var.description,
+            &var.default,
+        ])?;
+    }
+
+    // Update FTS index
+    conn.prepare_cached("DELETE FROM prompts_fts WHERE id = ?")?
+        .execute(params![&prompt.id])?;
+    conn.prepare_cached(
+        r#"
+        INSERT INTO prompts_fts (id, title, description, content, tags_text)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+    )?
+    .execute(params![
+        &prompt.id,
+        &prompt.title,
+        &prompt.description,
+        &prompt.content,
+        &tags_text,
+    ])?;
+
+    Ok(())
+}
+
+/// Append a `prompt_revisions` row when `prompt` differs from the latest one
+///
+/// A prompt stored before history existed gets its stored row recorded
+/// first, so the version being replaced is not lost.
+fn record_revision(conn: &Connection, prompt: &Prompt) -> Result<()> {
+    let mut latest: Option<String> = conn
+        .prepare_cached(
+            "SELECT content_hash FROM prompt_revisions WHERE prompt_id = ? ORDER BY id DESC LIMIT 1",
+        )?
+        .query_row(params![prompt.id], |row| row.get(0))
+        .optional()?;
+
+    if latest.is_none() {
+        let sql = format!("SELECT {} FROM prompts p WHERE p.id = ?", PROMPT_COLUMNS);
+        let stored = conn
+            .prepare_cached(&sql)?
+            .query_row(params![prompt.id], prompt_from_row)
+            .optional()?;
+        if let Some(stored) = stored {
+            latest = Some(insert_revision(conn, &stored)?);
+        }
+    }
+
+    if latest.as_deref() != Some(revision_hash(prompt)?.as_str()) {
+        insert_revision(conn, prompt)?;
+    }
+    Ok(())
+}
+
+fn insert_revision(conn: &Connection, prompt: &Prompt) -> Result<String> {
+    let hash = revision_hash(prompt)?;
+    conn.prepare_cached(
+        r#"
+        INSERT INTO prompt_revisions (prompt_id, content_hash, version, source, snapshot, recorded_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )?
+    .execute(params![
+        prompt.id,
+        hash,
+        prompt.version,
+        if prompt.is_local { "local" } else { "registry" },
+        serde_json::to_string(prompt)?,
+        Utc::now().to_rfc3339()
+    ])?;
+    Ok(hash)
+}
+
+/// SHA-256 over a prompt's content and metadata, including `version`;
+/// `saved_at` is bookkeeping and left out
+pub fn revision_hash(prompt: &Prompt) -> Result<String> {
+    let prompt = Prompt {
+        saved_at: None,
+        ..prompt.clone()
+    };
+    Ok(format!("{:x}", Sha256::digest(serde_json::to_vec(&prompt)?)))
+}
+
+/// Write one bundle row at `position` and replace its `bundle_prompts`
+fn write_bundle(conn: &Connection, bundle: &Bundle, position: usize) -> Result<()> {
+    // Upsert rather than replace: deleting the row would cascade to members
+    conn.prepare_cached(
+        r#"
+        INSERT INTO bundles (id, title, description, version, featured, author, position, is_local)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            title = excluded.title,
+            description = excluded.description,
+            version = excluded.version,
+            featured = excluded.featured,
+            author = excluded.author,
+            position = excluded.position,
+            is_local = excluded.is_local
+        "#,
+    )?
+    .execute(params![
+        bundle.id,
+        bundle.title,
+        bundle.description,
+        bundle.version,
+        bundle.featured as i32,
+        bundle.author,
+        position as i64,
+        bundle.is_local as i32
+    ])?;
+
+    conn.prepare_cached("DELETE FROM bundle_prompts WHERE bundle_id = ?")?
+        .execute(params![bundle.id])?;
+    let mut insert = conn.prepare_cached(
+        r#"
+        INSERT OR IGNORE INTO bundle_prompts (bundle_id, prompt_id, position)
+        SELECT ?, id, ? FROM prompts WHERE id = ?
+        "#,
+    )?;
+    for (position, prompt_id) in bundle.prompts.iter().enumerate() {
+        insert.execute(params![bundle.id, position as i64, prompt_id])?;
+    }
+
+    Ok(())
+}
+
+/// `registry_meta` key recording when a migration was applied
+fn migration_key(version: i32) -> String {
+    format!("migration_{}", version)
+}
+
+/// Select list read by `prompt_from_row` (prompts aliased as `p`)
+///
+/// Tags and variables are aggregated per row as JSON arrays, so any listing
+/// is a single query instead of one extra query per prompt.
+const PROMPT_COLUMNS: &str = r#"
+    p.id, p.title, p.content, p.description, p.category,
+    p.featured, p.version, p.author, p.saved_at, p.is_local,
+    (SELECT json_group_array(t.tag ORDER BY t.rowid)
+       FROM prompt_tags t WHERE t.prompt_id = p.id),
+    (SELECT json_group_array(json_object(
+                'name', v.name, 'type', v.var_type, 'required', v.required,
+                'description', v.description, 'default', v.default_value
+            ) ORDER BY v.id)
+       FROM prompt_variables v WHERE v.prompt_id = p.id)
+"#;
+
+/// Number of columns in `PROMPT_COLUMNS`; extra columns start here
+const PROMPT_COLUMN_COUNT: usize = 12;
+
+/// Select list read by `bundle_from_row` (bundles aliased as `b`)
+const BUNDLE_COLUMNS: &str = r#"
+    b.id, b.title, b.description, b.version, b.featured, b.author, b.is_local,
+    (SELECT json_group_array(bp.prompt_id ORDER BY bp.position)
+       FROM bundle_prompts bp WHERE bp.bundle_id = b.id)
+"#;
+
+/// Map a `BUNDLE_COLUMNS` row to a `Bundle`; `prompt_count` counts the
+/// members stored in the DB
+fn bundle_from_row(row: &Row) -> rusqlite::Result<Bundle> {
+    let prompts: Vec<String> = json_column(row, 7)?;
+    Ok(Bundle {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        description: row.get(2)?,
+        version: row.get(3)?,
+        prompt_count: prompts.len(),
+        featured: row.get::<_, i32>(4)? != 0,
+        author: row.get(5)?,
+        prompts,
+        is_local: row.get::<_, i32>(6)? != 0,
+    })
+}
+
+/// `prompt_variables` row as aggregated by `PROMPT_COLUMNS`
+#[derive(Deserialize)]
+struct StoredVariable {
+    name: String,
+    #[serde(rename = "type")]
+    var_type: String,
+    required: i64,
+    description: Option<String>,
+    default: Option<String>,
+}
+
+/// Map a `PROMPT_COLUMNS` row to a `Prompt`
+fn prompt_from_row(row: &Row) -> rusqlite::Result<Prompt> {
+    let variables: Vec<StoredVariable> = json_column(row, 11)?;
+    Ok(Prompt {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        content: row.get(2)?,
+        description: row.get(3)?,
+        category: row.get(4)?,
+        tags: json_column(row, 10)?,
+        variables: variables
+            .into_iter()
+            .map(|v| PromptVariable {
+                name: v.name,
+                var_type: str_to_var_type(&v.var_type),
+                required: v.required != 0,
+                description: v.description,
+                default: v.default,
+            })
+            .collect(),
+        featured: row.get::<_, i32>(5)? != 0,
+        version: row.get(6)?,
+        author: row.get(7)?,
+        saved_at: row.get(8)?,
+        is_local: row.get::<_, i32>(9)? != 0,
+    })
+}
+
+fn json_column<T: DeserializeOwned>(row: &Row, idx: usize) -> rusqlite::Result<T> {
+    let raw: String = row.get(idx)?;
+    serde_json::from_str(&raw)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
 }
 
 fn var_type_to_str(vt: &VariableType) -> &'static str {
@@ -583,6 +1273,305 @@ fn str_to_var_type(s: &str) -> VariableType {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::tempdir;
+
+    /// Build a database the way the v1 schema left it
+    fn create_v1_db(path: &Path) -> Result<()> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(MIGRATIONS[0].sql)?;
+        conn.execute(
+            "INSERT INTO registry_meta (key, value) VALUES ('schema_version', '1')",
+            [],
+        )?;
+        conn.execute(
+            "INSERT INTO prompts (id, title, content) VALUES ('kept', 'Kept', 'survives migration')",
+            [],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_v1_database_upgrades_to_current() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("jfp.db");
+        create_v1_db(&path)?;
+
+        let db = Database::connect(&path)?;
+        assert_eq!(db.schema_version()?, 1);
+        let status = db.migration_status()?;
+        assert!(status[0].applied);
+        assert!(status[1..].iter().all(|m| !m.applied));
+
+        let report = db.migrate()?;
+        assert_eq!(report.from_version, 1);
+        assert_eq!(report.to_version, SCHEMA_VERSION);
+        assert_eq!(
+            report.applied,
+            (2..=SCHEMA_VERSION).collect::<Vec<_>>()
+        );
+        let backup = report.backup_path.expect("existing DB is backed up");
+        assert!(backup.exists());
+
+        assert_eq!(db.schema_version()?, SCHEMA_VERSION);
+        assert!(db.migration_status()?.iter().all(|m| m.applied));
+        assert!(db.get_meta(&migration_key(SCHEMA_VERSION)).is_ok());
+        assert!(db.get_prompt("kept")?.is_some());
+
+        let index_exists: bool = db.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = 'idx_prompts_category')",
+            [],
+            |row| row.get(0),
+        )?;
+        assert!(index_exists);
+
+        // Re-running is a no-op
+        assert!(Database::open_at(&path)?.migrate()?.applied.is_empty());
+        Ok(())
+    }
+
+    fn sample_prompt_with_variables(id: &str) -> Prompt {
+        let mut prompt = Prompt::new(id, "Bulk Prompt", "Review {{LANGUAGE}} code");
+        prompt.description = Some("searchable description".to_string());
+        prompt.tags = vec!["review".to_string(), "quality".to_string()];
+        prompt.variables = vec![PromptVariable {
+            name: "LANGUAGE".to_string(),
+            var_type: VariableType::Select,
+            required: true,
+            description: Some("Language".to_string()),
+            default: Some("rust".to_string()),
+        }];
+        prompt
+    }
+
+    /// Dump every row a prompt owns, across all prompt tables
+    fn prompt_rows(db: &Database, id: &str) -> Result<Vec<String>> {
+        let mut rows = Vec::new();
+        for sql in [
+            "SELECT title || '|' || content || '|' || IFNULL(description, '') || '|' || tags_text FROM prompts WHERE id = ?",
+            "SELECT tag FROM prompt_tags WHERE prompt_id = ? ORDER BY rowid",
+            "SELECT name || '|' || var_type || '|' || required || '|' || IFNULL(default_value, '') FROM prompt_variables WHERE prompt_id = ?",
+            "SELECT title || '|' || content || '|' || tags_text FROM prompts_fts WHERE id = ?",
+        ] {
+            let mut stmt = db.conn.prepare(sql)?;
+            let values = stmt
+                .query_map(params![id], |row| row.get::<_, String>(0))?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            rows.push(values.join(";"));
+        }
+        Ok(rows)
+    }
+
+    #[test]
+    fn test_bulk_upsert_matches_single_upsert() -> Result<()> {
+        let single = Database::in_memory()?;
+        single.upsert_prompt(&sample_prompt_with_variables("p1"))?;
+
+        let mut bulk = Database::in_memory()?;
+        bulk.bulk_upsert_prompts(&[sample_prompt_with_variables("p1")])?;
+
+        assert_eq!(prompt_rows(&single, "p1")?, prompt_rows(&bulk, "p1")?);
+        assert!(bulk.fts_consistency()?.is_consistent());
+
+        let loaded = bulk.get_prompt("p1")?.expect("bulk prompt exists");
+        assert_eq!(loaded.variables.len(), 1);
+        assert_eq!(bulk.search("searchable", 10)?.len(), 1);
+        assert_eq!(bulk.search("quality", 10)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reindex_repairs_fts() -> Result<()> {
+        let db = Database::in_memory()?;
+        db.upsert_prompt(&sample_prompt_with_variables("p1"))?;
+        db.upsert_prompt(&sample_prompt_with_variables("p2"))?;
+
+        // Simulate the old bulk path: no FTS row, no tags_text
+        db.conn.execute_batch(
+            "DELETE FROM prompts_fts WHERE id = 'p1';
+             UPDATE prompts SET tags_text = NULL WHERE id = 'p1';
+             UPDATE prompts_fts SET content = 'outdated' WHERE id = 'p2';
+             INSERT INTO prompts_fts (id, title) VALUES ('gone', 'Gone');",
+        )?;
+
+        let check = db.fts_consistency()?;
+        assert!(!check.is_consistent());
+        assert_eq!(check.missing, vec!["p1"]);
+        assert_eq!(check.orphaned, vec!["gone"]);
+        assert_eq!(check.stale, vec!["p2"]);
+
+        assert_eq!(db.reindex_fts()?, 2);
+        assert!(db.fts_consistency()?.is_consistent());
+        // Both prompts differ only by id, so their rebuilt rows must match
+        assert_eq!(prompt_rows(&db, "p1")?, prompt_rows(&db, "p2")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync_prunes_removed_registry_prompts_only() -> Result<()> {
+        let db = Database::in_memory()?;
+        db.upsert_prompt(&Prompt::new("kept", "Kept", "same"))?;
+        db.upsert_prompt(&Prompt::new("edited", "Edited", "old content"))?;
+        db.upsert_prompt(&Prompt::new("gone", "Gone", "removed upstream"))?;
+        let mut local = Prompt::new("mine", "Mine", "local prompt");
+        local.is_local = true;
+        db.upsert_prompt(&local)?;
+
+        let incoming = vec![
+            Prompt::new("kept", "Kept", "same"),
+            Prompt::new("edited", "Edited", "new content"),
+            Prompt::new("fresh", "Fresh", "new upstream"),
+            Prompt::new("mine", "Mine", "registry copy"),
+        ];
+
+        let plan = db.plan_sync(&incoming, true)?;
+        assert_eq!(plan.added, vec!["fresh"]);
+        assert_eq!(plan.changed, vec!["edited"]);
+        assert_eq!(plan.removed, vec!["gone"]);
+        assert_eq!(plan.unchanged, 1);
+        assert_eq!(plan.local_conflicts, vec!["mine"]);
+        // Planning never writes
+        assert!(db.get_prompt("gone")?.is_some());
+
+        db.sync_prompts(&incoming, true)?;
+        assert!(db.get_prompt("gone")?.is_none());
+        assert!(db.search("removed", 10)?.is_empty());
+        assert_eq!(db.get_prompt("edited")?.map(|p| p.content).as_deref(), Some("new content"));
+        assert_eq!(db.get_prompt("mine")?.map(|p| p.content).as_deref(), Some("local prompt"));
+        assert!(db.fts_consistency()?.is_consistent());
+
+        // A second sync is a no-op
+        let again = db.sync_prompts(&incoming, true)?;
+        assert!(again.is_empty());
+        assert_eq!(again.unchanged, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync_with_duplicate_tags_is_stable() -> Result<()> {
+        let db = Database::in_memory()?;
+        let mut prompt = Prompt::new("p", "Title", "content");
+        prompt.tags = vec!["rust".to_string(), "cli".to_string(), "rust".to_string()];
+
+        assert_eq!(db.sync_prompts(std::slice::from_ref(&prompt), true)?.added, vec!["p"]);
+        let stored = db.get_prompt("p")?.expect("synced");
+        assert_eq!(stored.tags, vec!["rust", "cli"]);
+        assert!(db.fts_consistency()?.is_consistent());
+
+        // The stored round trip matches, so re-syncing changes nothing
+        let again = db.sync_prompts(&[prompt], true)?;
+        assert!(again.is_empty());
+        assert_eq!(again.unchanged, 1);
+        assert_eq!(db.prompt_revisions("p")?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync_without_prune_keeps_missing_rows() -> Result<()> {
+        let db = Database::in_memory()?;
+        db.upsert_prompt(&Prompt::new("existing", "Existing", "content"))?;
+
+        let diff = db.sync_prompts(&[Prompt::new("other", "Other", "content")], false)?;
+        assert!(diff.removed.is_empty());
+        assert!(db.get_prompt("existing")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync_bundles_keeps_order_and_skips_missing_members() -> Result<()> {
+        let db = Database::in_memory()?;
+        db.sync_prompts(
+            &[Prompt::new("a", "A", "a"), Prompt::new("b", "B", "b")],
+            true,
+        )?;
+
+        let mut second = Bundle::new("second", "Second");
+        second.prompts = vec!["b".to_string(), "missing".to_string(), "a".to_string()];
+        let first = Bundle::new("first", "First");
+        db.sync_bundles(&[second.clone(), first.clone()], true)?;
+
+        let bundles = db.list_bundles()?;
+        let ids: Vec<&str> = bundles.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, ["second", "first"]);
+        assert_eq!(bundles[0].prompts, ["b", "a"]);
+        assert_eq!(bundles[0].prompt_count, 2);
+
+        // Pruning drops bundles the registry no longer serves
+        db.sync_bundles(std::slice::from_ref(&first), true)?;
+        assert!(db.get_bundle("second")?.is_none());
+        assert_eq!(db.bundle_count()?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_sync_never_touches_local_bundles() -> Result<()> {
+        let db = Database::in_memory()?;
+        db.sync_prompts(&[Prompt::new("a", "A", "a"), Prompt::new("b", "B", "b")], true)?;
+        db.sync_bundles(&[Bundle::new("registry", "Registry")], true)?;
+
+        let mut mine = Bundle::new("mine", "Mine");
+        mine.prompts = vec!["b".to_string(), "a".to_string()];
+        db.save_local_bundle(&mine)?;
+        assert!(db.save_local_bundle(&Bundle::new("registry", "Taken")).is_err());
+
+        // Same id from the registry is ignored; pruning skips local bundles
+        let mut upstream = Bundle::new("mine", "Upstream");
+        upstream.prompts = vec!["a".to_string()];
+        db.sync_bundles(&[upstream], true)?;
+
+        let stored = db.get_bundle("mine")?.expect("local bundle kept");
+        assert!(stored.is_local);
+        assert_eq!(stored.title, "Mine");
+        assert_eq!(stored.prompts, ["b", "a"]);
+        assert!(db.get_bundle("registry")?.is_none());
+
+        assert!(!db.delete_local_bundle("missing")?);
+        assert!(db.delete_local_bundle("mine")?);
+        assert_eq!(db.bundle_count()?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_writes_record_distinct_revisions() -> Result<()> {
+        let db = Database::in_memory()?;
+        let v1 = Prompt::new("p", "P", "first");
+        db.upsert_prompt(&v1)?;
+        db.upsert_prompt(&v1)?;
+
+        let mut v2 = v1.clone();
+        v2.content = "second".to_string();
+        v2.version = Some("2.0.0".to_string());
+        db.sync_prompts(std::slice::from_ref(&v2), true)?;
+        // Reverting is a new revision too
+        db.upsert_prompt(&v1)?;
+
+        let revisions = db.prompt_revisions("p")?;
+        let revs: Vec<usize> = revisions.iter().map(|r| r.rev).collect();
+        assert_eq!(revs, [1, 2, 3]);
+        assert_eq!(revisions[1].prompt, v2);
+        assert_eq!(revisions[1].version.as_deref(), Some("2.0.0"));
+        assert_eq!(revisions[0].content_hash, revisions[2].content_hash);
+
+        // A prompt stored before history existed keeps its old version
+        db.conn.execute("DELETE FROM prompt_revisions", [])?;
+        db.upsert_prompt(&v2)?;
+        let revisions = db.prompt_revisions("p")?;
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].prompt, v1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fresh_database_skips_backup() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("jfp.db");
+
+        let db = Database::connect(&path)?;
+        let report = db.migrate()?;
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, SCHEMA_VERSION);
+        assert!(report.backup_path.is_none());
+        Ok(())
+    }
 
     #[test]
     fn test_open_in_memory() -> Result<()> {
@@ -712,4 +1701,48 @@ mod tests {
         assert!(tags.iter().any(|(t, c)| t == "tag2" && *c == 1));
         Ok(())
     }
+
+    fn synthetic_prompts(count: usize) -> Vec<Prompt> {
+        (0..count)
+            .map(|i| {
+                let mut prompt = Prompt::new(
+                    format!("prompt-{:05}", i),
+                    format!("Synthetic prompt {}", i),
+                    format!("Review {{{{CODE}}}} for issue {} in {{{{LANGUAGE}}}}", i),
+                );
+                prompt.category = Some(format!("category-{}", i % 12));
+                prompt.tags = vec![
+                    format!("tag-{}", i % 50),
+                    format!("tag-{}", (i / 7) % 50),
+                    "synthetic".to_string(),
+                ];
+                prompt.variables = ["CODE", "LANGUAGE"]
+                    .into_iter()
+                    .map(|name| PromptVariable {
+                        name: name.to_string(),
+                        var_type: VariableType::Multiline,
+                        required: true,
+                        description: None,
+                        default: None,
+                    })
+                    .collect();
+                prompt
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_list_loads_tags_and_variables_in_order() -> Result<()> {
+        let mut db = Database::in_memory()?;
+        let mut prompt = synthetic_prompts(1).remove(0);
+        prompt.tags = vec!["zeta".to_string(), "alpha".to_string()];
+        db.bulk_upsert_prompts(std::slice::from_ref(&prompt))?;
+
+        assert_eq!(db.list_prompts()?, vec![prompt.clone()]);
+        assert_eq!(db.list_prompts_filtered(None, Some("zeta"), false)?, vec![prompt.clone()]);
+        let (found, _) = db.search("synthetic", 5)?.remove(0);
+        assert_eq!(found, prompt);
+        Ok(())
+    }
 }
+
@@ -7,7 +7,7 @@
 
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use chrono::Utc;
@@ -15,7 +15,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::Database;
-use crate::types::Prompt;
+use crate::types::{Bundle, Prompt};
 
 /// JSONL metadata header (first line)
 #[derive(Debug, Serialize, Deserialize)]
@@ -32,8 +32,25 @@ pub struct MetaInfo {
     pub schema_version: i32,
 }
 
-/// Export prompts to JSONL file
+/// Local bundle line, written after the prompts
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleRecord {
+    #[serde(rename = "_bundle")]
+    bundle: Bundle,
+}
+
+/// Parsed JSONL library
+#[derive(Debug, Default)]
+pub struct JsonlContents {
+    pub meta: Option<JsonlMeta>,
+    pub prompts: Vec<Prompt>,
+    /// Local bundles (`{"_bundle": ...}` lines)
+    pub bundles: Vec<Bundle>,
+}
+
+/// Export prompts, then local bundles, to JSONL file
 ///
+/// Registry bundles are not exported; they come back with the registry.
 /// Uses atomic write pattern from rust-cli-with-sqlite skill:
 /// 1. Write to temp file
 /// 2. fsync
@@ -49,7 +66,7 @@ pub fn export_jsonl(db: &Database, path: &Path) -> Result<usize> {
     let count = prompts.len();
 
     // Create temp file in same directory for atomic rename
-    let temp_path = path.with_extension("jsonl.tmp");
+    let temp_path = crate::lock::temp_path(path);
 
     {
         let file = File::create(&temp_path)
@@ -74,6 +91,11 @@ pub fn export_jsonl(db: &Database, path: &Path) -> Result<usize> {
             writeln!(writer)?;
         }
 
+        for bundle in db.list_bundles()?.into_iter().filter(|b| b.is_local) {
+            serde_json::to_writer(&mut writer, &BundleRecord { bundle })?;
+            writeln!(writer)?;
+        }
+
         // Flush and fsync
         writer.flush()?;
         writer.into_inner()?.sync_all()?;
@@ -92,22 +114,97 @@ pub fn export_jsonl(db: &Database, path: &Path) -> Result<usize> {
     fs::rename(&temp_path, path)
         .with_context(|| format!("Failed to rename {:?} to {:?}", temp_path, path))?;
 
-    // Update version marker in DB
-    update_data_version(db)?;
-
     Ok(count)
 }
 
-/// Import prompts from JSONL file
+/// How `import_jsonl` combines the file with the existing database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Upsert file prompts; prompts missing from the file are kept
+    Merge,
+    /// Delete every stored prompt and local bundle, then insert the file's
+    Replace,
+}
+
+impl std::str::FromStr for ImportMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "merge" => Ok(Self::Merge),
+            "replace" => Ok(Self::Replace),
+            other => anyhow::bail!("Invalid import mode '{}' (expected merge or replace)", other),
+        }
+    }
+}
+
+/// Default JSONL backup location (SYNC_STRATEGY.md)
+pub fn default_jsonl_path() -> Option<PathBuf> {
+    crate::config::config_dir().map(|dir| dir.join("library").join("library.jsonl"))
+}
+
+/// Import prompts, then local bundles, from JSONL file
 ///
-/// Replaces all prompts in database with contents of JSONL file.
-/// Uses transaction for atomicity.
-pub fn import_jsonl(db: &mut Database, path: &Path) -> Result<usize> {
+/// The whole file is parsed, and the header's `schema_version` checked,
+/// before the database is touched. Uses a transaction for atomicity.
+pub fn import_jsonl(db: &mut Database, path: &Path, mode: ImportMode) -> Result<usize> {
+    import_jsonl_contents(db, load_jsonl(path)?, mode)
+}
+
+/// Parse a JSONL file and check its header's `schema_version`, without
+/// touching any database
+pub fn load_jsonl(path: &Path) -> Result<JsonlContents> {
+    let contents = read_jsonl(path)?;
+    if let Some(meta) = &contents.meta {
+        let version = meta.meta.schema_version;
+        if !(1..=crate::storage::SCHEMA_VERSION).contains(&version) {
+            anyhow::bail!(
+                "JSONL schema_version {} is not supported (expected 1..={})",
+                version,
+                crate::storage::SCHEMA_VERSION
+            );
+        }
+    }
+    Ok(contents)
+}
+
+/// Import contents returned by `load_jsonl`
+pub fn import_jsonl_contents(
+    db: &mut Database,
+    contents: JsonlContents,
+    mode: ImportMode,
+) -> Result<usize> {
+    let JsonlContents {
+        prompts, bundles, ..
+    } = contents;
+
+    match mode {
+        ImportMode::Merge => db.bulk_upsert_prompts(&prompts)?,
+        ImportMode::Replace => {
+            db.replace_prompts(&prompts)?;
+            for bundle in db.list_bundles()?.into_iter().filter(|b| b.is_local) {
+                db.delete_local_bundle(&bundle.id)?;
+            }
+        }
+    }
+    for bundle in &bundles {
+        db.save_local_bundle(bundle)?;
+    }
+
+    // Update version marker
+    update_data_version(db)?;
+
+    Ok(prompts.len())
+}
+
+/// Parse a JSONL file into its optional metadata header, prompts and bundles
+pub(super) fn read_jsonl(path: &Path) -> Result<JsonlContents> {
     let file =
         File::open(path).with_context(|| format!("Failed to open JSONL file: {:?}", path))?;
     let reader = BufReader::new(file);
 
-    let mut prompts = Vec::new();
+    let mut contents = JsonlContents::default();
     let mut line_num = 0;
     let mut saw_first_non_empty = false;
 
@@ -127,27 +224,31 @@ pub fn import_jsonl(db: &mut Database, path: &Path) -> Result<usize> {
             let parsed_first_line = serde_json::from_str::<Value>(trimmed)
                 .with_context(|| format!("Failed to parse JSON at line {}", line_num))?;
             if parsed_first_line.get("_meta").is_some() {
-                let _meta: JsonlMeta =
+                let header: JsonlMeta =
                     serde_json::from_value(parsed_first_line).with_context(|| {
                         format!("Failed to parse JSONL metadata at line {}", line_num)
                     })?;
+                contents.meta = Some(header);
                 continue;
             }
         }
 
+        let value = serde_json::from_str::<Value>(trimmed)
+            .with_context(|| format!("Failed to parse JSON at line {}", line_num))?;
+        if value.get("_bundle").is_some() {
+            let record: BundleRecord = serde_json::from_value(value)
+                .with_context(|| format!("Failed to parse bundle at line {}", line_num))?;
+            contents.bundles.push(record.bundle);
+            continue;
+        }
+
         // Parse prompt
-        let prompt: Prompt = serde_json::from_str(trimmed)
+        let prompt: Prompt = serde_json::from_value(value)
             .with_context(|| format!("Failed to parse prompt at line {}", line_num))?;
-        prompts.push(prompt);
+        contents.prompts.push(prompt);
     }
 
-    // Bulk import with transaction
-    db.bulk_upsert_prompts(&prompts)?;
-
-    // Update version marker
-    update_data_version(db)?;
-
-    Ok(prompts.len())
+    Ok(contents)
 }
 
 /// Get current data version from DB
@@ -186,7 +287,7 @@ mod tests {
 
         // Create new DB and import
         let mut db2 = Database::in_memory()?;
-        let imported = import_jsonl(&mut db2, &jsonl_path)?;
+        let imported = import_jsonl(&mut db2, &jsonl_path, ImportMode::Merge)?;
         assert_eq!(imported, 2);
 
         // Verify
@@ -233,7 +334,7 @@ mod tests {
         fs::write(&jsonl_path, format!("{}\n{}\n", first_line, second_line))?;
 
         let mut db = Database::in_memory()?;
-        let imported = import_jsonl(&mut db, &jsonl_path)?;
+        let imported = import_jsonl(&mut db, &jsonl_path, ImportMode::Merge)?;
         assert_eq!(imported, 2);
 
         let loaded = db.list_prompts()?;
@@ -256,8 +357,110 @@ mod tests {
         assert_eq!(exported, 2);
 
         let mut imported_db = Database::in_memory()?;
-        let imported = import_jsonl(&mut imported_db, &jsonl_path)?;
+        let imported = import_jsonl(&mut imported_db, &jsonl_path, ImportMode::Merge)?;
         assert_eq!(imported, 2);
         Ok(())
     }
+
+    #[test]
+    fn test_export_keeps_variables() -> Result<()> {
+        let dir = tempdir()?;
+        let jsonl_path = dir.path().join("prompts.jsonl");
+
+        let mut prompt = Prompt::new("vars", "Vars", "Hello {{NAME}}");
+        prompt.variables = vec![crate::types::PromptVariable {
+            name: "NAME".to_string(),
+            var_type: crate::types::VariableType::Text,
+            required: true,
+            description: None,
+            default: Some("world".to_string()),
+        }];
+        let mut db = Database::in_memory()?;
+        db.bulk_upsert_prompts(std::slice::from_ref(&prompt))?;
+        export_jsonl(&db, &jsonl_path)?;
+
+        let mut db2 = Database::in_memory()?;
+        import_jsonl(&mut db2, &jsonl_path, ImportMode::Merge)?;
+        assert_eq!(db2.get_prompt("vars")?, Some(prompt));
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_replace_drops_missing_prompts() -> Result<()> {
+        let dir = tempdir()?;
+        let jsonl_path = dir.path().join("prompts.jsonl");
+
+        let mut source = Database::in_memory()?;
+        source.bulk_upsert_prompts(&[Prompt::new("kept", "Kept", "kept content")])?;
+        export_jsonl(&source, &jsonl_path)?;
+
+        let mut db = Database::in_memory()?;
+        db.bulk_upsert_prompts(&[Prompt::new("stale", "Stale", "stale content")])?;
+        import_jsonl(&mut db, &jsonl_path, ImportMode::Merge)?;
+        assert_eq!(db.prompt_count()?, 2);
+
+        import_jsonl(&mut db, &jsonl_path, ImportMode::Replace)?;
+        let ids: Vec<String> = db.list_prompts()?.into_iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec!["kept".to_string()]);
+        assert!(db.fts_consistency()?.is_consistent());
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_bundles_roundtrip_and_registry_bundles_stay_out() -> Result<()> {
+        let dir = tempdir()?;
+        let jsonl_path = dir.path().join("prompts.jsonl");
+
+        let mut source = Database::in_memory()?;
+        source.bulk_upsert_prompts(&[
+            Prompt::new("a", "A", "a content"),
+            Prompt::new("b", "B", "b content"),
+        ])?;
+        source.sync_bundles(&[Bundle::new("registry", "Registry")], true)?;
+        let mut mine = Bundle::new("mine", "Mine");
+        mine.prompts = vec!["b".to_string(), "a".to_string()];
+        source.save_local_bundle(&mine)?;
+        export_jsonl(&source, &jsonl_path)?;
+
+        let contents = read_jsonl(&jsonl_path)?;
+        assert_eq!(contents.prompts.len(), 2);
+        let ids: Vec<&str> = contents.bundles.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, ["mine"]);
+
+        let mut db = Database::in_memory()?;
+        db.save_local_bundle(&Bundle::new("stale", "Stale"))?;
+        import_jsonl(&mut db, &jsonl_path, ImportMode::Replace)?;
+        let bundles = db.list_bundles()?;
+        assert_eq!(bundles.len(), 1);
+        assert!(bundles[0].is_local);
+        assert_eq!(bundles[0].prompts, ["b", "a"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_rejects_newer_schema_before_writing() -> Result<()> {
+        let dir = tempdir()?;
+        let jsonl_path = dir.path().join("prompts.jsonl");
+        let header = serde_json::json!({"_meta": {
+            "version": "v", "count": 1, "exported_at": "now",
+            "schema_version": crate::storage::SCHEMA_VERSION + 1,
+        }});
+        let prompt = serde_json::to_string(&Prompt::new("new", "New", "content"))?;
+        fs::write(&jsonl_path, format!("{}\n{}\n", header, prompt))?;
+
+        let mut db = Database::in_memory()?;
+        db.bulk_upsert_prompts(&[Prompt::new("old", "Old", "content")])?;
+        let err = import_jsonl(&mut db, &jsonl_path, ImportMode::Replace).unwrap_err();
+        assert!(err.to_string().contains("schema_version"));
+        assert_eq!(db.prompt_count()?, 1);
+        assert!(db.get_prompt("old")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_mode_from_str() {
+        assert_eq!("merge".parse::<ImportMode>().ok(), Some(ImportMode::Merge));
+        assert_eq!("replace".parse::<ImportMode>().ok(), Some(ImportMode::Replace));
+        assert!("append".parse::<ImportMode>().is_err());
+    }
 }
@@ -9,6 +9,9 @@
 mod database;
 mod jsonl;
 mod schema;
+mod sync;
 
 pub use database::*;
+pub use jsonl::*;
 pub use schema::*;
+pub use sync::*;
@@ -1,10 +1,60 @@
 //! Database schema and migrations
+//!
+//! Migrations run in order, each in its own transaction. Applied versions are
+//! recorded in `registry_meta` (`schema_version` plus `migration_<n>`), so new
+//! steps must be appended, never edited in place.
+
+/// A single schema migration step
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+/// Ordered migration list; `SCHEMA_VERSION` is the last entry's version
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Create prompts, tags, variables, bundles, metadata and FTS tables",
+        sql: MIGRATION_1_BASE_TABLES,
+    },
+    Migration {
+        version: 2,
+        description: "Add category, featured and tag indexes",
+        sql: MIGRATION_2_INDEXES,
+    },
+    Migration {
+        version: 3,
+        description: "Add sync_meta version marker",
+        sql: MIGRATION_3_SYNC_META,
+    },
+    Migration {
+        version: 4,
+        description: "Index prompt variables by prompt",
+        sql: MIGRATION_4_VARIABLE_INDEX,
+    },
+    Migration {
+        version: 5,
+        description: "Order bundles by position and re-import registry bundles",
+        sql: MIGRATION_5_BUNDLE_POSITION,
+    },
+    Migration {
+        version: 6,
+        description: "Flag user-defined local bundles",
+        sql: MIGRATION_6_LOCAL_BUNDLES,
+    },
+    Migration {
+        version: 7,
+        description: "Add prompt revision history",
+        sql: MIGRATION_7_PROMPT_REVISIONS,
+    },
+];
 
 /// Current schema version
-pub const SCHEMA_VERSION: i32 = 2;
+pub const SCHEMA_VERSION: i32 = 7;
 
-/// SQL to create the database schema
-pub const CREATE_SCHEMA: &str = r#"
+/// Migration 1: base tables
+const MIGRATION_1_BASE_TABLES: &str = r#"
 -- Prompts table (with denormalized tags_text for FTS)
 CREATE TABLE IF NOT EXISTS prompts (
     id TEXT PRIMARY KEY,
@@ -79,15 +129,70 @@ CREATE VIRTUAL TABLE IF NOT EXISTS prompts_fts USING fts5(
     tags_text
 );
 
--- Indexes
+"#;
+
+/// Migration 2: indexes
+const MIGRATION_2_INDEXES: &str = r#"
 CREATE INDEX IF NOT EXISTS idx_prompts_category ON prompts(category);
 CREATE INDEX IF NOT EXISTS idx_prompts_featured ON prompts(featured) WHERE featured = 1;
 CREATE INDEX IF NOT EXISTS idx_prompt_tags_tag ON prompt_tags(tag);
 "#;
 
+/// Migration 3: DB-side sync marker (SYNC_STRATEGY.md); at most one row
+const MIGRATION_3_SYNC_META: &str = r#"
+CREATE TABLE IF NOT EXISTS sync_meta (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    schema_version INTEGER NOT NULL,
+    last_synced_at TEXT NOT NULL,
+    source_of_truth TEXT NOT NULL DEFAULT 'sqlite',
+    jsonl_sha256 TEXT NOT NULL,
+    record_count INTEGER NOT NULL
+);
+"#;
+
+/// Migration 4: per-prompt variable lookups (the list queries aggregate
+/// variables for every row)
+const MIGRATION_4_VARIABLE_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_prompt_variables_prompt ON prompt_variables(prompt_id, id);
+"#;
+
+/// Migration 5: registry order for bundles and their members; clearing
+/// `data_version` makes the next bootstrap import the cached registry's
+/// bundles
+const MIGRATION_5_BUNDLE_POSITION: &str = r#"
+ALTER TABLE bundles ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+CREATE INDEX IF NOT EXISTS idx_bundle_prompts_position ON bundle_prompts(bundle_id, position);
+DELETE FROM registry_meta WHERE key = 'data_version';
+"#;
+
+/// Migration 6: local bundles are created by `jfp bundle create` and never
+/// touched by registry syncs
+const MIGRATION_6_LOCAL_BUNDLES: &str = r#"
+ALTER TABLE bundles ADD COLUMN is_local INTEGER NOT NULL DEFAULT 0;
+"#;
+
+/// Migration 7: one row per distinct version of a prompt, appended whenever
+/// a write changes its content hash; no foreign key, so history outlives
+/// pruned prompts
+const MIGRATION_7_PROMPT_REVISIONS: &str = r#"
+CREATE TABLE IF NOT EXISTS prompt_revisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    prompt_id TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    version TEXT,
+    source TEXT NOT NULL,
+    snapshot TEXT NOT NULL,
+    recorded_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_prompt_revisions_prompt ON prompt_revisions(prompt_id, id);
+CREATE INDEX IF NOT EXISTS idx_prompt_revisions_hash ON prompt_revisions(prompt_id, content_hash);
+"#;
+
 /// SQL to drop all tables (for reset)
 #[allow(dead_code)]
 pub const DROP_SCHEMA: &str = r#"
+DROP TABLE IF EXISTS prompt_revisions;
+DROP TABLE IF EXISTS sync_meta;
 DROP TABLE IF EXISTS bundle_prompts;
 DROP TABLE IF EXISTS bundles;
 DROP TABLE IF EXISTS prompt_variables;
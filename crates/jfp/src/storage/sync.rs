@@ -0,0 +1,461 @@
+//! Sync markers and recovery between SQLite and the JSONL library
+//!
+//! From SYNC_STRATEGY.md / RECOVERY_RUNBOOK.md:
+//! - `sync_meta` in the DB and `library.meta.json` next to the JSONL
+//! - Stable content hash: SHA-256 over prompts (then local bundles) sorted by
+//!   id, so it does not depend on file layout or row order
+//! - Recovery picks the authoritative store, rebuilds the other and
+//!   verifies counts/hashes afterwards
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::ErrorCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::jsonl::{export_jsonl, import_jsonl, read_jsonl, ImportMode};
+use super::{Database, SyncMeta, SCHEMA_VERSION};
+use crate::types::{Bundle, Prompt};
+
+/// SQLite is always the primary store
+const SOURCE_OF_TRUTH: &str = "sqlite";
+
+/// JSONL-side sync marker (`library.meta.json`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryMeta {
+    pub schema_version: i32,
+    pub last_synced_at: String,
+    pub source_of_truth: String,
+    /// Content hash of the SQLite store at the last sync
+    pub sqlite_sha256: String,
+    pub record_count: usize,
+}
+
+/// Counts and hashes of both stores
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncState {
+    pub sqlite_count: usize,
+    pub sqlite_sha256: String,
+    pub jsonl_count: usize,
+    pub jsonl_sha256: String,
+}
+
+impl SyncState {
+    /// Whether both stores hold the same prompts
+    pub fn in_sync(&self) -> bool {
+        self.sqlite_count == self.jsonl_count && self.sqlite_sha256 == self.jsonl_sha256
+    }
+}
+
+/// What `recover` did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    /// Both stores already matched
+    InSync,
+    /// SQLite was rebuilt from the JSONL
+    RebuiltSqlite,
+    /// The JSONL was re-exported from SQLite
+    ReexportedJsonl,
+}
+
+/// Outcome of `jfp db recover`
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryReport {
+    pub action: RecoveryAction,
+    pub reason: String,
+    pub db_path: PathBuf,
+    pub jsonl_path: PathBuf,
+    /// Where a corrupt database file was moved before rebuilding
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corrupt_backup: Option<PathBuf>,
+    pub state: SyncState,
+    pub verified: bool,
+}
+
+/// Stable SHA-256 of a prompt set and its local bundles, independent of
+/// input order
+///
+/// Without bundles this is the plain prompt hash, so markers written before
+/// local bundles existed stay valid.
+pub fn content_hash(prompts: &[Prompt], bundles: &[Bundle]) -> Result<String> {
+    let mut sorted: Vec<&Prompt> = prompts.iter().collect();
+    sorted.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut sorted_bundles: Vec<&Bundle> = bundles.iter().filter(|b| b.is_local).collect();
+    sorted_bundles.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut hasher = Sha256::new();
+    for prompt in sorted {
+        hasher.update(serde_json::to_vec(prompt)?);
+        hasher.update(b"\n");
+    }
+    for bundle in sorted_bundles {
+        hasher.update(b"bundle ");
+        hasher.update(serde_json::to_vec(bundle)?);
+        hasher.update(b"\n");
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Content hash of the DB's prompts and local bundles
+fn db_hash(db: &Database, prompts: &[Prompt]) -> Result<String> {
+    content_hash(prompts, &db.list_bundles()?)
+}
+
+/// `library.jsonl` -> `library.meta.json`
+pub fn library_meta_path(jsonl_path: &Path) -> PathBuf {
+    jsonl_path.with_extension("meta.json")
+}
+
+/// Read the JSONL marker; `None` when it does not exist
+pub fn read_library_meta(jsonl_path: &Path) -> Result<Option<LibraryMeta>> {
+    let path = library_meta_path(jsonl_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    let meta = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {:?}", path))?;
+    Ok(Some(meta))
+}
+
+fn write_library_meta(jsonl_path: &Path, meta: &LibraryMeta) -> Result<()> {
+    let path = library_meta_path(jsonl_path);
+    let temp_path = crate::lock::temp_path(&path);
+    fs::write(&temp_path, serde_json::to_string_pretty(meta)?)
+        .with_context(|| format!("Failed to write {:?}", temp_path))?;
+    fs::rename(&temp_path, &path)
+        .with_context(|| format!("Failed to rename {:?} to {:?}", temp_path, path))?;
+    Ok(())
+}
+
+/// Compute counts and content hashes of the DB and the JSONL file
+pub fn sync_state(db: &Database, jsonl_path: &Path) -> Result<SyncState> {
+    let db_prompts = db.list_prompts()?;
+    let jsonl = read_jsonl(jsonl_path)?;
+    Ok(SyncState {
+        sqlite_count: db_prompts.len(),
+        sqlite_sha256: db_hash(db, &db_prompts)?,
+        jsonl_count: jsonl.prompts.len(),
+        jsonl_sha256: content_hash(&jsonl.prompts, &jsonl.bundles)?,
+    })
+}
+
+/// Record a completed sync in both `sync_meta` and `library.meta.json`
+pub fn mark_synced(db: &Database, jsonl_path: &Path) -> Result<SyncState> {
+    let state = sync_state(db, jsonl_path)?;
+    let now = Utc::now().to_rfc3339();
+
+    db.set_sync_meta(&SyncMeta {
+        schema_version: SCHEMA_VERSION,
+        last_synced_at: now.clone(),
+        source_of_truth: SOURCE_OF_TRUTH.to_string(),
+        jsonl_sha256: state.jsonl_sha256.clone(),
+        record_count: state.sqlite_count,
+    })?;
+    write_library_meta(
+        jsonl_path,
+        &LibraryMeta {
+            schema_version: SCHEMA_VERSION,
+            last_synced_at: now,
+            source_of_truth: SOURCE_OF_TRUTH.to_string(),
+            sqlite_sha256: state.sqlite_sha256.clone(),
+            record_count: state.jsonl_count,
+        },
+    )?;
+
+    Ok(state)
+}
+
+/// Pick the authoritative store, rebuild the other and verify the result
+///
+/// - Corrupt DB (integrity check fails) -> rebuild SQLite from the JSONL
+/// - Missing/unreadable JSONL -> re-export from SQLite
+/// - JSONL changed since the last sync while the DB did not -> rebuild SQLite
+/// - Otherwise SQLite wins and the stale JSONL is re-exported
+pub fn recover(db_path: &Path, jsonl_path: &Path) -> Result<RecoveryReport> {
+    let jsonl = read_jsonl(jsonl_path);
+
+    let Some(mut db) = open_healthy(db_path)? else {
+        // DB is corrupt: the JSONL is the only source left
+        if let Err(e) = &jsonl {
+            anyhow::bail!(
+                "Database is corrupt and there is no usable JSONL backup at {:?}: {:#}",
+                jsonl_path,
+                e
+            );
+        }
+        let backup = move_aside(db_path)?;
+        let mut db = Database::open_at(db_path)?;
+        import_jsonl(&mut db, jsonl_path, ImportMode::Replace)?;
+        return finish(
+            &db,
+            db_path,
+            jsonl_path,
+            RecoveryAction::RebuiltSqlite,
+            "database failed integrity check".to_string(),
+            Some(backup),
+        );
+    };
+
+    let jsonl = match jsonl {
+        Ok(jsonl) => jsonl,
+        Err(e) => {
+            let reason = if jsonl_path.exists() {
+                format!("JSONL is unreadable: {:#}", e)
+            } else {
+                "JSONL is missing".to_string()
+            };
+            export_jsonl(&db, jsonl_path)?;
+            return finish(
+                &db,
+                db_path,
+                jsonl_path,
+                RecoveryAction::ReexportedJsonl,
+                reason,
+                None,
+            );
+        }
+    };
+
+    let sqlite_sha256 = db_hash(&db, &db.list_prompts()?)?;
+    let jsonl_sha256 = content_hash(&jsonl.prompts, &jsonl.bundles)?;
+    if sqlite_sha256 == jsonl_sha256 {
+        return finish(
+            &db,
+            db_path,
+            jsonl_path,
+            RecoveryAction::InSync,
+            "stores already match".to_string(),
+            None,
+        );
+    }
+
+    // Each store's marker records the hash both stores had at the last sync
+    let db_changed = db
+        .sync_meta()?
+        .is_none_or(|meta| meta.jsonl_sha256 != sqlite_sha256);
+    let jsonl_changed = read_library_meta(jsonl_path)
+        .ok()
+        .flatten()
+        .is_none_or(|meta| meta.sqlite_sha256 != jsonl_sha256);
+
+    if jsonl_changed && !db_changed {
+        import_jsonl(&mut db, jsonl_path, ImportMode::Replace)?;
+        finish(
+            &db,
+            db_path,
+            jsonl_path,
+            RecoveryAction::RebuiltSqlite,
+            "JSONL changed since the last sync".to_string(),
+            None,
+        )
+    } else {
+        export_jsonl(&db, jsonl_path)?;
+        finish(
+            &db,
+            db_path,
+            jsonl_path,
+            RecoveryAction::ReexportedJsonl,
+            "JSONL is stale".to_string(),
+            None,
+        )
+    }
+}
+
+fn finish(
+    db: &Database,
+    db_path: &Path,
+    jsonl_path: &Path,
+    action: RecoveryAction,
+    reason: String,
+    corrupt_backup: Option<PathBuf>,
+) -> Result<RecoveryReport> {
+    let state = sync_state(db, jsonl_path)?;
+    let verified = state.in_sync();
+    if verified {
+        mark_synced(db, jsonl_path)?;
+    }
+    Ok(RecoveryReport {
+        action,
+        reason,
+        db_path: db_path.to_path_buf(),
+        jsonl_path: jsonl_path.to_path_buf(),
+        corrupt_backup,
+        state,
+        verified,
+    })
+}
+
+/// Open the DB, returning `None` when SQLite reports it as corrupt
+fn open_healthy(db_path: &Path) -> Result<Option<Database>> {
+    let db = match Database::open_at(db_path) {
+        Ok(db) => db,
+        Err(e) if is_corruption(&e) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    match db.integrity_check() {
+        Ok(true) => Ok(Some(db)),
+        Ok(false) => Ok(None),
+        Err(e) if is_corruption(&e) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether SQLite reported `err` as a corrupt or non-database file
+pub(crate) fn is_corruption(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::SqliteFailure(failure, _))
+                if matches!(failure.code, ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+        )
+    })
+}
+
+/// Move a corrupt DB (and its WAL/SHM sidecars) out of the way
+fn move_aside(db_path: &Path) -> Result<PathBuf> {
+    let stamp = Utc::now().format("%Y%m%d%H%M%S");
+    let backup = PathBuf::from(format!("{}.corrupt-{}", db_path.display(), stamp));
+    fs::rename(db_path, &backup)
+        .with_context(|| format!("Failed to move {:?} to {:?}", db_path, backup))?;
+    for suffix in ["-wal", "-shm"] {
+        let sidecar = PathBuf::from(format!("{}{}", db_path.display(), suffix));
+        if sidecar.exists() {
+            fs::remove_file(&sidecar)
+                .with_context(|| format!("Failed to remove {:?}", sidecar))?;
+        }
+    }
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn seed(db: &mut Database) -> Result<()> {
+        db.bulk_upsert_prompts(&[
+            Prompt::new("alpha", "Alpha", "alpha content"),
+            Prompt::new("beta", "Beta", "beta content"),
+        ])
+    }
+
+    #[test]
+    fn test_content_hash_ignores_order() -> Result<()> {
+        let a = Prompt::new("a", "A", "one");
+        let b = Prompt::new("b", "B", "two");
+        assert_eq!(
+            content_hash(&[a.clone(), b.clone()], &[])?,
+            content_hash(&[b, a.clone()], &[])?
+        );
+        assert_ne!(content_hash(std::slice::from_ref(&a), &[])?, content_hash(&[], &[])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mark_synced_writes_both_markers() -> Result<()> {
+        let dir = tempdir()?;
+        let jsonl_path = dir.path().join("library.jsonl");
+        let mut db = Database::in_memory()?;
+        seed(&mut db)?;
+        export_jsonl(&db, &jsonl_path)?;
+
+        let state = mark_synced(&db, &jsonl_path)?;
+        assert!(state.in_sync());
+
+        let db_meta = db.sync_meta()?.expect("sync_meta row");
+        assert_eq!(db_meta.jsonl_sha256, state.jsonl_sha256);
+        assert_eq!(db_meta.record_count, 2);
+        let library = read_library_meta(&jsonl_path)?.expect("library.meta.json");
+        assert_eq!(library.sqlite_sha256, state.sqlite_sha256);
+        assert_eq!(library.source_of_truth, "sqlite");
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_reexports_stale_jsonl() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("jfp.db");
+        let jsonl_path = dir.path().join("library.jsonl");
+        {
+            let mut db = Database::open_at(&db_path)?;
+            seed(&mut db)?;
+            export_jsonl(&db, &jsonl_path)?;
+            mark_synced(&db, &jsonl_path)?;
+            db.upsert_prompt(&Prompt::new("gamma", "Gamma", "new in db"))?;
+        }
+
+        let report = recover(&db_path, &jsonl_path)?;
+        assert_eq!(report.action, RecoveryAction::ReexportedJsonl);
+        assert!(report.verified);
+        assert_eq!(report.state.jsonl_count, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_rebuilds_db_when_jsonl_changed() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("jfp.db");
+        let jsonl_path = dir.path().join("library.jsonl");
+        {
+            let mut db = Database::open_at(&db_path)?;
+            seed(&mut db)?;
+            export_jsonl(&db, &jsonl_path)?;
+            mark_synced(&db, &jsonl_path)?;
+        }
+        let edited = Prompt::new("alpha", "Alpha", "edited in jsonl");
+        fs::write(&jsonl_path, format!("{}\n", serde_json::to_string(&edited)?))?;
+
+        let report = recover(&db_path, &jsonl_path)?;
+        assert_eq!(report.action, RecoveryAction::RebuiltSqlite);
+        assert!(report.verified);
+        let db = Database::open_at(&db_path)?;
+        assert_eq!(db.prompt_count()?, 1);
+        assert_eq!(db.get_prompt("alpha")?.map(|p| p.content), Some(edited.content));
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_rebuilds_corrupt_db_from_jsonl() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("jfp.db");
+        let jsonl_path = dir.path().join("library.jsonl");
+        {
+            let mut db = Database::open_at(&db_path)?;
+            seed(&mut db)?;
+            db.checkpoint()?;
+            export_jsonl(&db, &jsonl_path)?;
+        }
+        fs::write(&db_path, b"this is not a sqlite database, just garbage bytes")?;
+
+        let report = recover(&db_path, &jsonl_path)?;
+        assert_eq!(report.action, RecoveryAction::RebuiltSqlite);
+        assert!(report.verified);
+        assert!(report.corrupt_backup.as_ref().is_some_and(|p| p.exists()));
+        assert_eq!(Database::open_at(&db_path)?.prompt_count()?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_in_sync_is_noop() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("jfp.db");
+        let jsonl_path = dir.path().join("library.jsonl");
+        {
+            let mut db = Database::open_at(&db_path)?;
+            seed(&mut db)?;
+            export_jsonl(&db, &jsonl_path)?;
+        }
+
+        let report = recover(&db_path, &jsonl_path)?;
+        assert_eq!(report.action, RecoveryAction::InSync);
+        assert!(report.verified);
+        assert!(read_library_meta(&jsonl_path)?.is_some());
+        Ok(())
+    }
+}
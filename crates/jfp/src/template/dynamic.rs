@@ -0,0 +1,148 @@
+//! Dynamic defaults
+//!
+//! Values jfp can work out from where it runs, used when nothing more
+//! explicit sets them:
+//! - CWD, PROJECT_NAME (the cwd basename)
+//! - GIT_BRANCH, GIT_ROOT, GIT_STAGED_DIFF, RECENT_COMMITS (from the local
+//!   `git` binary; unset outside a repository)
+//!
+//! Each value is computed on first use, so `git` only runs for prompts that
+//! reference it.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use super::file::MAX_FILE_VAR_SIZE;
+
+/// Commits listed in RECENT_COMMITS
+const RECENT_COMMIT_COUNT: &str = "10";
+
+pub struct DynamicDefaults {
+    cwd: PathBuf,
+    cache: HashMap<String, Option<String>>,
+}
+
+impl DynamicDefaults {
+    pub fn new(cwd: impl Into<PathBuf>) -> Self {
+        Self {
+            cwd: cwd.into(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// From the process's current directory
+    pub fn current() -> Option<Self> {
+        std::env::current_dir().ok().map(Self::new)
+    }
+
+    /// The value for `name`, computed once
+    pub fn get(&mut self, name: &str) -> Option<String> {
+        if let Some(value) = self.cache.get(name) {
+            return value.clone();
+        }
+        let value = self.compute(name);
+        self.cache.insert(name.to_string(), value.clone());
+        value
+    }
+
+    fn compute(&self, name: &str) -> Option<String> {
+        match name {
+            "CWD" => Some(self.cwd.display().to_string()),
+            "PROJECT_NAME" => self.cwd.file_name().map(|name| name.to_string_lossy().into_owned()),
+            "GIT_BRANCH" => self.git(&["rev-parse", "--abbrev-ref", "HEAD"]),
+            "GIT_ROOT" => self.git(&["rev-parse", "--show-toplevel"]),
+            "GIT_STAGED_DIFF" => self.git(&["diff", "--cached"]).map(cap),
+            "RECENT_COMMITS" => self.git(&["log", "--oneline", "-n", RECENT_COMMIT_COUNT]),
+            _ => None,
+        }
+    }
+
+    /// Trimmed stdout of a successful `git` run in the cwd
+    fn git(&self, args: &[&str]) -> Option<String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.cwd)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+    }
+}
+
+/// Cap a diff the way `file` variables are capped
+fn cap(text: String) -> String {
+    let max = MAX_FILE_VAR_SIZE as usize;
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n\n[Diff truncated to {} bytes from {} bytes]", &text[..end], max, text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn git(dir: &std::path::Path, args: &[&str]) -> bool {
+        Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    #[test]
+    fn test_dynamic_defaults_from_git() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let project = dir.path().join("my-project");
+        fs::create_dir(&project)?;
+
+        let mut outside = DynamicDefaults::new(&project);
+        assert_eq!(outside.get("PROJECT_NAME").as_deref(), Some("my-project"));
+        assert_eq!(outside.get("CWD"), Some(project.display().to_string()));
+        assert_eq!(outside.get("UNKNOWN"), None);
+        assert_eq!(outside.get("GIT_BRANCH"), None);
+
+        // Skip the git part where git is unavailable
+        if !git(&project, &["init", "-q", "-b", "main"]) {
+            return Ok(());
+        }
+        assert_eq!(outside.get("GIT_BRANCH"), None, "cached from before the init");
+
+        fs::write(project.join("a.txt"), "one\n")?;
+        let identity = ["-c", "user.name=t", "-c", "user.email=t@example.com"];
+        assert!(git(&project, &["add", "a.txt"]));
+        assert!(git(&project, &[&identity[..], &["commit", "-q", "-m", "First commit"]].concat()));
+        fs::write(project.join("a.txt"), "two\n")?;
+        assert!(git(&project, &["add", "a.txt"]));
+
+        let mut inside = DynamicDefaults::new(&project);
+        assert_eq!(inside.get("GIT_BRANCH").as_deref(), Some("main"));
+        let root = inside.get("GIT_ROOT").unwrap();
+        assert!(root.ends_with("my-project"), "{}", root);
+        assert!(inside.get("RECENT_COMMITS").unwrap().ends_with("First commit"));
+        assert!(inside.get("GIT_STAGED_DIFF").unwrap().contains("+two"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cap_keeps_char_boundaries() {
+        let text = format!("{}é", "a".repeat(MAX_FILE_VAR_SIZE as usize - 1));
+        let capped = cap(text.clone());
+        assert!(capped.starts_with(&"a".repeat(MAX_FILE_VAR_SIZE as usize - 1)));
+        assert!(capped.ends_with(&format!("[Diff truncated to 102400 bytes from {} bytes]", text.len())));
+        assert_eq!(cap("short".to_string()), "short");
+    }
+}
@@ -0,0 +1,129 @@
+//! `file` and `path` variable values
+//!
+//! - `file`: the value names a file whose text is substituted, capped at
+//!   `MAX_FILE_VAR_SIZE` bytes with a truncation notice; binary files are
+//!   rejected
+//! - `path`: the value must name an existing path and is passed through
+//! - A leading `~/` is the home directory, as typed at an interactive prompt
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Largest file content substituted for a `file` variable (100KB)
+pub const MAX_FILE_VAR_SIZE: u64 = 102400;
+
+#[derive(Debug, Error)]
+pub enum FileError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("binary files cannot be used as text")]
+    Binary,
+}
+
+/// Text read for a `file` variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileText {
+    pub content: String,
+    /// Size of the whole file in bytes
+    pub size: u64,
+    pub truncated: bool,
+}
+
+/// `path` with a leading `~/` expanded
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Read the text of the file at `path`, truncated to `MAX_FILE_VAR_SIZE`
+/// bytes with `[File truncated to 102400 bytes from <SIZE> bytes]` appended
+pub fn read_file_value(path: &str) -> Result<FileText, FileError> {
+    let mut file = File::open(expand_home(path))?;
+    let metadata = file.metadata()?;
+    if metadata.is_dir() {
+        return Err(io::Error::other("is a directory").into());
+    }
+    let size = metadata.len();
+
+    let mut bytes = Vec::new();
+    file.by_ref().take(MAX_FILE_VAR_SIZE).read_to_end(&mut bytes)?;
+    let truncated = size > MAX_FILE_VAR_SIZE;
+
+    // A cut can split the last character; anything else invalid is binary
+    let text = match std::str::from_utf8(&bytes) {
+        Ok(text) => text,
+        Err(e) if truncated && e.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..e.valid_up_to()]).map_err(|_| FileError::Binary)?
+        }
+        Err(_) => return Err(FileError::Binary),
+    };
+    if text.contains('\0') {
+        return Err(FileError::Binary);
+    }
+
+    let mut content = text.to_string();
+    if truncated {
+        content.push_str(&format!(
+            "\n\n[File truncated to {} bytes from {} bytes]",
+            MAX_FILE_VAR_SIZE, size
+        ));
+    }
+    Ok(FileText {
+        content,
+        size,
+        truncated,
+    })
+}
+
+/// Whether `path` exists, for `path` variables
+pub fn path_exists(path: &str) -> bool {
+    expand_home(path).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_file_values_are_capped_and_binaries_rejected() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let small = dir.path().join("small.txt");
+        fs::write(&small, "hello\n")?;
+        let text = read_file_value(small.to_str().unwrap())?;
+        assert_eq!(text.content, "hello\n");
+        assert!(!text.truncated);
+
+        // 'é' straddles the cap, so the cut drops its first byte too
+        let big = dir.path().join("big.txt");
+        let body = format!("{}é{}", "a".repeat(MAX_FILE_VAR_SIZE as usize - 1), "b".repeat(99));
+        fs::write(&big, &body)?;
+        let text = read_file_value(big.to_str().unwrap())?;
+        assert!(text.truncated);
+        assert_eq!(text.size, body.len() as u64);
+        assert_eq!(
+            text.content,
+            format!(
+                "{}\n\n[File truncated to 102400 bytes from {} bytes]",
+                "a".repeat(MAX_FILE_VAR_SIZE as usize - 1),
+                body.len()
+            )
+        );
+
+        let binary = dir.path().join("image.bin");
+        fs::write(&binary, [0x89, b'P', b'N', b'G', 0, 1, 2])?;
+        assert!(matches!(read_file_value(binary.to_str().unwrap()), Err(FileError::Binary)));
+
+        let missing = dir.path().join("missing.txt");
+        assert!(matches!(read_file_value(missing.to_str().unwrap()), Err(FileError::Io(_))));
+        assert!(!path_exists(missing.to_str().unwrap()));
+        assert!(path_exists(dir.path().to_str().unwrap()));
+        Ok(())
+    }
+}
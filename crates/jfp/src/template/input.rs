@@ -0,0 +1,178 @@
+//! Asking for variable values on the terminal
+//!
+//! - Shows the variable's description and current default; an empty answer
+//!   keeps the default
+//! - `file` and `path` variables are read in raw mode with Tab completion of
+//!   paths (a unique match is completed, several are listed)
+//! - Ctrl+C cancels the whole fill
+
+use std::fs;
+use std::io::{self, Write};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+
+use super::file::expand_home;
+use crate::types::{PromptVariable, VariableType};
+
+/// Ask for `name` on the terminal
+///
+/// `Err` with `ErrorKind::Interrupted` means the user cancelled.
+pub fn ask_stdin(
+    name: &str,
+    variable: Option<&PromptVariable>,
+    default: Option<&str>,
+) -> io::Result<Option<String>> {
+    let description = variable
+        .and_then(|v| v.description.as_ref())
+        .map(|d| format!(" ({})", d))
+        .unwrap_or_default();
+    let default_hint = default.map(|d| format!(" [{}]", d)).unwrap_or_default();
+    let prompt = format!("{}{}{}: ", name, description, default_hint);
+
+    let input = match variable.map(|v| v.var_type) {
+        Some(VariableType::File | VariableType::Path) => read_path_line(&prompt)?,
+        _ => {
+            print!("{}", prompt);
+            io::stdout().flush()?;
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+            input
+        }
+    };
+
+    let value = input.trim();
+    if value.is_empty() {
+        Ok(default.map(str::to_string))
+    } else {
+        Ok(Some(value.to_string()))
+    }
+}
+
+/// Leaves raw mode when dropped
+struct RawMode;
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Read a line with Tab completing paths
+fn read_path_line(prompt: &str) -> io::Result<String> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    let _raw = RawMode;
+
+    let mut line = String::new();
+    write!(stdout, "{}", prompt)?;
+    stdout.flush()?;
+    loop {
+        let Event::Key(KeyEvent { code, modifiers, kind, .. }) = event::read()? else {
+            continue;
+        };
+        if kind == KeyEventKind::Release {
+            continue;
+        }
+        let control = modifiers.contains(KeyModifiers::CONTROL);
+        match code {
+            KeyCode::Enter => {
+                write!(stdout, "\r\n")?;
+                return Ok(line);
+            }
+            KeyCode::Char('c') if control => {
+                write!(stdout, "\r\n")?;
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            KeyCode::Char('d') if control && line.is_empty() => {
+                write!(stdout, "\r\n")?;
+                return Ok(line);
+            }
+            KeyCode::Char(c) if !control => {
+                line.push(c);
+                write!(stdout, "{}", c)?;
+            }
+            KeyCode::Backspace if line.pop().is_some() => write!(stdout, "\x08 \x08")?,
+            KeyCode::Tab => {
+                let candidates = complete_path(&line);
+                let prefix = common_prefix(&candidates);
+                if prefix.len() > line.len() {
+                    write!(stdout, "{}", &prefix[line.len()..])?;
+                    line = prefix;
+                } else if candidates.len() > 1 {
+                    write!(stdout, "\r\n{}\r\n{}{}", candidates.join("  "), prompt, line)?;
+                }
+            }
+            _ => {}
+        }
+        stdout.flush()?;
+    }
+}
+
+/// Paths starting with `input`, directories ending in `/`; hidden entries
+/// only when `input` names them
+pub fn complete_path(input: &str) -> Vec<String> {
+    let (dir, prefix) = match input.rfind('/') {
+        Some(slash) => input.split_at(slash + 1),
+        None => ("", input),
+    };
+    let listing = if dir.is_empty() { expand_home(".") } else { expand_home(dir) };
+    let Ok(entries) = fs::read_dir(listing) else {
+        return Vec::new();
+    };
+
+    let mut candidates: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{}{}{}", dir, name, slash))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// Longest prefix shared by all `candidates`
+fn common_prefix(candidates: &[String]) -> String {
+    let Some((first, rest)) = candidates.split_first() else {
+        return String::new();
+    };
+    let mut prefix = first.as_str();
+    for candidate in rest {
+        let shared = prefix
+            .char_indices()
+            .zip(candidate.chars())
+            .find(|((_, a), b)| a != b)
+            .map(|((i, _), _)| i)
+            .unwrap_or(prefix.len().min(candidate.len()));
+        prefix = &prefix[..shared];
+    }
+    prefix.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_complete_path() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join("src"))?;
+        fs::write(dir.path().join("setup.sh"), "")?;
+        fs::write(dir.path().join(".secret"), "")?;
+        let base = format!("{}/", dir.path().display());
+
+        let candidates = complete_path(&format!("{}s", base));
+        assert_eq!(candidates, [format!("{}setup.sh", base), format!("{}src/", base)]);
+        assert_eq!(common_prefix(&candidates), format!("{}s", base));
+        assert_eq!(complete_path(&format!("{}sr", base)), [format!("{}src/", base)]);
+        assert_eq!(complete_path(&format!("{}.s", base)), [format!("{}.secret", base)]);
+        assert_eq!(complete_path(&base).len(), 2);
+        Ok(())
+    }
+}
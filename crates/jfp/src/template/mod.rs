@@ -0,0 +1,653 @@
+//! Prompt templates
+//!
+//! - `{{NAME}}` placeholders are parsed once into a `Template`
+//! - Values are resolved per name from ordered sources, first match wins:
+//!   CLI (`--var NAME=VALUE` or `--NAME=VALUE`), context file,
+//!   `JFP_VAR_<NAME>` env vars, config `[variables]`, the prompt's own
+//!   defaults, dynamic defaults (see `dynamic`), then (with --fill on a TTY)
+//!   the user, who is asked for anything unset or only defaulted
+//! - CLI names the prompt neither declares nor uses are an error
+//! - Placeholders left without a value stay in the output as `{{NAME}}` and
+//!   are reported, as are placeholders the prompt does not declare
+//! - Required variables without a value are a `missing_variables` error
+//! - `file` variables substitute the named file's text and `path` variables
+//!   must name an existing path (see `file`)
+
+mod dynamic;
+mod file;
+mod input;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::process::ExitCode;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::types::{Prompt, PromptVariable, VariableType};
+
+pub use dynamic::*;
+pub use file::*;
+pub use input::*;
+
+/// Prefix of environment variables supplying template values
+pub const ENV_PREFIX: &str = "JFP_VAR_";
+
+/// `[A-Za-z][A-Za-z0-9_]*`, the names a placeholder may use
+pub fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Text between each `{{` and the following `}}`, in order, valid name or not
+pub fn scan(content: &str) -> Vec<&str> {
+    Template::parse(content)
+        .segments
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Text(_) => None,
+            Segment::Placeholder(raw) => Some(raw),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment<'a> {
+    Text(&'a str),
+    /// Text between `{{` and `}}`
+    Placeholder(&'a str),
+}
+
+/// Prompt content split into text and placeholders
+#[derive(Debug, Clone)]
+pub struct Template<'a> {
+    segments: Vec<Segment<'a>>,
+}
+
+impl<'a> Template<'a> {
+    pub fn parse(content: &'a str) -> Self {
+        let mut segments = Vec::new();
+        let mut rest = content;
+        while let Some(start) = rest.find("{{") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else {
+                break;
+            };
+            if start > 0 {
+                segments.push(Segment::Text(&rest[..start]));
+            }
+            segments.push(Segment::Placeholder(&after[..end]));
+            rest = &after[end + 2..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest));
+        }
+        Self { segments }
+    }
+
+    /// Valid placeholder names, each once, in order of first use
+    pub fn placeholders(&self) -> Vec<&'a str> {
+        let mut names: Vec<&str> = Vec::new();
+        for segment in &self.segments {
+            if let Segment::Placeholder(name) = *segment
+                && is_variable_name(name)
+                && !names.contains(&name)
+            {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Substitute `values`; placeholders without one are kept as written
+    pub fn render(&self, values: &HashMap<String, String>) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match *segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Placeholder(name) => match values.get(name) {
+                    Some(value) if is_variable_name(name) => out.push_str(value),
+                    _ => {
+                        out.push_str("{{");
+                        out.push_str(name);
+                        out.push_str("}}");
+                    }
+                },
+            }
+        }
+        out
+    }
+}
+
+/// Where a resolved value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Cli,
+    Context,
+    Env,
+    Config,
+    Default,
+    Dynamic,
+    Interactive,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedValue {
+    pub name: String,
+    /// The value as given; for `file` variables, the file name
+    pub value: String,
+    pub source: Source,
+    /// Size in bytes of the file read for a `file` variable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// Rendered content plus what went into it
+#[derive(Debug, Clone)]
+pub struct Rendered {
+    pub content: String,
+    pub values: Vec<ResolvedValue>,
+    /// Placeholders left in the output for lack of a value
+    pub unresolved: Vec<String>,
+    /// Placeholders the prompt does not declare as variables
+    pub undeclared: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("Missing required variables: {}", .0.join(", "))]
+    MissingVariables(Vec<String>),
+    #[error("Unknown variables: {} (not used by this prompt)", .0.join(", "))]
+    UnknownVariables(Vec<String>),
+    #[error("Invalid variable '{0}' (expected NAME=VALUE, NAME matching [a-zA-Z][a-zA-Z0-9_]*)")]
+    InvalidAssignment(String),
+    #[error("{name}: cannot use file '{path}': {source}")]
+    File {
+        name: String,
+        path: String,
+        source: FileError,
+    },
+    #[error("{name}: path '{path}' does not exist")]
+    PathNotFound { name: String, path: String },
+    #[error("Cancelled")]
+    Cancelled,
+}
+
+impl TemplateError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MissingVariables(_) => "missing_variables",
+            Self::UnknownVariables(_) => "unknown_variables",
+            Self::InvalidAssignment(_) => "invalid_variable",
+            Self::File { source: FileError::Binary, .. } => "binary_file",
+            Self::File { .. } => "file_error",
+            Self::PathNotFound { .. } => "path_not_found",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// 130 for a cancelled fill (as for SIGINT), else 1
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::Cancelled => ExitCode::from(130),
+            _ => ExitCode::FAILURE,
+        }
+    }
+
+    /// Error JSON, with the `missing` or `unknown` names
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "error": self.code(),
+            "message": self.to_string(),
+        });
+        match self {
+            Self::MissingVariables(names) => json["missing"] = serde_json::json!(names),
+            Self::UnknownVariables(names) => json["unknown"] = serde_json::json!(names),
+            _ => {}
+        }
+        json
+    }
+}
+
+/// Values from `--var NAME=VALUE` (trailing `--NAME=VALUE` arguments are
+/// rewritten to this form); a later value for the same name wins
+pub fn cli_values(vars: &[String]) -> Result<BTreeMap<String, String>, TemplateError> {
+    let mut values = BTreeMap::new();
+    for var in vars {
+        match var.split_once('=') {
+            Some((name, value)) if is_variable_name(name) => {
+                values.insert(name.to_string(), value.to_string());
+            }
+            _ => return Err(TemplateError::InvalidAssignment(var.clone())),
+        }
+    }
+    Ok(values)
+}
+
+/// Asks the user for a value: name, declaration (if any), current default;
+/// `ErrorKind::Interrupted` cancels
+pub type Ask<'a> =
+    Box<dyn FnMut(&str, Option<&PromptVariable>, Option<&str>) -> io::Result<Option<String>> + 'a>;
+
+/// Resolves a prompt's variables from ordered sources
+pub struct Resolver<'a> {
+    prompt: &'a Prompt,
+    cli: BTreeMap<String, String>,
+    context: HashMap<String, String>,
+    env: HashMap<String, String>,
+    config: Option<&'a BTreeMap<String, String>>,
+    dynamic: Option<DynamicDefaults>,
+    ask: Option<Ask<'a>>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(prompt: &'a Prompt) -> Self {
+        Self {
+            prompt,
+            cli: BTreeMap::new(),
+            context: HashMap::new(),
+            env: HashMap::new(),
+            config: None,
+            dynamic: None,
+            ask: None,
+        }
+    }
+
+    /// Values given on the command line; each must be a declared variable or
+    /// a placeholder in the content
+    pub fn with_cli(mut self, values: BTreeMap<String, String>) -> Self {
+        self.cli = values;
+        self
+    }
+
+    pub fn with_context(mut self, values: HashMap<String, String>) -> Self {
+        self.context = values;
+        self
+    }
+
+    /// Take `JFP_VAR_<NAME>` entries from `vars` (e.g. `std::env::vars()`)
+    pub fn with_env(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env = vars
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.strip_prefix(ENV_PREFIX)?.to_string(), value)))
+            .collect();
+        self
+    }
+
+    /// Config `[variables]`, which take precedence over prompt defaults
+    pub fn with_config(mut self, values: &'a BTreeMap<String, String>) -> Self {
+        self.config = Some(values);
+        self
+    }
+
+    pub fn with_dynamic(mut self, dynamic: DynamicDefaults) -> Self {
+        self.dynamic = Some(dynamic);
+        self
+    }
+
+    pub fn with_ask(mut self, ask: Ask<'a>) -> Self {
+        self.ask = Some(ask);
+        self
+    }
+
+    pub fn render(mut self) -> Result<Rendered, TemplateError> {
+        let prompt = self.prompt;
+        let template = Template::parse(&prompt.content);
+        let placeholders = template.placeholders();
+        let declared = |name: &str| prompt.variables.iter().find(|v| v.name == name);
+
+        // Declared variables, then placeholders nobody declared
+        let mut names: Vec<&str> = prompt.variables.iter().map(|v| v.name.as_str()).collect();
+        let undeclared: Vec<String> = placeholders
+            .iter()
+            .filter(|name| declared(name).is_none())
+            .map(|name| name.to_string())
+            .collect();
+        names.extend(undeclared.iter().map(String::as_str));
+
+        let unknown: Vec<String> = self
+            .cli
+            .keys()
+            .filter(|name| !names.contains(&name.as_str()))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            return Err(TemplateError::UnknownVariables(unknown));
+        }
+
+        let mut values = Vec::new();
+        for name in names {
+            let variable = declared(name);
+            let mut resolved = self.lookup(name, variable);
+            let explicit = resolved
+                .as_ref()
+                .is_some_and(|(_, source)| {
+                    !matches!(source, Source::Config | Source::Dynamic | Source::Default)
+                });
+            if !explicit && let Some(ask) = self.ask.as_mut() {
+                let default = resolved.as_ref().map(|(value, _)| value.as_str());
+                match ask(name, variable, default) {
+                    Ok(Some(value)) if !value.is_empty() => {
+                        resolved = Some((value, Source::Interactive))
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                        return Err(TemplateError::Cancelled);
+                    }
+                    _ => {}
+                }
+            }
+            if let Some((value, source)) = resolved {
+                values.push(ResolvedValue {
+                    name: name.to_string(),
+                    value,
+                    source,
+                    file_size: None,
+                    truncated: false,
+                });
+            }
+        }
+
+        let missing: Vec<String> = prompt
+            .variables
+            .iter()
+            .filter(|v| v.required && !values.iter().any(|r| r.name == v.name))
+            .map(|v| v.name.clone())
+            .collect();
+        if !missing.is_empty() {
+            return Err(TemplateError::MissingVariables(missing));
+        }
+
+        let mut map = HashMap::new();
+        for resolved in &mut values {
+            let text = match declared(&resolved.name).map(|v| v.var_type) {
+                Some(VariableType::File) => {
+                    let file = read_file_value(&resolved.value).map_err(|source| TemplateError::File {
+                        name: resolved.name.clone(),
+                        path: resolved.value.clone(),
+                        source,
+                    })?;
+                    resolved.file_size = Some(file.size);
+                    resolved.truncated = file.truncated;
+                    file.content
+                }
+                Some(VariableType::Path) if !path_exists(&resolved.value) => {
+                    return Err(TemplateError::PathNotFound {
+                        name: resolved.name.clone(),
+                        path: resolved.value.clone(),
+                    });
+                }
+                _ => resolved.value.clone(),
+            };
+            map.insert(resolved.name.clone(), text);
+        }
+        Ok(Rendered {
+            content: template.render(&map),
+            unresolved: placeholders
+                .iter()
+                .filter(|name| !map.contains_key(**name))
+                .map(|name| name.to_string())
+                .collect(),
+            undeclared,
+            values,
+        })
+    }
+
+    /// First value for `name` from the non-interactive sources
+    fn lookup(&mut self, name: &str, variable: Option<&PromptVariable>) -> Option<(String, Source)> {
+        let explicit = [
+            (self.cli.get(name), Source::Cli),
+            (self.context.get(name), Source::Context),
+            (self.env.get(name), Source::Env),
+            (self.config.and_then(|config| config.get(name)), Source::Config),
+        ]
+        .into_iter()
+        .find_map(|(value, source)| value.map(|value| (value.clone(), source)));
+        // An author's default beats a guess; dynamic values are only
+        // computed when nothing else set one
+        explicit
+            .or_else(|| Some((variable?.default.clone()?, Source::Default)))
+            .or_else(|| {
+                let value = self.dynamic.as_mut()?.get(name)?;
+                Some((value, Source::Dynamic))
+            })
+    }
+}
+
+/// Load key/value context from a JSON or TOML file
+pub fn load_context_file(path: &str) -> Result<HashMap<String, String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read context file: {}", e))?;
+
+    // Try JSON first
+    if path.ends_with(".json") {
+        let map: HashMap<String, serde_json::Value> = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+        return Ok(map.into_iter()
+            .map(|(k, v)| (k, value_to_string(&v)))
+            .collect());
+    }
+
+    // Try TOML
+    if path.ends_with(".toml") {
+        let map: HashMap<String, toml::Value> = toml::from_str(&content)
+            .map_err(|e| format!("Failed to parse TOML: {}", e))?;
+        return Ok(map.into_iter()
+            .map(|(k, v)| (k, toml_value_to_string(&v)))
+            .collect());
+    }
+
+    // Default: try JSON
+    match serde_json::from_str::<HashMap<String, serde_json::Value>>(&content) {
+        Ok(map) => Ok(map.into_iter()
+            .map(|(k, v)| (k, value_to_string(&v)))
+            .collect()),
+        Err(_) => {
+            // Try TOML
+            let map: HashMap<String, toml::Value> = toml::from_str(&content)
+                .map_err(|e| format!("Failed to parse context file: {}", e))?;
+            Ok(map.into_iter()
+                .map(|(k, v)| (k, toml_value_to_string(&v)))
+                .collect())
+        }
+    }
+}
+
+fn value_to_string(v: &serde_json::Value) -> String {
+    match v {
+        serde_json::Value::String(s) => s.clone(),
+        _ => v.to_string(),
+    }
+}
+
+fn toml_value_to_string(v: &toml::Value) -> String {
+    match v {
+        toml::Value::String(s) => s.clone(),
+        _ => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variable(name: &str, required: bool, default: Option<&str>) -> PromptVariable {
+        PromptVariable {
+            name: name.to_string(),
+            var_type: Default::default(),
+            required,
+            description: None,
+            default: default.map(str::to_string),
+        }
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_once_and_render() {
+        let template = Template::parse("a {{X}} b {{ y }} {{X}} {{Z}} {{unclosed");
+        assert_eq!(template.placeholders(), ["X", "Z"]);
+        assert_eq!(
+            template.render(&values(&[("X", "1"), ("Z", "{{X}}")])),
+            "a 1 b {{ y }} 1 {{X}} {{unclosed"
+        );
+        assert_eq!(scan("{{A}}{{ b }}"), ["A", " b "]);
+    }
+
+    #[test]
+    fn test_sources_are_ordered() -> Result<(), TemplateError> {
+        let mut prompt = Prompt::new("p", "P", "{{A}} {{B}} {{C}} {{D}} {{E}} {{F}}");
+        prompt.variables = vec![
+            variable("A", true, Some("default")),
+            variable("B", false, Some("default")),
+            variable("C", false, Some("default")),
+            variable("D", false, Some("default")),
+            variable("E", false, Some("default")),
+        ];
+        let config = BTreeMap::from([("D".to_string(), "config".to_string())]);
+
+        let rendered = Resolver::new(&prompt)
+            .with_cli(BTreeMap::from([("A".to_string(), "cli".to_string())]))
+            .with_context(values(&[("A", "context"), ("B", "context")]))
+            .with_env([
+                ("JFP_VAR_B".to_string(), "env".to_string()),
+                ("JFP_VAR_C".to_string(), "env".to_string()),
+                ("C".to_string(), "unprefixed".to_string()),
+            ])
+            .with_config(&config)
+            .render()?;
+
+        assert_eq!(rendered.content, "cli context env config default {{F}}");
+        let sources: Vec<Source> = rendered.values.iter().map(|v| v.source).collect();
+        assert_eq!(sources, [Source::Cli, Source::Context, Source::Env, Source::Config, Source::Default]);
+        assert_eq!(rendered.unresolved, ["F"]);
+        assert_eq!(rendered.undeclared, ["F"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_interactive_fills_defaults_and_gaps_only() -> Result<(), TemplateError> {
+        let mut prompt = Prompt::new("p", "P", "{{A}} {{B}} {{C}}");
+        prompt.variables = vec![variable("A", false, Some("default")), variable("B", false, None)];
+
+        let mut asked = Vec::new();
+        let rendered = Resolver::new(&prompt)
+            .with_cli(BTreeMap::from([("B".to_string(), "cli".to_string())]))
+            .with_ask(Box::new(|name, _, default| {
+                asked.push((name.to_string(), default.map(str::to_string)));
+                Ok((name == "C").then(|| "typed".to_string()))
+            }))
+            .render()?;
+
+        assert_eq!(rendered.content, "default cli typed");
+        assert_eq!(asked, [("A".to_string(), Some("default".to_string())), ("C".to_string(), None)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_required_variables() {
+        let mut prompt = Prompt::new("p", "P", "{{A}} {{B}}");
+        prompt.variables = vec![variable("A", true, None), variable("B", true, None), variable("C", false, None)];
+        let err = Resolver::new(&prompt)
+            .with_ask(Box::new(|_, _, _| Ok(None)))
+            .render()
+            .unwrap_err();
+        assert_eq!(err.code(), "missing_variables");
+        assert_eq!(err.to_json()["missing"], serde_json::json!(["A", "B"]));
+    }
+
+    #[test]
+    fn test_cli_values() {
+        let vars = ["LANG=rust", "EMPTY=", "EXPR=a=b", "LANG=go"].map(String::from);
+        let values = cli_values(&vars).unwrap();
+        assert_eq!(values["LANG"], "go");
+        assert_eq!(values["EMPTY"], "");
+        assert_eq!(values["EXPR"], "a=b");
+
+        for bad in ["NOEQUALS", "1X=y", "_X=y", "A-B=c", "=x"] {
+            let err = cli_values(&[bad.to_string()]).unwrap_err();
+            assert_eq!(err.code(), "invalid_variable", "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_unknown_cli_variables() {
+        let mut prompt = Prompt::new("p", "P", "{{USED}}");
+        prompt.variables = vec![variable("DECLARED", false, None)];
+        let cli = |name: &str| BTreeMap::from([(name.to_string(), "x".to_string())]);
+
+        assert!(Resolver::new(&prompt).with_cli(cli("USED")).render().is_ok());
+        assert!(Resolver::new(&prompt).with_cli(cli("DECLARED")).render().is_ok());
+        let err = Resolver::new(&prompt).with_cli(cli("OTHER")).render().unwrap_err();
+        assert_eq!(err.to_json()["unknown"], serde_json::json!(["OTHER"]));
+    }
+
+    #[test]
+    fn test_file_and_path_variables() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let notes = dir.path().join("notes.txt");
+        fs::write(&notes, "from file")?;
+        let notes = notes.display().to_string();
+
+        let mut prompt = Prompt::new("p", "P", "{{NOTES}} in {{DIR}}");
+        prompt.variables = vec![variable("NOTES", false, None), variable("DIR", false, None)];
+        prompt.variables[0].var_type = VariableType::File;
+        prompt.variables[1].var_type = VariableType::Path;
+        let cli = |notes: &str, dir: &str| {
+            BTreeMap::from([
+                ("NOTES".to_string(), notes.to_string()),
+                ("DIR".to_string(), dir.to_string()),
+            ])
+        };
+
+        let dir_name = dir.path().display().to_string();
+        let rendered = Resolver::new(&prompt).with_cli(cli(&notes, &dir_name)).render()?;
+        assert_eq!(rendered.content, format!("from file in {}", dir_name));
+        assert_eq!(rendered.values[0].value, notes);
+        assert_eq!(rendered.values[0].file_size, Some(9));
+
+        let missing = format!("{}/missing", dir_name);
+        let err = Resolver::new(&prompt).with_cli(cli(&notes, &missing)).render().unwrap_err();
+        assert_eq!(err.code(), "path_not_found");
+        let err = Resolver::new(&prompt).with_cli(cli(&missing, &dir_name)).render().unwrap_err();
+        assert_eq!(err.code(), "file_error");
+        Ok(())
+    }
+
+    #[test]
+    fn test_dynamic_defaults_rank_below_config_and_prompt_defaults() -> Result<(), TemplateError> {
+        let dir = tempfile::tempdir().unwrap();
+        let mut prompt = Prompt::new("p", "P", "{{PROJECT_NAME}} {{CWD}}");
+        prompt.variables = vec![variable("PROJECT_NAME", false, Some("default"))];
+
+        let rendered = Resolver::new(&prompt)
+            .with_dynamic(DynamicDefaults::new(dir.path()))
+            .render()?;
+        assert_eq!(rendered.content, format!("default {}", dir.path().display()));
+        assert_eq!(rendered.values[0].source, Source::Default);
+        assert_eq!(rendered.values[1].source, Source::Dynamic);
+
+        let config = BTreeMap::from([("CWD".to_string(), "config".to_string())]);
+        let rendered = Resolver::new(&prompt)
+            .with_config(&config)
+            .with_dynamic(DynamicDefaults::new(dir.path()))
+            .render()?;
+        assert_eq!(rendered.content, "default config");
+        assert_eq!(rendered.values[1].source, Source::Config);
+
+        prompt.variables[0].default = None;
+        let rendered = Resolver::new(&prompt)
+            .with_dynamic(DynamicDefaults::new(dir.path()))
+            .render()?;
+        let name = dir.path().file_name().unwrap().to_string_lossy();
+        assert_eq!(rendered.content, format!("{} {}", name, dir.path().display()));
+        assert_eq!(rendered.values[0].source, Source::Dynamic);
+        Ok(())
+    }
+}
@@ -23,6 +23,9 @@ pub struct Bundle {
     /// List of prompt IDs in this bundle
     #[serde(default)]
     pub prompts: Vec<String>,
+    /// User-defined bundle, never overwritten by the registry
+    #[serde(default)]
+    pub is_local: bool,
 }
 
 impl Bundle {
@@ -36,6 +39,7 @@ impl Bundle {
             featured: false,
             author: None,
             prompts: Vec::new(),
+            is_local: false,
         }
     }
 }
@@ -54,6 +58,7 @@ pub struct BundleSummary {
     pub featured: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<String>,
+    pub is_local: bool,
 }
 
 impl From<&Bundle> for BundleSummary {
@@ -66,6 +71,7 @@ impl From<&Bundle> for BundleSummary {
             prompt_count: b.prompt_count,
             featured: b.featured,
             author: b.author.clone(),
+            is_local: b.is_local,
         }
     }
 }
@@ -3,10 +3,16 @@
 //! From EXISTING_JFP_STRUCTURE.md section 3 (Config System)
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Main configuration structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Every section is `#[serde(default)]` so a partial config file only
+/// overrides the keys it sets.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub registry: RegistryConfig,
     pub updates: UpdatesConfig,
@@ -15,23 +21,15 @@ pub struct Config {
     #[serde(rename = "localPrompts")]
     pub local_prompts: LocalPromptsConfig,
     pub analytics: AnalyticsConfig,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            registry: RegistryConfig::default(),
-            updates: UpdatesConfig::default(),
-            skills: SkillsConfig::default(),
-            output: OutputConfig::default(),
-            local_prompts: LocalPromptsConfig::default(),
-            analytics: AnalyticsConfig::default(),
-        }
-    }
+    pub sync: SyncConfig,
+    pub database: DatabaseConfig,
+    /// Default values for prompt template variables, keyed by variable name
+    pub variables: BTreeMap<String, String>,
 }
 
 /// Registry configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RegistryConfig {
     pub url: String,
     pub remote: String,
@@ -67,6 +65,7 @@ impl Default for RegistryConfig {
 
 /// Update checking configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UpdatesConfig {
     #[serde(rename = "autoCheck")]
     pub auto_check: bool,
@@ -93,6 +92,7 @@ impl Default for UpdatesConfig {
 
 /// Skills installation configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SkillsConfig {
     #[serde(rename = "personalDir")]
     pub personal_dir: PathBuf,
@@ -115,6 +115,7 @@ impl Default for SkillsConfig {
 
 /// Output formatting configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OutputConfig {
     pub color: bool,
     pub json: bool,
@@ -131,9 +132,13 @@ impl Default for OutputConfig {
 
 /// Local prompts configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LocalPromptsConfig {
     pub enabled: bool,
     pub dir: PathBuf,
+    /// Extra prompts dir, usually set by a project `.jfp.toml`
+    #[serde(rename = "projectDir", skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<PathBuf>,
 }
 
 impl Default for LocalPromptsConfig {
@@ -142,18 +147,46 @@ impl Default for LocalPromptsConfig {
         Self {
             enabled: true,
             dir: config_dir.join("local"),
+            project_dir: None,
         }
     }
 }
 
 /// Analytics configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AnalyticsConfig {
     pub enabled: bool,
 }
 
-impl Default for AnalyticsConfig {
+/// Cross-process sync configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// How long writers wait for `locks/sync.lock` before failing
+    #[serde(rename = "lockTimeoutMs")]
+    pub lock_timeout_ms: u64,
+}
+
+impl SyncConfig {
+    pub fn lock_timeout(&self) -> Duration {
+        Duration::from_millis(self.lock_timeout_ms)
+    }
+}
+
+impl Default for SyncConfig {
     fn default() -> Self {
-        Self { enabled: false }
+        Self {
+            lock_timeout_ms: 5000,
+        }
     }
 }
+
+/// Local database configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    /// Never write the DB from read commands (`--read-only`)
+    #[serde(rename = "readOnly")]
+    pub read_only: bool,
+}
@@ -3,8 +3,12 @@
 //! These types are derived from EXISTING_JFP_STRUCTURE.md spec.
 //! Do not modify without updating the spec document.
 
+mod bundle;
+mod config;
 mod prompt;
 mod registry;
 
+pub use bundle::*;
+pub use config::*;
 pub use prompt::*;
 pub use registry::*;
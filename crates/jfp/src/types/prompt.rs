@@ -32,7 +32,7 @@ pub enum VariableType {
 }
 
 /// Core prompt structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Prompt {
     pub id: String,
     pub title: String,
@@ -113,6 +113,7 @@ pub struct PromptSummary {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
     pub featured: bool,
+    pub is_local: bool,
 }
 
 impl From<&Prompt> for PromptSummary {
@@ -124,6 +125,7 @@ impl From<&Prompt> for PromptSummary {
             category: p.category.clone(),
             tags: p.tags.clone(),
             featured: p.featured,
+            is_local: p.is_local,
         }
     }
 }
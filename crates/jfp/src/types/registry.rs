@@ -4,7 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use super::Prompt;
+use super::{Bundle, Prompt};
 
 /// Registry metadata for cache management
 ///
@@ -19,6 +19,9 @@ pub struct RegistryMeta {
     pub fetched_at: String,
     #[serde(rename = "promptCount")]
     pub prompt_count: usize,
+    /// Registry URL the data was fetched from; `None` for bundled data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
 }
 
 impl RegistryMeta {
@@ -28,14 +31,17 @@ impl RegistryMeta {
             etag: None,
             fetched_at: chrono::Utc::now().to_rfc3339(),
             prompt_count,
+            url: None,
         }
     }
 }
 
-/// Full registry containing prompts and metadata
+/// Full registry containing prompts, bundles and metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Registry {
     pub prompts: Vec<Prompt>,
+    #[serde(default)]
+    pub bundles: Vec<Bundle>,
     #[serde(flatten)]
     pub meta: RegistryMeta,
 }
@@ -46,10 +52,17 @@ impl Registry {
         let count = prompts.len();
         Self {
             prompts,
+            bundles: Vec::new(),
             meta: RegistryMeta::new(count),
         }
     }
 
+    /// Attach bundles to the registry
+    pub fn with_bundles(mut self, bundles: Vec<Bundle>) -> Self {
+        self.bundles = bundles;
+        self
+    }
+
     /// Find a prompt by ID
     pub fn get(&self, id: &str) -> Option<&Prompt> {
         self.prompts.iter().find(|p| p.id == id)